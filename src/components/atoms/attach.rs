@@ -20,6 +20,8 @@ pub struct AttachEvent {
 pub struct AttachProps {
     #[props(default = AttachType::Button)]
     atype: AttachType,
+    #[props(default = false)]
+    multiple: bool,
     on_click: EventHandler<Event<FormData>>,
 }
 
@@ -54,6 +56,7 @@ pub fn Attach(props: AttachProps) -> Element {
             r#type: "file",
             id: "input_file",
             class: "attach__input",
+            multiple: props.multiple,
             oninput: move |event| props.on_click.call(event)
         }
     )
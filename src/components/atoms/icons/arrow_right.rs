@@ -0,0 +1,16 @@
+use dioxus::prelude::*;
+
+use super::icon::IconShape;
+
+#[derive(PartialEq, Clone)]
+pub struct ArrowRight;
+impl IconShape for ArrowRight {
+    fn view_box(&self) -> String {
+        String::from("0 0 24 24")
+    }
+    fn child_elements(&self) -> Element {
+        rsx!(path {
+            d: "M5 12h14m0 0-6-6m6 6-6 6"
+        })
+    }
+}
@@ -1,5 +1,6 @@
 pub mod arrow_down_circle;
 pub mod arrow_left;
+pub mod arrow_right;
 pub mod arrow_up_circle;
 pub mod attachment;
 pub mod chat_conversation;
@@ -14,15 +15,22 @@ pub mod layers;
 pub mod log_out;
 pub mod menu_hamburguer;
 pub mod new_chat;
+pub mod pause;
+pub mod play;
+pub mod poll;
 pub mod reply;
 pub mod search;
 pub mod send;
+pub mod sticker;
 pub mod trash;
 pub mod user_circle;
 pub mod warning;
+pub mod zoom_in;
+pub mod zoom_out;
 
 pub use arrow_down_circle::ArrowDownCircle;
 pub use arrow_left::ArrowLeft;
+pub use arrow_right::ArrowRight;
 pub use arrow_up_circle::ArrowUpCircle;
 pub use attachment::Attachment;
 pub use chat_conversation::ChatConversation;
@@ -37,9 +45,15 @@ pub use layers::Layers;
 pub use log_out::LogOut;
 pub use menu_hamburguer::MenuHamburger;
 pub use new_chat::NewChat;
+pub use pause::Pause;
+pub use play::Play;
+pub use poll::Poll;
 pub use reply::Reply;
 pub use search::Search;
 pub use send::Send;
+pub use sticker::Sticker;
 pub use trash::Trash;
 pub use user_circle::UserCircle;
 pub use warning::Warning;
+pub use zoom_in::ZoomIn;
+pub use zoom_out::ZoomOut;
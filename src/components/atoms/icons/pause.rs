@@ -0,0 +1,16 @@
+use dioxus::prelude::*;
+
+use super::icon::IconShape;
+
+#[derive(PartialEq, Clone)]
+pub struct Pause;
+impl IconShape for Pause {
+    fn view_box(&self) -> String {
+        String::from("0 0 24 24")
+    }
+    fn child_elements(&self) -> Element {
+        rsx!(path {
+            d: "M8 5v14m8-14v14"
+        })
+    }
+}
@@ -0,0 +1,16 @@
+use dioxus::prelude::*;
+
+use super::icon::IconShape;
+
+#[derive(PartialEq, Clone)]
+pub struct Play;
+impl IconShape for Play {
+    fn view_box(&self) -> String {
+        String::from("0 0 24 24")
+    }
+    fn child_elements(&self) -> Element {
+        rsx!(
+            path { d: "M6 5.58c0-1.05 0-1.58.22-1.87a1 1 0 0 1 .74-.4c.37-.02.81.27 1.7.85l9.26 6.02c.76.5 1.15.75 1.28 1.07a1 1 0 0 1 0 .74c-.13.32-.52.57-1.28 1.07L8.66 19.08c-.89.58-1.33.87-1.7.85a1 1 0 0 1-.74-.4C6 19.24 6 18.71 6 17.66V5.58Z" }
+        )
+    }
+}
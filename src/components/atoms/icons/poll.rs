@@ -0,0 +1,14 @@
+use dioxus::prelude::*;
+
+use super::icon::IconShape;
+
+#[derive(PartialEq, Clone)]
+pub struct Poll;
+impl IconShape for Poll {
+    fn view_box(&self) -> String {
+        String::from("0 0 24 24")
+    }
+    fn child_elements(&self) -> Element {
+        rsx!( path { d: "M6 20V14m6 6V4m6 16v-9" } )
+    }
+}
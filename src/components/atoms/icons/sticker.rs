@@ -0,0 +1,18 @@
+use dioxus::prelude::*;
+
+use super::icon::IconShape;
+
+#[derive(PartialEq, Clone)]
+pub struct Sticker;
+impl IconShape for Sticker {
+    fn view_box(&self) -> String {
+        String::from("0 0 24 24")
+    }
+    fn child_elements(&self) -> Element {
+        rsx!(
+            path { d: "M20 12V6a2 2 0 0 0-2-2H6a2 2 0 0 0-2 2v12a2 2 0 0 0 2 2h6l8-8Z" }
+            path { d: "M12 20v-4a4 4 0 0 1 4-4h4" }
+            path { d: "M9 10h.01M15 10h.01" }
+        )
+    }
+}
@@ -0,0 +1,170 @@
+use dioxus::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlAudioElement;
+
+use crate::{
+    components::atoms::{File, Icon, Pause, Play},
+    services::matrix::matrix::{AudioContent, ImageType},
+    utils::vec_to_url::vec_to_url,
+};
+
+const WAVEFORM_BARS: usize = 40;
+
+#[derive(PartialEq, Props, Clone)]
+pub struct AudioProps {
+    body: AudioContent,
+    is_reply: bool,
+}
+
+pub fn AudioMessage(props: AudioProps) -> Element {
+    let mut element = use_signal::<Option<HtmlAudioElement>>(|| None);
+    let mut playing = use_signal(|| false);
+    let mut elapsed = use_signal(|| 0u64);
+    let mut duration = use_signal(|| props.body.duration);
+
+    // Object urls are created once, otherwise every progress update would
+    // reload the audio
+    let url = use_hook(|| match &props.body.file.source {
+        Some(ImageType::URL(url)) => Some(url.clone()),
+        Some(ImageType::Media(content)) => vec_to_url(content.clone()).ok(),
+        None => None,
+    });
+
+    if props.is_reply {
+        return rsx!(File {
+            body: props.body.file.clone(),
+            is_reply: props.is_reply
+        });
+    }
+
+    let Some(url) = url else {
+        return rsx!( strong { "File Not Found" } );
+    };
+
+    let progress = match duration() {
+        Some(total) if total > 0 => elapsed() as f64 / total as f64,
+        _ => 0.0,
+    };
+
+    let time = match (playing() || elapsed() > 0, duration()) {
+        (true, _) => format_duration(elapsed()),
+        (false, Some(total)) => format_duration(total),
+        (false, None) => String::from("--:--"),
+    };
+
+    let on_toggle = move |_| {
+        let Some(audio) = element() else {
+            return;
+        };
+
+        if playing() {
+            let _ = audio.pause();
+        } else {
+            let _ = audio.play();
+        }
+    };
+
+    let on_seek = move |position: f64| {
+        let (Some(audio), Some(total)) = (element(), duration()) else {
+            return;
+        };
+
+        audio.set_current_time(total as f64 * position / 1000.0);
+    };
+
+    rsx!(
+        section { class: "audio",
+            audio {
+                src: "{url}",
+                preload: "metadata",
+                onmounted: move |event| {
+                    let audio = event
+                        .data
+                        .downcast::<web_sys::Element>()
+                        .and_then(|element| element.clone().dyn_into::<HtmlAudioElement>().ok());
+                    element.set(audio);
+                },
+                onloadedmetadata: move |_| {
+                    if let Some(audio) = element() {
+                        let seconds = audio.duration();
+                        if seconds.is_finite() {
+                            duration.set(Some((seconds * 1000.0) as u64));
+                        }
+                    }
+                },
+                ontimeupdate: move |_| {
+                    if let Some(audio) = element() {
+                        elapsed.set((audio.current_time() * 1000.0) as u64);
+                    }
+                },
+                onplay: move |_| playing.set(true),
+                onpause: move |_| playing.set(false),
+                onended: move |_| {
+                    playing.set(false);
+                    elapsed.set(0);
+                }
+            }
+            button { class: "audio__toggle", onclick: on_toggle,
+                if playing() {
+                    Icon { stroke: "var(--icon-white)", icon: Pause }
+                } else {
+                    Icon { stroke: "var(--icon-white)", fill: "var(--icon-white)", icon: Play }
+                }
+            }
+            div { class: "audio__content",
+                match &props.body.waveform {
+                    Some(waveform) if !waveform.is_empty() => {
+                        let bars = waveform_bars(waveform);
+                        let count = bars.len();
+
+                        rsx!(
+                            div { class: "audio__waveform",
+                                for (i, height) in bars.into_iter().enumerate() {
+                                    span {
+                                        key: "{i}",
+                                        class: if (i as f64) < progress * count as f64 { "audio__bar audio__bar--played" } else { "audio__bar" },
+                                        style: "height: {height}%",
+                                        onclick: move |_| on_seek(i as f64 / count as f64)
+                                    }
+                                }
+                            }
+                        )
+                    }
+                    _ => rsx!(
+                        span { class: "audio__name", "{props.body.file.body}" }
+                        div { class: "audio__progress",
+                            div {
+                                class: "audio__progress-value",
+                                style: "width: {progress * 100.0}%"
+                            }
+                        }
+                    ),
+                }
+            }
+            span { class: "audio__time", "{time}" }
+        }
+    )
+}
+
+/// Averages the waveform down to a fixed number of bars, as percentages
+/// of the loudest one
+fn waveform_bars(waveform: &[u16]) -> Vec<u8> {
+    let chunk = (waveform.len() + WAVEFORM_BARS - 1) / WAVEFORM_BARS;
+    let averages = waveform
+        .chunks(chunk.max(1))
+        .map(|values| values.iter().map(|v| *v as u32).sum::<u32>() / values.len() as u32)
+        .collect::<Vec<_>>();
+
+    let max = averages.iter().copied().max().unwrap_or(0).max(1);
+
+    averages
+        .into_iter()
+        .map(|value| (value * 100 / max).max(8) as u8)
+        .collect()
+}
+
+fn format_duration(millis: u64) -> String {
+    let seconds = millis / 1000;
+
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
@@ -2,10 +2,12 @@ use dioxus::prelude::*;
 
 use crate::{
     components::atoms::{
-        message::ThreadPreview, File, HtmlMessage, ImageMessage, TextMessage, ThreadMessage,
-        VideoMessage,
+        message::ThreadPreview, AudioMessage, BeaconMessage, EmoteMessage, File, HtmlMessage,
+        ImageMessage, LocationMessage, NoticeMessage, PollMessage, StickerMessage, TextMessage,
+        ThreadMessage, UrlPreviewCard, VideoMessage,
     },
     services::matrix::matrix::TimelineMessageType,
+    utils::url_preview::find_urls,
 };
 
 #[derive(PartialEq, Debug, Clone)]
@@ -13,6 +15,10 @@ pub struct Content {
     pub content: TimelineMessageType,
     pub is_reply: bool,
     pub thread: Option<ThreadPreview>,
+    /// Display name of the sender, emotes are written in third person
+    pub sender: String,
+    /// Empty for replied messages, polls answer to it
+    pub event_id: String,
 }
 
 #[derive(PartialEq, Props, Clone)]
@@ -21,6 +27,16 @@ pub struct ContentMessageProps {
 }
 
 pub fn ContentMessage(props: ContentMessageProps) -> Element {
+    // Only the first link gets a preview, replies show none
+    let preview_url = match &props.message.content {
+        TimelineMessageType::Text(body) | TimelineMessageType::Html(body)
+            if !props.message.is_reply =>
+        {
+            find_urls(body).into_iter().next()
+        }
+        _ => None,
+    };
+
     rsx!(
         div {
             class: "message__content",
@@ -60,6 +76,64 @@ pub fn ContentMessage(props: ContentMessageProps) -> Element {
                       }
                     )
                 }
+                TimelineMessageType::Audio(audio) => {
+                    rsx!(
+                      AudioMessage {
+                        body: audio,
+                        is_reply: props.message.is_reply
+                      }
+                    )
+                }
+                TimelineMessageType::Emote(emote) => {
+                    rsx!(
+                      EmoteMessage {
+                        body: emote,
+                        sender: props.message.sender.clone(),
+                        is_reply: props.message.is_reply
+                      }
+                    )
+                }
+                TimelineMessageType::Notice(notice) => {
+                    rsx!(
+                      NoticeMessage {
+                        body: notice,
+                        is_reply: props.message.is_reply
+                      }
+                    )
+                }
+                TimelineMessageType::Poll(poll) => {
+                    rsx!(
+                      PollMessage {
+                        body: poll,
+                        event_id: props.message.event_id.clone(),
+                        is_reply: props.message.is_reply
+                      }
+                    )
+                }
+                TimelineMessageType::Location(location) => {
+                    rsx!(
+                      LocationMessage {
+                        body: location,
+                        is_reply: props.message.is_reply
+                      }
+                    )
+                }
+                TimelineMessageType::Beacon(beacon) => {
+                    rsx!(
+                      BeaconMessage {
+                        body: beacon,
+                        is_reply: props.message.is_reply
+                      }
+                    )
+                }
+                TimelineMessageType::Sticker(sticker) => {
+                    rsx!(
+                      StickerMessage {
+                        body: sticker,
+                        is_reply: props.message.is_reply
+                      }
+                    )
+                }
                 TimelineMessageType::Html(t) => {
                   rsx!(
                     HtmlMessage {
@@ -70,6 +144,13 @@ pub fn ContentMessage(props: ContentMessageProps) -> Element {
                 }
             }
 
+            if let Some(url) = preview_url {
+                UrlPreviewCard {
+                  key: "{url}",
+                  url: url
+                }
+            }
+
             // Thread replies
             if let Some(thread) = &props.message.thread {
               // hover_menu_options.set(vec![MenuOption::ShowThread, MenuOption::Reply]);
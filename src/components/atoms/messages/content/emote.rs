@@ -0,0 +1,23 @@
+use dioxus::prelude::*;
+
+use crate::services::matrix::matrix::FormattedContent;
+
+#[derive(PartialEq, Props, Clone)]
+pub struct EmoteProps {
+    body: FormattedContent,
+    sender: String,
+    is_reply: bool,
+}
+
+pub fn EmoteMessage(props: EmoteProps) -> Element {
+    rsx!(
+        p {
+            class: if props.is_reply { "message__content__emote message-reply__content--text" } else { "message__content__emote" },
+            span { class: "message__content__emote-sender", "* {props.sender} " }
+            match &props.body.formatted {
+                Some(html) => rsx!( span { dangerous_inner_html: "{html}" } ),
+                None => rsx!( span { "{props.body.body}" } ),
+            }
+        }
+    )
+}
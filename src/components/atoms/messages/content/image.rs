@@ -1,8 +1,12 @@
 use dioxus::prelude::*;
 
 use crate::{
+    hooks::{
+        use_lightbox::{timeline_images, use_lightbox},
+        use_messages::use_messages,
+    },
     services::matrix::matrix::{FileContent, ImageType},
-    utils::vec_to_url::vec_to_url,
+    utils::{media::blurhash_to_url, vec_to_url::vec_to_url},
 };
 
 #[derive(PartialEq, Props, Clone)]
@@ -12,20 +16,68 @@ pub struct ImageProps {
 }
 
 pub fn ImageMessage(props: ImageProps) -> Element {
+    let mut loaded = use_signal(|| false);
+    let mut lightbox = use_lightbox();
+    let messages = use_messages();
+
+    let body = props.body.clone();
+    let on_open = move |_| {
+        if !props.is_reply {
+            lightbox.open(timeline_images(&messages.get()), &body);
+        }
+    };
+
     let message__content__image = if props.is_reply {
         "message__content__image--is-replying message-reply__content--media"
     } else {
         "message__content__image--not-replying"
     };
 
+    // Decoding the blurhash is costly, done again only if the media changes
+    let blurhash = props
+        .body
+        .info
+        .as_ref()
+        .and_then(|info| Some((info.blurhash.clone()?, info.width, info.height)));
+    let blurhash_url = use_memo(use_reactive((&blurhash,), |(blurhash,)| {
+        blurhash.and_then(|(blurhash, width, height)| blurhash_to_url(&blurhash, width, height))
+    }));
+
+    // Keeps the image box at its final size, blurred, until it loads
+    let placeholder = match &props.body.info {
+        Some(info) if !props.is_reply && !loaded() => {
+            let background = blurhash_url()
+                .map(|url| format!("background: center / cover url({url});"))
+                .unwrap_or_default();
+
+            format!(
+                "aspect-ratio: {} / {}; {background}",
+                info.width, info.height
+            )
+        }
+        _ => String::new(),
+    };
+
     match props.body.source {
-        Some(ImageType::URL(url)) => rsx!( img { class: "{message__content__image}", src: "{url}" } ),
+        Some(ImageType::URL(url)) => rsx!(img {
+            class: "{message__content__image}",
+            style: "{placeholder}",
+            src: "{url}",
+            onload: move |_| loaded.set(true),
+            onclick: on_open
+        }),
         Some(ImageType::Media(content)) => {
             let url = vec_to_url(content);
 
             match url {
                 Ok(url) => rsx!(
-                    img { class: "{message__content__image}", src: "{url}" }
+                    img {
+                        class: "{message__content__image}",
+                        style: "{placeholder}",
+                        src: "{url}",
+                        onload: move |_| loaded.set(true),
+                        onclick: on_open
+                    }
                     a { href: "{url}" }
                 ),
                 Err(_) => rsx!( strong { "Unable to read file" } ),
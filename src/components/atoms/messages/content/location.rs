@@ -0,0 +1,245 @@
+use std::{
+    f64::consts::PI,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, Local};
+use dioxus::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
+
+use crate::{
+    services::matrix::matrix::{BeaconContent, LocationContent},
+    utils::settings::{get_bool, MAP_TILES_AUTOLOAD},
+};
+
+const TILE_SIZE: f64 = 256.0;
+const MAX_ZOOM: i32 = 17;
+const MIN_ZOOM: i32 = 10;
+/// Widest accuracy circle, in pixels, before zooming out
+const MAX_ACCURACY_RADIUS: f64 = 60.0;
+
+#[derive(PartialEq, Props, Clone)]
+pub struct LocationProps {
+    body: LocationContent,
+    is_reply: bool,
+}
+
+pub fn LocationMessage(props: LocationProps) -> Element {
+    let i18 = use_i18();
+
+    if props.is_reply {
+        let title = props
+            .body
+            .description
+            .clone()
+            .unwrap_or_else(|| translate!(i18, "chat.location.title"));
+
+        return rsx!( p { class: "message-reply__content--text", "📍 {title}" } );
+    }
+
+    rsx!(
+        section { class: "location",
+            StaticMap { location: props.body.clone() }
+            LocationDetails { location: props.body }
+        }
+    )
+}
+
+#[derive(PartialEq, Props, Clone)]
+pub struct BeaconProps {
+    body: BeaconContent,
+    is_reply: bool,
+}
+
+pub fn BeaconMessage(props: BeaconProps) -> Element {
+    let i18 = use_i18();
+
+    let is_live = props.body.is_live();
+    let title = props
+        .body
+        .description
+        .clone()
+        .unwrap_or_else(|| translate!(i18, "chat.location.live"));
+
+    if props.is_reply {
+        return rsx!( p { class: "message-reply__content--text", "📍 {title}" } );
+    }
+
+    let status = if is_live {
+        translate!(i18, "chat.location.sharing")
+    } else {
+        translate!(i18, "chat.location.ended")
+    };
+
+    rsx!(
+        section { class: "location",
+            div { class: "location__header",
+                span { class: if is_live { "location__badge location__badge--live" } else { "location__badge" },
+                    "{status}"
+                }
+                span { class: "location__title", "{title}" }
+            }
+            match (&props.body.location, props.body.updated_at) {
+                (Some(location), Some(updated_at)) => rsx!(
+                    StaticMap { location: location.clone() }
+                    LocationDetails { location: location.clone() }
+                    span { class: "location__updated",
+                        {format!("{} {}", translate!(i18, "chat.location.updated"), format_time(updated_at))}
+                    }
+                ),
+                _ => rsx!(
+                    p { class: "location__waiting", {translate!(i18, "chat.location.waiting")} }
+                ),
+            }
+        }
+    )
+}
+
+#[derive(PartialEq, Props, Clone)]
+struct LocationDetailsProps {
+    location: LocationContent,
+}
+
+fn LocationDetails(props: LocationDetailsProps) -> Element {
+    let i18 = use_i18();
+    let location = props.location;
+
+    rsx!(
+        div { class: "location__details",
+            if let Some(description) = &location.description {
+                span { class: "location__description", "{description}" }
+            }
+            span { class: "location__coordinates",
+                "{location.latitude:.5}, {location.longitude:.5}"
+                if let Some(accuracy) = location.accuracy {
+                    " (± {accuracy.round()} m)"
+                }
+            }
+            a {
+                class: "location__link",
+                href: "{location.openstreetmap_url()}",
+                target: "_blank",
+                rel: "noopener noreferrer",
+                {translate!(i18, "chat.location.open_map")}
+            }
+        }
+    )
+}
+
+#[derive(PartialEq, Props, Clone)]
+struct StaticMapProps {
+    location: LocationContent,
+}
+
+/// A few OpenStreetMap tiles around the location, with its accuracy circle.
+/// The tiles wait for a click unless the user opted in to load them
+fn StaticMap(props: StaticMapProps) -> Element {
+    let i18 = use_i18();
+    let mut show_tiles = use_signal(|| get_bool(MAP_TILES_AUTOLOAD));
+    let location = props.location;
+
+    if !show_tiles() {
+        return rsx!(
+            button {
+                class: "location__map location__map--hidden",
+                onclick: move |_| show_tiles.set(true),
+                span { class: "location__marker" }
+                span { class: "location__show", {translate!(i18, "chat.location.show_map")} }
+            }
+        );
+    }
+
+    let zoom = map_zoom(&location);
+    let (x, y) = tile_position(location.latitude, location.longitude, zoom);
+    let (tile_x, tile_y) = (x.floor() as i64, y.floor() as i64);
+
+    // The center tile of a 3x3 grid holds the location
+    let left = (x - (tile_x - 1) as f64) * TILE_SIZE;
+    let top = (y - (tile_y - 1) as f64) * TILE_SIZE;
+
+    let tiles_per_row = 2_i64.pow(zoom as u32);
+    let tiles = (-1..=1)
+        .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+        .filter(|(_, dy)| (0..tiles_per_row).contains(&(tile_y + dy)))
+        .map(|(dx, dy)| {
+            let column = (tile_x + dx).rem_euclid(tiles_per_row);
+            let url = format!(
+                "https://tile.openstreetmap.org/{zoom}/{column}/{}.png",
+                tile_y + dy
+            );
+
+            (
+                url,
+                (dx + 1) as f64 * TILE_SIZE,
+                (dy + 1) as f64 * TILE_SIZE,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let radius = location
+        .accuracy
+        .map(|accuracy| accuracy / meters_per_pixel(location.latitude, zoom));
+
+    rsx!(
+        a {
+            class: "location__map",
+            href: "{location.openstreetmap_url()}",
+            target: "_blank",
+            rel: "noopener noreferrer",
+            div {
+                class: "location__tiles",
+                style: "left: calc(50% - {left}px); top: calc(50% - {top}px)",
+                for (url , tile_left , tile_top) in tiles {
+                    img {
+                        key: "{url}",
+                        class: "location__tile",
+                        src: "{url}",
+                        alt: "",
+                        loading: "lazy",
+                        style: "left: {tile_left}px; top: {tile_top}px"
+                    }
+                }
+            }
+            if let Some(radius) = radius {
+                span {
+                    class: "location__accuracy",
+                    style: "width: {radius * 2.0}px; height: {radius * 2.0}px"
+                }
+            }
+            span { class: "location__marker" }
+            span { class: "location__attribution", "© OpenStreetMap" }
+        }
+    )
+}
+
+fn format_time(ms: u64) -> String {
+    let datetime = DateTime::<Local>::from(UNIX_EPOCH + Duration::from_millis(ms));
+    datetime.format("%H:%M").to_string()
+}
+
+/// Closest zoom that keeps the accuracy circle within the map
+fn map_zoom(location: &LocationContent) -> i32 {
+    let Some(accuracy) = location.accuracy else {
+        return MAX_ZOOM - 1;
+    };
+
+    (MIN_ZOOM..=MAX_ZOOM)
+        .rev()
+        .find(|zoom| accuracy / meters_per_pixel(location.latitude, *zoom) <= MAX_ACCURACY_RADIUS)
+        .unwrap_or(MIN_ZOOM)
+}
+
+/// Position in tiles of the web mercator projection
+fn tile_position(latitude: f64, longitude: f64, zoom: i32) -> (f64, f64) {
+    let n = 2_f64.powi(zoom);
+    let latitude = latitude.clamp(-85.0511, 85.0511).to_radians();
+
+    let x = (longitude + 180.0) / 360.0 * n;
+    let y = (1.0 - (latitude.tan() + 1.0 / latitude.cos()).ln() / PI) / 2.0 * n;
+
+    (x, y)
+}
+
+fn meters_per_pixel(latitude: f64, zoom: i32) -> f64 {
+    156_543.033_92 * latitude.to_radians().cos() / 2_f64.powi(zoom)
+}
@@ -1,15 +1,29 @@
+pub mod audio;
 pub mod content;
+pub mod emote;
 pub mod file;
 pub mod html;
 pub mod image;
+pub mod location;
+pub mod notice;
+pub mod poll;
+pub mod sticker;
 pub mod text;
 pub mod thread;
+pub mod url_preview;
 pub mod video;
 
+pub use audio::AudioMessage;
 pub use content::ContentMessage;
+pub use emote::EmoteMessage;
 pub use file::File;
 pub use html::HtmlMessage;
 pub use image::ImageMessage;
+pub use location::{BeaconMessage, LocationMessage};
+pub use notice::NoticeMessage;
+pub use poll::PollMessage;
+pub use sticker::StickerMessage;
 pub use text::TextMessage;
 pub use thread::ThreadMessage;
+pub use url_preview::UrlPreviewCard;
 pub use video::VideoMessage;
@@ -0,0 +1,21 @@
+use dioxus::prelude::*;
+
+use crate::services::matrix::matrix::FormattedContent;
+
+#[derive(PartialEq, Props, Clone)]
+pub struct NoticeProps {
+    body: FormattedContent,
+    is_reply: bool,
+}
+
+pub fn NoticeMessage(props: NoticeProps) -> Element {
+    rsx!(
+        div {
+            class: if props.is_reply { "message__content__notice message-reply__content--text" } else { "message__content__notice" },
+            match &props.body.formatted {
+                Some(html) => rsx!( div { dangerous_inner_html: "{html}" } ),
+                None => rsx!( span { "{props.body.body}" } ),
+            }
+        }
+    )
+}
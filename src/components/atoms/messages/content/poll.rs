@@ -0,0 +1,145 @@
+use dioxus::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
+
+use crate::{
+    hooks::{
+        use_client::use_client, use_messages::use_messages, use_notification::use_notification,
+        use_room::use_room, use_session::use_session,
+    },
+    services::matrix::matrix::{end_poll, send_poll_response, PollContent, PollKind},
+};
+
+#[derive(PartialEq, Props, Clone)]
+pub struct PollProps {
+    body: PollContent,
+    event_id: String,
+    is_reply: bool,
+}
+
+pub fn PollMessage(props: PollProps) -> Element {
+    let i18 = use_i18();
+    let client = use_client();
+    let room = use_room();
+    let session = use_session();
+    let mut messages = use_messages();
+    let mut notification = use_notification();
+
+    if props.is_reply {
+        return rsx!(
+            p { class: "message-reply__content--text", "📊 {props.body.question}" }
+        );
+    }
+
+    let poll = props.body.clone();
+    let user_id = session.get().map(|s| s.user_id).unwrap_or_default();
+    let selection = poll.selection_of(&user_id);
+    let tally = poll.tally();
+    let total = poll
+        .votes
+        .values()
+        .filter(|vote| !vote.answers.is_empty())
+        .count();
+
+    let show_results = poll.is_ended() || matches!(poll.kind, PollKind::Disclosed);
+    let winner = tally.iter().copied().max().unwrap_or(0);
+    let can_end = !poll.is_ended() && poll.can_end(&user_id);
+
+    let poll_id = props.event_id.clone();
+    let on_vote = move |answer: String| {
+        let poll_id = poll_id.clone();
+        let user_id = user_id.clone();
+
+        spawn(async move {
+            let answers = vec![answer];
+
+            match send_poll_response(&client.get(), &room.get().id, &poll_id, &answers).await {
+                Ok(_) => messages.update_poll(&poll_id, |poll| {
+                    poll.apply_local_response(&user_id, &answers)
+                }),
+                Err(_) => {
+                    notification.handle_error(&translate!(i18, "chat.poll.errors.vote"));
+                }
+            }
+        });
+    };
+
+    let poll_id = props.event_id.clone();
+    let on_end = move |_| {
+        let poll_id = poll_id.clone();
+
+        spawn(async move {
+            let fallback = translate!(i18, "chat.poll.ended");
+
+            match end_poll(&client.get(), &room.get().id, &poll_id, &fallback).await {
+                Ok(_) => messages.update_poll(&poll_id, |poll| poll.apply_local_end()),
+                Err(_) => {
+                    notification.handle_error(&translate!(i18, "chat.poll.errors.end"));
+                }
+            }
+        });
+    };
+
+    let status = if poll.is_ended() {
+        translate!(i18, "chat.poll.ended")
+    } else if show_results {
+        translate!(i18, "chat.poll.disclosed")
+    } else {
+        translate!(i18, "chat.poll.undisclosed")
+    };
+
+    rsx!(
+        section { class: "poll",
+            h3 { class: "poll__question", "{poll.question}" }
+            ul { class: "poll__answers",
+                for (answer , votes) in poll.answers.clone().into_iter().zip(tally.into_iter()) {
+                    {
+                        let is_selected = selection.contains(&answer.id);
+                        let is_winner = poll.is_ended() && votes > 0 && votes == winner;
+                        let answer_class = match (is_selected, is_winner) {
+                            (true, true) => "poll__answer poll__answer--selected poll__answer--winner",
+                            (true, false) => "poll__answer poll__answer--selected",
+                            (false, true) => "poll__answer poll__answer--winner",
+                            (false, false) => "poll__answer",
+                        };
+                        let percentage = if total > 0 { votes * 100 / total } else { 0 };
+                        let mut on_vote = on_vote.clone();
+                        let id = answer.id.clone();
+
+                        rsx!(
+                            li { key: "{answer.id}",
+                                button {
+                                    class: "{answer_class}",
+                                    disabled: poll.is_ended(),
+                                    onclick: move |_| on_vote(id.clone()),
+                                    div { class: "poll__answer__header",
+                                        span { class: "poll__answer__check" }
+                                        span { class: "poll__answer__text", "{answer.text}" }
+                                        if show_results {
+                                            span { class: "poll__answer__votes", "{votes}" }
+                                        }
+                                    }
+                                    if show_results {
+                                        div { class: "poll__progress",
+                                            div {
+                                                class: "poll__progress-value",
+                                                style: "width: {percentage}%"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        )
+                    }
+                }
+            }
+            div { class: "poll__footer",
+                span { class: "poll__status",
+                    {format!("{status} · {total} {}", translate!(i18, "chat.poll.votes"))}
+                }
+                if can_end {
+                    button { class: "poll__end", onclick: on_end, {translate!(i18, "chat.poll.cta.end")} }
+                }
+            }
+        }
+    )
+}
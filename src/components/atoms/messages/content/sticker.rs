@@ -0,0 +1,38 @@
+use dioxus::prelude::*;
+
+use crate::services::matrix::matrix::{FileContent, ImageType};
+
+#[derive(PartialEq, Props, Clone)]
+pub struct StickerProps {
+    body: FileContent,
+    is_reply: bool,
+}
+
+pub fn StickerMessage(props: StickerProps) -> Element {
+    let Some(ImageType::URL(url)) = &props.body.source else {
+        return rsx!( strong { "File Not Found" } );
+    };
+
+    let sticker_class = if props.is_reply {
+        "message__content__sticker message-reply__content--media"
+    } else {
+        "message__content__sticker"
+    };
+
+    // Stickers keep their ratio within a fixed box
+    let ratio = props
+        .body
+        .info
+        .as_ref()
+        .filter(|info| info.width > 0 && info.height > 0)
+        .map(|info| format!("aspect-ratio: {} / {};", info.width, info.height))
+        .unwrap_or_default();
+
+    rsx!(img {
+        class: "{sticker_class}",
+        style: "{ratio}",
+        src: "{url}",
+        alt: "{props.body.body}",
+        title: "{props.body.body}"
+    })
+}
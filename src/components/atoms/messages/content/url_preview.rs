@@ -0,0 +1,46 @@
+use dioxus::prelude::*;
+
+use crate::hooks::use_url_preview::{use_url_preview, PreviewState};
+
+#[derive(PartialEq, Props, Clone)]
+pub struct UrlPreviewProps {
+    url: String,
+}
+
+pub fn UrlPreviewCard(props: UrlPreviewProps) -> Element {
+    let mut previews = use_url_preview();
+
+    use_hook(|| {
+        let url = props.url.clone();
+        spawn(async move {
+            previews.load(&url).await;
+        })
+    });
+
+    let Some(PreviewState::Loaded(preview)) = previews.get(&props.url) else {
+        return None;
+    };
+
+    rsx!(
+        a {
+            class: "url-preview",
+            href: "{preview.url}",
+            target: "_blank",
+            rel: "noopener noreferrer",
+            if let Some(image) = &preview.image {
+                img { class: "url-preview__image", src: "{image}", alt: "" }
+            }
+            div { class: "url-preview__content",
+                if let Some(site_name) = &preview.site_name {
+                    span { class: "url-preview__site", "{site_name}" }
+                }
+                if let Some(title) = &preview.title {
+                    strong { class: "url-preview__title", "{title}" }
+                }
+                if let Some(description) = &preview.description {
+                    p { class: "url-preview__description", "{description}" }
+                }
+            }
+        }
+    )
+}
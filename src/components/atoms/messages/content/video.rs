@@ -1,7 +1,7 @@
 use crate::{
     components::atoms::File,
     services::matrix::matrix::{FileContent, ImageType},
-    utils::vec_to_url::vec_to_url,
+    utils::{media::blurhash_to_url, vec_to_url::vec_to_url},
 };
 use dioxus::prelude::*;
 
@@ -18,33 +18,56 @@ pub fn VideoMessage(props: VideoProps) -> Element {
         "message__content__video--not-replying"
     };
 
+    // Decoding the blurhash is costly, done again only if the media changes
+    let blurhash = props
+        .body
+        .info
+        .as_ref()
+        .and_then(|info| Some((info.blurhash.clone()?, info.width, info.height)));
+    let blurhash_url = use_memo(use_reactive((&blurhash,), |(blurhash,)| {
+        blurhash.and_then(|(blurhash, width, height)| blurhash_to_url(&blurhash, width, height))
+    }));
+
+    // The blurhash stands in as poster, at the video's aspect ratio
+    let (style, poster) = match &props.body.info {
+        Some(info) => (
+            format!(
+                "aspect-ratio: {} / {}; height: auto;",
+                info.width, info.height
+            ),
+            blurhash_url().unwrap_or_default(),
+        ),
+        None => (String::new(), String::new()),
+    };
+
     if !props.is_reply {
         match props.body.source {
-            Some(ImageType::URL(url)) => rsx!(
-                video {
+            Some(ImageType::URL(url)) => rsx!(video {
+                class: "{message__content__video}",
+                style: "{style}",
+                poster: "{poster}",
+                src: "{url}",
+                controls: true,
+                autoplay: false
+            }),
+
+            Some(ImageType::Media(content)) => match vec_to_url(content.to_vec()) {
+                Ok(url) => rsx!(video {
                     class: "{message__content__video}",
+                    style: "{style}",
+                    poster: "{poster}",
                     src: "{url}",
                     controls: true,
                     autoplay: false
-                }
-            ),
-
-            Some(ImageType::Media(content)) => {
-                match vec_to_url(content.to_vec()) {
-                    Ok(url) => rsx!(
-                        video {
-                            class: "{message__content__video}",
-                            src: "{url}",
-                            controls: true,
-                            autoplay: false
-                        }
-                    ),
-                    Err(_) => rsx!( strong { "Unable to read file" } ),
-                }
-            }
+                }),
+                Err(_) => rsx!( strong { "Unable to read file" } ),
+            },
             None => rsx!( strong { "File Not Found" } ),
         }
     } else {
-        rsx!( File { body: props.body.clone(), is_reply: props.is_reply } )
+        rsx!(File {
+            body: props.body.clone(),
+            is_reply: props.is_reply
+        })
     }
 }
@@ -2,7 +2,7 @@ use dioxus::prelude::*;
 use dioxus_std::i18n::*;
 use dioxus_std::translate;
 
-use crate::components::atoms::{FileDownload, Icon, Layers, Reply};
+use crate::components::atoms::{FileDownload, Icon, Layers, Reply, UserCircle, Warning};
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum MenuOption {
@@ -11,6 +11,11 @@ pub enum MenuOption {
     Close,
     ShowThread,
     CreateThread,
+    Retry,
+    Discard,
+    Ignore,
+    Report,
+    ReportUser,
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -112,7 +117,67 @@ pub fn HoverMenu(props: HoverMenuProps) -> Element {
                                 }
                             )
                         }
-                        MenuOption::Close => {
+                        MenuOption::Ignore => {
+                            rsx!(
+                                li {
+                                    button {
+                                        class: "hover-menu__option",
+                                        onclick: move |_| {
+                                            props.on_click.call(MenuEvent {option: MenuOption::Ignore })
+                                        },
+                                        Icon {
+                                            stroke: "var(--text-1)",
+                                            icon: Warning
+                                        }
+                                        span {
+                                            class: "hover-menu__option__title",
+                                            {translate!(i18, "chat.menu.ignore")}
+                                        }
+                                    }
+                                }
+                            )
+                        }
+                        MenuOption::Report => {
+                            rsx!(
+                                li {
+                                    button {
+                                        class: "hover-menu__option",
+                                        onclick: move |_| {
+                                            props.on_click.call(MenuEvent {option: MenuOption::Report })
+                                        },
+                                        Icon {
+                                            stroke: "var(--text-1)",
+                                            icon: Warning
+                                        }
+                                        span {
+                                            class: "hover-menu__option__title",
+                                            {translate!(i18, "chat.menu.report")}
+                                        }
+                                    }
+                                }
+                            )
+                        }
+                        MenuOption::ReportUser => {
+                            rsx!(
+                                li {
+                                    button {
+                                        class: "hover-menu__option",
+                                        onclick: move |_| {
+                                            props.on_click.call(MenuEvent {option: MenuOption::ReportUser })
+                                        },
+                                        Icon {
+                                            stroke: "var(--text-1)",
+                                            icon: UserCircle
+                                        }
+                                        span {
+                                            class: "hover-menu__option__title",
+                                            {translate!(i18, "chat.menu.report_user")}
+                                        }
+                                    }
+                                }
+                            )
+                        }
+                        MenuOption::Close | MenuOption::Retry | MenuOption::Discard => {
                             rsx!(div{})
                         }
                     }
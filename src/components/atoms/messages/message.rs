@@ -1,6 +1,7 @@
 use std::ops::Deref;
 
 use dioxus::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
 
 use crate::{
     components::atoms::{
@@ -8,6 +9,7 @@ use crate::{
         hover_menu::{MenuEvent, MenuOption},
         Avatar, ContentMessage, HoverMenu,
     },
+    hooks::use_send_queue::QueueState,
     services::matrix::matrix::{EventOrigin, TimelineMessageType, TimelineRelation},
 };
 
@@ -42,15 +44,31 @@ pub struct Message {
 pub struct MessageViewProps {
     pub message: Message,
     pub is_replying: bool,
+    pub status: Option<QueueState>,
+    #[props(default = false)]
+    pub read_only: bool,
     on_event: EventHandler<MenuEvent>,
 }
 
 pub type Messages = Vec<TimelineRelation>;
 
 pub fn MessageView(props: MessageViewProps) -> Element {
-    let hover_menu_options = use_signal::<Vec<MenuOption>>(|| match props.message.thread {
-        Some(_) => vec![MenuOption::ShowThread, MenuOption::Reply],
-        None => vec![MenuOption::CreateThread, MenuOption::Reply],
+    let i18 = use_i18();
+    let hover_menu_options = use_signal::<Vec<MenuOption>>(|| {
+        let mut options = match props.message.thread {
+            Some(_) => vec![MenuOption::ShowThread, MenuOption::Reply],
+            None => vec![MenuOption::CreateThread, MenuOption::Reply],
+        };
+
+        if matches!(props.message.origin, EventOrigin::OTHER) {
+            options.extend([
+                MenuOption::Report,
+                MenuOption::ReportUser,
+                MenuOption::Ignore,
+            ]);
+        }
+
+        options
     });
 
     let message_container = match props.message.origin {
@@ -69,15 +87,28 @@ pub fn MessageView(props: MessageViewProps) -> Element {
         "message-view--replying"
     };
 
+    // Stickers go without the bubble around them
+    let message_wrapper = match props.message.content {
+        TimelineMessageType::Sticker(_) => "message-wrapper message-wrapper--sticker",
+        _ => "message-wrapper",
+    };
+
+    let message_sending = match props.status {
+        Some(QueueState::Sending) => "message-view--sending",
+        _ => "",
+    };
+
     let content = Content {
         content: props.message.content.clone(),
         is_reply: props.is_replying,
         thread: props.message.thread.clone(),
+        sender: props.message.display_name.clone(),
+        event_id: props.message.event_id.clone(),
     };
 
     rsx! {
         div { class: "dropdown {dropdown_left}",
-            div { class: "{message_class} {message_container}",
+            div { class: "{message_class} {message_container} {message_sending}",
                 // Header content (Avatar)
                 match &props.message.origin {
                     EventOrigin::ME => None,
@@ -89,7 +120,7 @@ pub fn MessageView(props: MessageViewProps) -> Element {
                         }
                     )
                 },
-                article { class: "message-wrapper",
+                article { class: "{message_wrapper}",
                     // Name sender content
                     match props.message.origin {
                         EventOrigin::OTHER => rsx!(
@@ -112,10 +143,30 @@ pub fn MessageView(props: MessageViewProps) -> Element {
                         ContentMessage { message: content.clone() }
                         span { class: "message__time", "{props.message.time}" }
                     }
+
+                    if let Some(QueueState::Failed) = props.status {
+                        div { class: "message__failed",
+                            span { {translate!(i18, "chat.queue.failed")} }
+                            button {
+                                class: "message__failed__action",
+                                onclick: move |_| {
+                                    props.on_event.call(MenuEvent { option: MenuOption::Retry });
+                                },
+                                {translate!(i18, "chat.queue.retry")}
+                            }
+                            button {
+                                class: "message__failed__action",
+                                onclick: move |_| {
+                                    props.on_event.call(MenuEvent { option: MenuOption::Discard });
+                                },
+                                {translate!(i18, "chat.queue.discard")}
+                            }
+                        }
+                    }
                 }
             }
 
-            if !props.is_replying {
+            if !props.is_replying && !props.read_only {
                 HoverMenu {
                     options: hover_menu_options.read().deref().to_vec(),
                     on_click: move |event: MenuEvent| {
@@ -55,6 +55,8 @@ pub fn MessageReply(props: MessageReplyProps) -> Element {
                         content: props.message.content.clone(),
                         is_reply: true,
                         thread: None,
+                        sender: props.message.display_name.clone(),
+                        event_id: String::new(),
                     }
                 }
             }
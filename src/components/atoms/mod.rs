@@ -12,6 +12,7 @@ pub mod loading_status;
 pub mod menu_item;
 pub mod messages;
 pub mod notification;
+pub mod public_room;
 pub mod room;
 pub mod room_skeleton;
 pub mod space;
@@ -34,9 +35,10 @@ pub use loading_status::LoadingStatus;
 pub use menu_item::MenuItem;
 pub use messages::*;
 pub use notification::Notification;
+pub use public_room::PublicRoomView;
 pub use room::RoomView;
 pub use room_skeleton::RoomViewSkeleton;
-pub use space::Space;
+pub use space::{Space, Subspace};
 pub use space_skeleton::SpaceSkeleton;
 pub use spinner::Spinner;
 pub use textarea::TextareaInput;
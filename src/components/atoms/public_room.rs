@@ -0,0 +1,52 @@
+use dioxus::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
+
+use crate::{components::atoms::Avatar, services::matrix::matrix::PublicRoomEntry};
+
+#[derive(PartialEq, Props, Clone)]
+pub struct PublicRoomViewProps {
+    entry: PublicRoomEntry,
+    on_click: EventHandler<MouseEvent>,
+}
+
+pub fn PublicRoomView(props: PublicRoomViewProps) -> Element {
+    let i18 = use_i18();
+    let entry = &props.entry;
+
+    rsx! {
+        div {
+            class: "public-room fade-in",
+            onclick: move |event| props.on_click.call(event),
+
+            Avatar {
+                name: entry.room.name.clone(),
+                size: 48,
+                uri: entry.room.avatar_uri.clone()
+            }
+            article { class: "public-room__content",
+                p { class: "public-room__title", "{entry.room.name}" }
+                if let Some(alias) = &entry.alias {
+                    p { class: "public-room__alias", "{alias}" }
+                }
+                if let Some(topic) = &entry.topic {
+                    p { class: "public-room__topic", "{topic}" }
+                }
+                div { class: "public-room__badges",
+                    span { class: "public-room__badge",
+                        "{entry.members} " {translate!(i18, "chat.public.members")}
+                    }
+                    if entry.world_readable {
+                        span { class: "public-room__badge public-room__badge--readable",
+                            {translate!(i18, "chat.public.world_readable")}
+                        }
+                    }
+                    if entry.guest_can_join {
+                        span { class: "public-room__badge",
+                            {translate!(i18, "chat.public.guest_can_join")}
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -10,6 +10,15 @@ pub struct RoomItem {
     pub is_direct: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InvitedRoom {
+    pub room: RoomItem,
+    pub inviter_id: Option<String>,
+    pub inviter_name: Option<String>,
+    pub inviter_avatar: Option<String>,
+    pub reason: Option<String>,
+}
+
 #[derive(PartialEq, Props, Clone)]
 pub struct RoomViewProps {
     displayname: String,
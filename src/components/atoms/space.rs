@@ -1,6 +1,6 @@
 use dioxus::prelude::*;
 
-use crate::components::atoms::{avatar::Variant, Avatar};
+use crate::components::atoms::{avatar::Variant, ArrowDownCircle, ArrowUpCircle, Avatar, Icon};
 
 #[derive(PartialEq, Props, Clone)]
 pub struct SpaceProps {
@@ -24,3 +24,33 @@ pub fn Space(props: SpaceProps) -> Element {
         }
     )
 }
+
+#[derive(PartialEq, Props, Clone)]
+pub struct SubspaceProps {
+    text: String,
+    #[props(!optional)]
+    uri: Option<String>,
+    is_open: bool,
+    on_click: EventHandler<MouseEvent>,
+}
+
+pub fn Subspace(props: SubspaceProps) -> Element {
+    rsx!(
+        button {
+            class: "subspace",
+            onclick: move |event| props.on_click.call(event),
+            Avatar {
+                name: props.text.to_string(),
+                size: 24,
+                uri: props.uri.clone(),
+                variant: Variant::SemiRound
+            }
+            span { class: "subspace__title", "{props.text}" }
+            if props.is_open {
+                Icon { stroke: "var(--text-1)", icon: ArrowUpCircle, height: 20, width: 20 }
+            } else {
+                Icon { stroke: "var(--text-1)", icon: ArrowDownCircle, height: 20, width: 20 }
+            }
+        }
+    )
+}
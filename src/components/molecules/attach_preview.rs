@@ -3,9 +3,12 @@ use dioxus_std::{i18n::use_i18, translate};
 use std::ops::Deref;
 
 use crate::{
-    components::atoms::{button::Variant, header_main::HeaderCallOptions, Button, Card, File},
+    components::atoms::{
+        button::Variant, header_main::HeaderCallOptions, Button, Close, File, Icon,
+    },
     hooks::use_attach::use_attach,
     services::matrix::matrix::FileContent,
+    utils::{media::is_compressible, nice_bytes::nice_bytes},
 };
 
 #[derive(PartialEq, Props, Clone)]
@@ -15,92 +18,112 @@ pub struct AttachPreviewProps {
 
 pub fn AttachPreview(props: AttachPreviewProps) -> Element {
     let i18 = use_i18();
-    let attach = use_attach();
+    let mut attach = use_attach();
 
     let on_handle_card = move |_| props.on_event.call(HeaderCallOptions::CLOSE);
 
-    match attach.get() {
-        Some(file) => match file.content_type.type_() {
-            mime::IMAGE => {
-                rsx!(
-                    article {
-                        class: "attach__wrapper--image",
-                        img {
-                            class: "attach__content--image",
-                            src: "{file.preview_url.deref()}"
-                        }
-                    }
+    let compress = attach.compress_images();
+    let has_images = attach
+        .get_all()
+        .iter()
+        .any(|file| is_compressible(&file.content_type));
 
-                    Card {
-                        file: "{file.preview_url.deref()}",
-                        on_click: on_handle_card
-                    }
-                )
-            }
-            mime::VIDEO => {
-                rsx!(
-                    article {
-                        class: "attach__wrapper--video",
-                        video {
-                            class: "attach__content--video",
-                            src: "{file.preview_url.deref()}",
-                            controls: true,
-                            autoplay: true
-                        }
-                        div {
-                            class: "attach__cta--video",
-                            Button {
-                                text: translate!(i18, "chat.attach_preview.cta.cancel"),
-                                variant: Variant::Secondary,
-                                status: None,
-                                on_click: on_handle_card
+    let on_toggle_compress = move |_| {
+        spawn(async move {
+            attach.set_compress_images(!compress).await;
+        });
+    };
+
+    rsx!(
+        section { class: "attach-list",
+            h2 { class: "attach-list__title", {translate!(i18, "chat.attach_preview.title")} }
+            div { class: "attach-list__items",
+                for (index , file) in attach.get_all().into_iter().enumerate() {
+                    article { key: "{index}-{file.name}", class: "attach-list__item",
+                        div { class: "attach-list__preview",
+                            match file.content_type.type_() {
+                                mime::IMAGE => rsx!(
+                                    img {
+                                        class: "attach-list__media",
+                                        src: "{file.preview_url.deref()}"
+                                    }
+                                ),
+                                mime::VIDEO => rsx!(
+                                    video {
+                                        class: "attach-list__media",
+                                        src: "{file.preview_url.deref()}",
+                                        controls: true,
+                                        autoplay: false
+                                    }
+                                ),
+                                mime::AUDIO => rsx!(
+                                    audio {
+                                        class: "attach-list__audio",
+                                        src: "{file.preview_url.deref()}",
+                                        controls: true
+                                    }
+                                ),
+                                _ => rsx!(
+                                    File {
+                                        body: FileContent {
+                                            size: Some(file.size),
+                                            body: file.name.clone(),
+                                            source: None,
+                                            info: None,
+                                        },
+                                        is_reply: false
+                                    }
+                                ),
                             }
                         }
-                    }
-                )
-            }
-            _ => {
-                rsx!(
-                    article {
-                        class: "attach__wrapper--file",
-                        div {
-                            class: "attach__content--file",
-                            h2 {
-                                class: "attach__title--file",
-                                {translate!(i18, "chat.attach_preview.title")}
-                            }
-                            div {
-                                class: "attach__spacer",
-                                File {
-                                    body: FileContent {
-                                        size: Some(file.size),
-                                        body: file.name,
-                                        source: None,
-                                    },
-                                    is_reply: false
-                                }
-
-                                div {
-                                    class: "attach__spacer",
-                                    Button {
-                                        text: translate!(i18, "chat.attach_preview.cta.cancel"),
-                                        variant: Variant::Secondary,
-                                        status: None,
-                                        on_click: on_handle_card
+                        div { class: "attach-list__details",
+                            span { class: "attach-list__name", "{file.name}" }
+                            match &file.compressed {
+                                Some(compressed) if compress => rsx!(
+                                    span { class: "attach-list__size",
+                                        span { class: "attach-list__size--original", {nice_bytes(file.size as f64)} }
+                                        " → {nice_bytes(compressed.size as f64)}"
                                     }
-                                }
+                                ),
+                                _ => rsx!(
+                                    span { class: "attach-list__size", {nice_bytes(file.size as f64)} }
+                                ),
                             }
+                            input {
+                                class: "attach-list__caption",
+                                r#type: "text",
+                                placeholder: translate!(i18, "chat.attach_preview.caption"),
+                                value: "{file.caption}",
+                                oninput: move |event| attach.set_caption(index, event.value())
+                            }
+                        }
+                        button {
+                            class: "attach-list__remove",
+                            title: translate!(i18, "chat.attach_preview.cta.remove"),
+                            onclick: move |_| attach.remove(index),
+                            Icon { stroke: "var(--icon-subdued)", icon: Close }
                         }
                     }
-                )
+                }
             }
-        },
-        None => {
-            rsx!(
-                div {
-                    {translate!(i18, "chat.attach_preview.not_found")}
+            if has_images {
+                label { class: "attach-list__compress",
+                    input {
+                        r#type: "checkbox",
+                        checked: compress,
+                        onchange: on_toggle_compress
+                    }
+                    {translate!(i18, "chat.attach_preview.compress")}
                 }
-            )
+            }
+            div { class: "attach-list__cta",
+                Button {
+                    text: translate!(i18, "chat.attach_preview.cta.cancel"),
+                    variant: Variant::Secondary,
+                    status: None,
+                    on_click: on_handle_card
+                }
+            }
         }
-    }
+    )
 }
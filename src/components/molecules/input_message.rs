@@ -1,8 +1,10 @@
-use std::ops::Deref;
-
 use dioxus::{html::input_data::keyboard_types, prelude::*};
-use dioxus_std::{i18n::use_i18, translate};
-use futures_util::TryFutureExt;
+use dioxus_std::{
+    i18n::{use_i18, UseI18},
+    translate,
+};
+use gloo::events::EventListener;
+use wasm_bindgen::JsCast;
 
 use crate::{
     components::{
@@ -10,16 +12,21 @@ use crate::{
             header_main::{HeaderCallOptions, HeaderEvent},
             hover_menu::{MenuEvent, MenuOption},
             message::MessageView,
-            Attach, Button, Close, Icon, Message, TextareaInput,
+            Attach, Button, Close, Icon, Message, Poll, Sticker, TextareaInput,
         },
-        molecules::AttachPreview,
+        molecules::{poll_form::PollMessageEvent, AttachPreview, PollForm, StickerPicker, Uploads},
     },
     hooks::{
-        use_attach::{use_attach, AttachError, AttachFile},
+        use_attach::{use_attach, AttachError},
+        use_client::use_client,
         use_notification::use_notification,
         use_reply::use_reply,
+        use_room::use_room,
+        use_send_queue::use_send_queue,
+    },
+    services::matrix::matrix::{
+        media_upload_limit, Attachment, EventOrigin, PackImage, TimelineMessageType,
     },
-    services::matrix::matrix::{Attachment, EventOrigin, TimelineMessageType},
 };
 
 #[derive(Debug, Clone)]
@@ -27,6 +34,11 @@ pub struct FormMessageEvent {
     pub value: String,
 }
 
+pub struct AttachMessageEvent {
+    pub attachment: Attachment,
+    pub caption: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ReplyingTo {
     pub event_id: String,
@@ -41,7 +53,23 @@ pub struct InputMessageProps {
     placeholder: String,
     on_submit: EventHandler<FormMessageEvent>,
     on_event: EventHandler<HeaderEvent>,
-    on_attach: Option<EventHandler<Attachment>>,
+    on_attach: Option<EventHandler<AttachMessageEvent>>,
+    on_poll: Option<EventHandler<PollMessageEvent>>,
+    on_sticker: Option<EventHandler<PackImage>>,
+}
+
+pub fn attach_error_message(i18: &UseI18, error: &AttachError) -> String {
+    match error {
+        AttachError::NotFound => translate!(i18, "chat.input_message.not_found"),
+        AttachError::UncoverType => translate!(i18, "chat.input_message.file_type"),
+        AttachError::UnknownContent => translate!(i18, "chat.input_message.unknown_content"),
+        AttachError::TooLarge(name) => {
+            format!(
+                "{}: {name}",
+                translate!(i18, "chat.input_message.too_large")
+            )
+        }
+    }
 }
 
 pub fn InputMessage(props: InputMessageProps) -> Element {
@@ -50,95 +78,68 @@ pub fn InputMessage(props: InputMessageProps) -> Element {
     let mut notification = use_notification();
     let mut replying_to = use_reply();
 
+    let client = use_client();
+    let room = use_room();
+    let mut send_queue = use_send_queue();
+
     let mut message_field = use_signal(String::new);
-    let mut wrapper_style = use_signal(|| {
-        r#"
-            flex-direction: column;
-        "#
+    let mut creating_poll = use_signal(|| false);
+    let mut picking_sticker = use_signal(|| false);
+
+    // Files over the homeserver limit are turned down before uploading
+    use_coroutine(|_: UnboundedReceiver<()>| async move {
+        if attach.upload_limit().is_none() {
+            attach.set_upload_limit(media_upload_limit(&client.get()).await);
+        }
     });
 
-    let mut on_handle_send_attach = move || {
-        wrapper_style.set(
-            r#"
-                flex-direction: column;
-            "#,
-        );
+    let mut on_attach_errors = move |errors: Vec<AttachError>| {
+        for error in errors {
+            notification.handle_error(&attach_error_message(&i18, &error));
+        }
     };
 
     let on_handle_attach = move |event: Event<FormData>| {
-        spawn({
-            async move {
-                let files = &event.files().ok_or(AttachError::NotFound)?;
-                let fs = files.files();
-
-                let existing_file = fs.get(0).ok_or(AttachError::NotFound)?;
-                let content = files
-                    .read_file(existing_file)
-                    .await
-                    .ok_or(AttachError::NotFound)?;
-                let infered_type = infer::get(content.deref()).ok_or(AttachError::UncoverType)?;
-
-                let content_type: Result<mime::Mime, _> = infered_type.mime_type().parse();
-                let content_type = content_type.map_err(|_| AttachError::UnknownContent)?;
-
-                let blob = match content_type.type_() {
-                    mime::IMAGE => gloo::file::Blob::new(content.deref()),
-                    mime::VIDEO => gloo::file::Blob::new_with_options(
-                        content.deref(),
-                        Some(infered_type.mime_type()),
-                    ),
-                    _ => gloo::file::Blob::new(content.deref()),
-                };
-
-                let size = blob.size().clone();
-                let object_url = gloo::file::ObjectUrl::from(blob);
-
-                attach.set(Some(AttachFile {
-                    name: existing_file.to_string(),
-                    preview_url: object_url,
-                    data: content.clone(),
-                    content_type,
-                    size,
-                }));
-
-                wrapper_style.set(
-                    r#"
-                        flex-direction: column;
-                        position: absolute;
-                        height: calc(100vh - 70px);
-                        background: var(--background);
-                    "#,
-                );
-
-                Ok::<(), AttachError>(())
-            }
-            .unwrap_or_else(move |e: AttachError| {
-                let message_error = match e {
-                    AttachError::NotFound => translate!(i18, "chat.input_message.not_found"),
-                    AttachError::UncoverType => translate!(i18, "chat.input_message.file_type"),
-                    AttachError::UnknownContent => {
-                        translate!(i18, "chat.input_message.unknown_content")
-                    }
-                };
+        spawn(async move {
+            let Some(files) = event.files() else {
+                on_attach_errors(vec![AttachError::NotFound]);
+                return;
+            };
 
-                notification.handle_error(&message_error);
-            })
+            on_attach_errors(attach.push_files(files).await);
         });
     };
 
-    let mut on_handle_file_cta = move |file: AttachFile| {
+    let on_handle_files_cta = move |_| {
         if let Some(l) = props.on_attach {
-            let attachment = Attachment {
-                body: file.name.clone(),
-                data: file.data.clone(),
-                content_type: file.content_type.clone(),
-            };
+            for file in attach.get_all() {
+                let caption = Some(file.caption.trim().to_string()).filter(|c| !c.is_empty());
+
+                l.call(AttachMessageEvent {
+                    attachment: file.attachment(attach.compress_images()),
+                    caption,
+                });
+            }
 
-            l.call(attachment);
-            on_handle_send_attach();
+            attach.reset();
         }
     };
 
+    let wrapper_style = if attach.get_all().is_empty() && !creating_poll() {
+        r#"
+            flex-direction: column;
+        "#
+    } else {
+        r#"
+            flex-direction: column;
+            position: absolute;
+            height: calc(100vh - 70px);
+            background: var(--background);
+        "#
+    };
+
+    let uploads = send_queue.uploads_for(&room.get().id);
+
     let mut on_key_press = move |event: KeyboardEvent| {
         let modifiers = event.modifiers();
 
@@ -160,8 +161,29 @@ pub fn InputMessage(props: InputMessageProps) -> Element {
     rsx! {
       div {
         id: "input_field",
-        style: "{wrapper_style.read()}",
+        style: "{wrapper_style}",
         class: "input__message",
+        onmounted: move |event| {
+            let Some(element) = event.data.downcast::<web_sys::Element>().cloned() else {
+                return;
+            };
+
+            // Images copied elsewhere are attached when pasted
+            EventListener::new(&element, "paste", move |event| {
+                let files = event
+                    .dyn_ref::<web_sys::ClipboardEvent>()
+                    .and_then(|event| event.clipboard_data())
+                    .and_then(|data| data.files())
+                    .filter(|files| files.length() > 0);
+
+                if let (Some(files), Some(_)) = (files, props.on_attach) {
+                    wasm_bindgen_futures::spawn_local(async move {
+                        on_attach_errors(attach.push_clipboard(files).await);
+                    });
+                }
+            })
+            .forget();
+        },
 
         if let Some(replying) = replying_to.get() {
             div {
@@ -201,42 +223,98 @@ pub fn InputMessage(props: InputMessageProps) -> Element {
             }
         }
 
-        if let Some(_) = attach.get() {
+        if !uploads.is_empty() {
+            Uploads {
+                uploads: uploads,
+                on_cancel: move |txn_id: String| send_queue.cancel(&txn_id)
+            }
+        }
+
+        if creating_poll() {
+            PollForm {
+                on_submit: move |event| {
+                    if let Some(l) = props.on_poll {
+                        l.call(event);
+                    }
+                    creating_poll.set(false);
+                },
+                on_cancel: move |_| creating_poll.set(false)
+            }
+        } else if !attach.get_all().is_empty() {
             AttachPreview {
                 on_event: move |_| {
-                    on_handle_send_attach();
                     attach.reset();
                 }
             }
         }
 
-        div {
-            class: "input__message__container",
-            if let Some(_) = &props.on_attach {
-                Attach {
-                    on_click: on_handle_attach
-                }
+        if picking_sticker() && !creating_poll() {
+            StickerPicker {
+                on_select: move |image| {
+                    if let Some(l) = props.on_sticker {
+                        l.call(image);
+                    }
+                    picking_sticker.set(false);
+                },
+                on_close: move |_| picking_sticker.set(false)
             }
+        }
+
+        if !creating_poll() {
+            div {
+                class: "input__message__container",
+                if let Some(_) = &props.on_attach {
+                    Attach {
+                        multiple: true,
+                        on_click: on_handle_attach
+                    }
+                }
 
-            if let Some(file) = attach.get().clone() {
-                Button {
-                    text: translate!(i18, "chat.input_message.cta"),
-                    status: None,
-                    on_click: move |_| {
-                        on_handle_file_cta(file.clone());
+                if props.on_poll.is_some() && attach.get_all().is_empty() {
+                    button {
+                        class: "input__message__poll",
+                        title: translate!(i18, "chat.poll_form.title"),
+                        onclick: move |_| {
+                            picking_sticker.set(false);
+                            creating_poll.set(true);
+                        },
+                        Icon {
+                            stroke: "var(--icon-white)",
+                            icon: Poll
+                        }
                     }
                 }
-            } else {
-                TextareaInput {
-                    value: "{message_field}",
-                    placeholder: props.placeholder,
-                    on_input: move |event: FormEvent| {
-                        message_field.set(event.value());
-                    },
-                    on_keypress: on_key_press,
-                    on_click: move |_| {
-                        props.on_submit.call(FormMessageEvent { value: message_field() });
-                        message_field.set(String::new());
+
+                if props.on_sticker.is_some() && attach.get_all().is_empty() {
+                    button {
+                        class: "input__message__sticker",
+                        title: translate!(i18, "chat.stickers.title"),
+                        onclick: move |_| picking_sticker.toggle(),
+                        Icon {
+                            stroke: "var(--icon-white)",
+                            icon: Sticker
+                        }
+                    }
+                }
+
+                if !attach.get_all().is_empty() {
+                    Button {
+                        text: translate!(i18, "chat.input_message.cta"),
+                        status: None,
+                        on_click: on_handle_files_cta
+                    }
+                } else {
+                    TextareaInput {
+                        value: "{message_field}",
+                        placeholder: props.placeholder,
+                        on_input: move |event: FormEvent| {
+                            message_field.set(event.value());
+                        },
+                        on_keypress: on_key_press,
+                        on_click: move |_| {
+                            props.on_submit.call(FormMessageEvent { value: message_field() });
+                            message_field.set(String::new());
+                        }
                     }
                 }
             }
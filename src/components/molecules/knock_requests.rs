@@ -0,0 +1,64 @@
+use dioxus::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
+
+use crate::{
+    components::atoms::{button::Variant, Avatar, Button},
+    services::matrix::matrix::KnockRequest,
+};
+
+#[derive(PartialEq, Props, Clone)]
+pub struct KnockRequestsProps {
+    requests: Vec<KnockRequest>,
+    #[props(default = false)]
+    disabled: bool,
+    on_approve: EventHandler<String>,
+    on_deny: EventHandler<String>,
+}
+
+pub fn KnockRequests(props: KnockRequestsProps) -> Element {
+    let i18 = use_i18();
+
+    rsx!(
+        section { class: "knock-requests",
+            p { class: "knock-requests__title",
+                {translate!(i18, "chat.knock.requests.title")}
+            }
+            for request in props.requests.into_iter() {
+                div { key: "{request.member.id}", class: "knock-requests__item",
+                    Avatar {
+                        name: request.member.name.clone(),
+                        size: 32,
+                        uri: request.member.avatar_uri.clone()
+                    }
+                    div { class: "knock-requests__content",
+                        span { class: "knock-requests__name", "{request.member.name}" }
+                        if let Some(reason) = &request.reason {
+                            span { class: "knock-requests__reason", "{reason}" }
+                        }
+                    }
+                    div { class: "knock-requests__actions",
+                        Button {
+                            text: translate!(i18, "chat.knock.requests.approve"),
+                            status: None,
+                            disabled: props.disabled,
+                            on_click: {
+                                let id = request.member.id.clone();
+                                move |_| { props.on_approve.call(id.clone()) }
+                            }
+                        }
+                        Button {
+                            text: translate!(i18, "chat.knock.requests.deny"),
+                            variant: Variant::Tertiary,
+                            status: None,
+                            disabled: props.disabled,
+                            on_click: {
+                                let id = request.member.id.clone();
+                                move |_| { props.on_deny.call(id.clone()) }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    )
+}
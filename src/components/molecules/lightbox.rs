@@ -0,0 +1,163 @@
+use dioxus::{html::input_data::keyboard_types::Key, prelude::*};
+use dioxus_std::{i18n::use_i18, translate};
+use wasm_bindgen::JsCast;
+
+use crate::{
+    components::atoms::{ArrowLeft, ArrowRight, Close, FileDownload, Icon, ZoomIn, ZoomOut},
+    hooks::use_lightbox::use_lightbox,
+    services::matrix::matrix::ImageType,
+    utils::vec_to_url::vec_to_url,
+};
+
+const MIN_ZOOM: f64 = 1.0;
+const MAX_ZOOM: f64 = 4.0;
+const ZOOM_STEP: f64 = 0.5;
+
+pub fn Lightbox() -> Element {
+    let i18 = use_i18();
+    let mut lightbox = use_lightbox();
+    let mut zoom = use_signal(|| MIN_ZOOM);
+
+    // Object urls of decrypted images are created once per image shown
+    let url = use_memo(move || {
+        let image = lightbox
+            .get()
+            .and_then(|l| l.images.get(l.index).cloned())?;
+
+        match image.source? {
+            ImageType::URL(url) => Some(url),
+            ImageType::Media(content) => vec_to_url(content).ok(),
+        }
+    });
+
+    // Every image starts unzoomed
+    use_effect(move || {
+        let _ = lightbox.get().map(|l| l.index);
+        zoom.set(MIN_ZOOM);
+    });
+
+    let Some(current) = lightbox.get() else {
+        return None;
+    };
+
+    let Some(image) = current.images.get(current.index).cloned() else {
+        return None;
+    };
+
+    let has_previous = current.index > 0;
+    let has_next = current.index + 1 < current.images.len();
+
+    let mut zoom_by = move |step: f64| {
+        zoom.set((zoom() + step).clamp(MIN_ZOOM, MAX_ZOOM));
+    };
+
+    let on_keydown = move |event: KeyboardEvent| match event.key() {
+        Key::ArrowLeft => lightbox.previous(),
+        Key::ArrowRight => lightbox.next(),
+        Key::Escape => lightbox.close(),
+        Key::Character(c) if c == "+" => zoom_by(ZOOM_STEP),
+        Key::Character(c) if c == "-" => zoom_by(-ZOOM_STEP),
+        _ => {}
+    };
+
+    let image_class = if zoom() > MIN_ZOOM {
+        "lightbox__image lightbox__image--zoomed"
+    } else {
+        "lightbox__image"
+    };
+
+    rsx!(
+        section {
+            class: "lightbox",
+            tabindex: 0,
+            onkeydown: on_keydown,
+            onmounted: move |event| {
+                if let Some(element) = event
+                    .data
+                    .downcast::<web_sys::Element>()
+                    .and_then(|element| element.clone().dyn_into::<web_sys::HtmlElement>().ok())
+                {
+                    let _ = element.focus();
+                }
+            },
+            div { class: "lightbox__header",
+                span { class: "lightbox__name", "{image.body}" }
+                span { class: "lightbox__counter",
+                    "{current.index + 1} / {current.images.len()}"
+                }
+                div { class: "lightbox__actions",
+                    button {
+                        class: "lightbox__cta",
+                        title: translate!(i18, "chat.lightbox.zoom_out"),
+                        disabled: zoom() <= MIN_ZOOM,
+                        onclick: move |_| zoom_by(-ZOOM_STEP),
+                        Icon { stroke: "var(--icon-white)", icon: ZoomOut }
+                    }
+                    button {
+                        class: "lightbox__cta",
+                        title: translate!(i18, "chat.lightbox.zoom_in"),
+                        disabled: zoom() >= MAX_ZOOM,
+                        onclick: move |_| zoom_by(ZOOM_STEP),
+                        Icon { stroke: "var(--icon-white)", icon: ZoomIn }
+                    }
+                    if let Some(url) = url() {
+                        a {
+                            class: "lightbox__cta",
+                            title: translate!(i18, "chat.lightbox.download"),
+                            href: "{url}",
+                            download: "{image.body}",
+                            target: "_blank",
+                            Icon { stroke: "var(--icon-white)", icon: FileDownload }
+                        }
+                    }
+                    button {
+                        class: "lightbox__cta",
+                        title: translate!(i18, "chat.lightbox.close"),
+                        onclick: move |_| lightbox.close(),
+                        Icon { stroke: "var(--icon-white)", icon: Close }
+                    }
+                }
+            }
+            div { class: "lightbox__content",
+                if has_previous {
+                    button {
+                        class: "lightbox__nav lightbox__nav--previous",
+                        title: translate!(i18, "chat.lightbox.previous"),
+                        onclick: move |_| lightbox.previous(),
+                        Icon { stroke: "var(--icon-white)", icon: ArrowLeft }
+                    }
+                }
+                div { class: "lightbox__viewport",
+                    match url() {
+                        Some(url) => rsx!(
+                            img {
+                                class: "{image_class}",
+                                style: "transform: scale({zoom()})",
+                                src: "{url}",
+                                alt: "{image.body}",
+                                onclick: move |_| {
+                                    if zoom() > MIN_ZOOM {
+                                        zoom.set(MIN_ZOOM)
+                                    } else {
+                                        zoom.set(MIN_ZOOM + 2.0 * ZOOM_STEP)
+                                    }
+                                }
+                            }
+                        ),
+                        None => rsx!(
+                            strong { class: "lightbox__error", {translate!(i18, "chat.lightbox.not_found")} }
+                        ),
+                    }
+                }
+                if has_next {
+                    button {
+                        class: "lightbox__nav lightbox__nav--next",
+                        title: translate!(i18, "chat.lightbox.next"),
+                        onclick: move |_| lightbox.next(),
+                        Icon { stroke: "var(--icon-white)", icon: ArrowRight }
+                    }
+                }
+            }
+        }
+    )
+}
@@ -18,9 +18,15 @@ use crate::components::{
     },
     molecules::input_message::ReplyingTo,
 };
+use crate::hooks::use_ignored_users::use_ignored_users;
+use crate::hooks::use_notification::use_notification;
 use crate::hooks::use_reply::use_reply;
+use crate::hooks::use_report::use_report;
+use crate::hooks::use_room::use_room;
+use crate::hooks::use_send_queue::use_send_queue;
 use crate::hooks::use_thread::use_thread;
 use crate::services::matrix::matrix::EventOrigin;
+use crate::services::matrix::matrix::ReportTarget;
 use crate::services::matrix::matrix::TimelineMessage;
 use crate::services::matrix::matrix::TimelineRelation;
 use crate::services::matrix::matrix::TimelineThread;
@@ -36,12 +42,36 @@ pub struct ListProps {
     on_scroll: EventHandler<ListEvent>,
     #[props(default = false)]
     show_load_button: bool,
+    #[props(default = false)]
+    read_only: bool,
 }
 
 pub fn List(props: ListProps) -> Element {
     let i18 = use_i18();
     let mut replying_to = use_reply();
     let mut threading_to = use_thread();
+    let mut send_queue = use_send_queue();
+    let mut ignored = use_ignored_users();
+    let mut notification = use_notification();
+    let room = use_room();
+    let mut report = use_report();
+
+    let mut on_report = move |event_id: String| {
+        report.set(ReportTarget::Event {
+            room_id: room.get().id,
+            event_id,
+        })
+    };
+
+    let mut on_report_user = move |user_id: String| report.set(ReportTarget::User { user_id });
+
+    let mut on_ignore = move |user_id: String| {
+        spawn(async move {
+            if ignored.ignore(&user_id).await.is_err() {
+                notification.handle_error(&translate!(i18, "chat.ignore.errors.ignore"));
+            }
+        });
+    };
 
     let mut container_to_scroll = use_signal::<Option<Box<HtmlElement>>>(|| None);
     let mut list_to_scroll = use_signal::<Option<Box<HtmlElement>>>(|| None);
@@ -124,6 +154,8 @@ pub fn List(props: ListProps) -> Element {
                                             thread: None
                                         },
                                         is_replying: false,
+                                        status: send_queue.state_of(&event_id),
+                                        read_only: props.read_only,
                                         on_event: move |event: MenuEvent| {
                                             match event.option {
                                                 MenuOption::Download => {
@@ -140,6 +172,11 @@ pub fn List(props: ListProps) -> Element {
 
                                                     replying_to.set(Some(replying));
                                                 }
+                                                MenuOption::Retry => send_queue.retry(&event_id),
+                                                MenuOption::Discard => send_queue.discard(&event_id),
+                                                MenuOption::Report => on_report(event_id.clone()),
+                                                MenuOption::ReportUser => on_report_user(message.sender.id.clone()),
+                                                MenuOption::Ignore => on_ignore(message.sender.id.clone()),
                                                 MenuOption::Close => {
                                                     info!("close");
                                                 }
@@ -184,6 +221,8 @@ pub fn List(props: ListProps) -> Element {
                                             thread: None
                                         },
                                         is_replying: false,
+                                        status: send_queue.state_of(&event_id),
+                                        read_only: props.read_only,
                                         on_event: move |event: MenuEvent| {
                                             info!("menu option list: {:?}", event.option);
 
@@ -202,6 +241,11 @@ pub fn List(props: ListProps) -> Element {
 
                                                     replying_to.set(Some(replying));
                                                 }
+                                                MenuOption::Retry => send_queue.retry(&event_id),
+                                                MenuOption::Discard => send_queue.discard(&event_id),
+                                                MenuOption::Report => on_report(event_id.clone()),
+                                                MenuOption::ReportUser => on_report_user(message.sender.id.clone()),
+                                                MenuOption::Ignore => on_ignore(message.sender.id.clone()),
                                                 MenuOption::Close => {
                                                     info!("close");
                                                 }
@@ -260,6 +304,8 @@ pub fn List(props: ListProps) -> Element {
                                             thread: Some(ThreadPreview{meta_senders: thread_avatars, count: (thread.len() - 1) as i8 })
                                         },
                                         is_replying: false,
+                                        status: send_queue.state_of(&event_id),
+                                        read_only: props.read_only,
                                         on_event: move |event: MenuEvent| {
                                             match event.option {
                                                 MenuOption::Download => {
@@ -276,6 +322,11 @@ pub fn List(props: ListProps) -> Element {
 
                                                     replying_to.set(Some(replying));
                                                 }
+                                                MenuOption::Retry => send_queue.retry(&event_id),
+                                                MenuOption::Discard => send_queue.discard(&event_id),
+                                                MenuOption::Report => on_report(event_id.clone()),
+                                                MenuOption::ReportUser => on_report_user(head_message.sender.id.clone()),
+                                                MenuOption::Ignore => on_ignore(head_message.sender.id.clone()),
                                                 MenuOption::Close => {
                                                     info!("close");
                                                 }
@@ -337,6 +388,8 @@ pub fn List(props: ListProps) -> Element {
                                         thread: None
                                     },
                                     is_replying: false,
+                                    status: send_queue.state_of(&event_id),
+                                    read_only: props.read_only,
                                     on_event: move |event: MenuEvent| {
                                         info!("menu option list: {:?}", event.option);
 
@@ -355,6 +408,11 @@ pub fn List(props: ListProps) -> Element {
 
                                                 replying_to.set(Some(replying));
                                             }
+                                            MenuOption::Retry => send_queue.retry(&event_id),
+                                            MenuOption::Discard => send_queue.discard(&event_id),
+                                            MenuOption::Report => on_report(event_id.clone()),
+                                            MenuOption::ReportUser => on_report_user(message.sender.id.clone()),
+                                            MenuOption::Ignore => on_ignore(message.sender.id.clone()),
                                             MenuOption::Close => {
                                                 info!("close");
                                             }
@@ -44,7 +44,18 @@ pub fn Menu(props: MenuProps) -> Element {
         div { class: "menu fade-in-left",
             div { class: "menu__content",
                 ul {
-                    if !session.is_guest() {
+                    if session.is_guest() {
+                        li {
+                            MenuItem {
+                                title: translate!(i18, "menu.upgrade"),
+                                icon: rsx!(Icon { height : 24, width : 24, stroke : "var(--text-1)", icon : UserCircle }),
+                                on_click: move |event| {
+                                    props.on_click.call(event);
+                                    nav.push(Route::Upgrade {});
+                                }
+                            }
+                        }
+                    } else {
                         li {
                             MenuItem {
                                 title: translate!(i18, "menu.profile"),
@@ -1,15 +1,27 @@
 pub mod attach_preview;
 pub mod guest;
 pub mod input_message;
+pub mod knock_requests;
+pub mod lightbox;
 pub mod list;
 pub mod menu;
 pub mod modal;
+pub mod poll_form;
 pub mod rooms;
+pub mod setting_toggle;
+pub mod sticker_picker;
+pub mod uploads;
 
 pub use attach_preview::AttachPreview;
 pub use guest::Guest;
 pub use input_message::InputMessage;
+pub use knock_requests::KnockRequests;
+pub use lightbox::Lightbox;
 pub use list::List;
 pub use menu::Menu;
-pub use modal::Modal;
-pub use rooms::RoomsList;
+pub use modal::{Modal, ReportModal};
+pub use poll_form::PollForm;
+pub use rooms::{RoomsList, SpaceTree};
+pub use setting_toggle::SettingToggle;
+pub use sticker_picker::StickerPicker;
+pub use uploads::Uploads;
@@ -1,5 +1,8 @@
 use crate::{
-    components::atoms::{Avatar, ChatConversation, Close, Group, Icon, NewChat},
+    components::atoms::{
+        button::Variant, Avatar, Button, ChatConversation, Close, Group, Icon, Layers,
+        MessageInput, NewChat,
+    },
     hooks::use_modal::use_modal,
 };
 use dioxus::prelude::*;
@@ -18,6 +21,7 @@ pub enum RoomType {
     CHAT,
     GROUP,
     CHANNEL,
+    SPACE,
 }
 
 pub fn Modal(props: ModalProps) -> Element {
@@ -110,6 +114,152 @@ pub fn Modal(props: ModalProps) -> Element {
                             {translate!(i18, "modal.options.channel")}
                         }
                     }
+                    button {
+                        class: "modal__cta__wrapper",
+                        onclick: move |_| {
+                            props.on_click.call(ModalForm { value: RoomType::SPACE })
+                        },
+                        Icon {
+                            stroke: "var(--text-1)",
+                            icon: Layers
+                        }
+                        span {
+                            class: "modal__cta__title",
+                            {translate!(i18, "modal.options.space")}
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum ReportSeverity {
+    Spam,
+    Offensive,
+    Illegal,
+}
+
+impl ReportSeverity {
+    /// Score of the report, -100 being the most offensive
+    pub fn score(&self) -> i32 {
+        match self {
+            ReportSeverity::Spam => -30,
+            ReportSeverity::Offensive => -70,
+            ReportSeverity::Illegal => -100,
+        }
+    }
+}
+
+pub struct ReportForm {
+    pub reason: String,
+    pub severity: ReportSeverity,
+}
+
+#[derive(PartialEq, Props, Clone)]
+pub struct ReportModalProps {
+    title: String,
+    on_confirm: EventHandler<ReportForm>,
+    on_close: EventHandler<MouseEvent>,
+}
+
+pub fn ReportModal(props: ReportModalProps) -> Element {
+    let i18 = use_i18();
+
+    let mut reason = use_signal::<String>(|| String::new());
+    let mut severity = use_signal::<ReportSeverity>(|| ReportSeverity::Spam);
+
+    let severities = [
+        (
+            ReportSeverity::Spam,
+            translate!(i18, "modal.report.severity.spam"),
+        ),
+        (
+            ReportSeverity::Offensive,
+            translate!(i18, "modal.report.severity.offensive"),
+        ),
+        (
+            ReportSeverity::Illegal,
+            translate!(i18, "modal.report.severity.illegal"),
+        ),
+    ];
+
+    rsx! {
+        section {
+            class: "modal",
+            div {
+                class: "modal__cta--hide",
+                onclick: move |event| {
+                    props.on_close.call(event)
+                },
+            }
+            div {
+                class: "modal__wrapper fade-in-below",
+                article {
+                    class: "modal__title",
+                    div {
+                        p {
+                            class: "modal__user__title",
+                            "{props.title}"
+                        }
+                        p {
+                            class: "modal__user__subtitle",
+                            {translate!(i18, "modal.report.subtitle")}
+                        }
+                    }
+                    button {
+                        class: "modal__cta--close",
+                        onclick: move |event| {props.on_close.call(event)},
+                        Icon {
+                            stroke: "var(--icon-subdued)",
+                            icon: Close
+                        }
+                    }
+                }
+                article {
+                    class: "modal__report",
+                    div {
+                        class: "modal__report__severity",
+                        for (value, label) in severities {
+                            Button {
+                                text: label,
+                                variant: if severity() == value { Variant::Primary } else { Variant::Secondary },
+                                status: None,
+                                on_click: move |_| { severity.set(value) }
+                            }
+                        }
+                    }
+                    MessageInput {
+                        message: "{reason}",
+                        placeholder: translate!(i18, "modal.report.reason.placeholder"),
+                        label: translate!(i18, "modal.report.reason.label"),
+                        error: None,
+                        on_input: move |event: FormEvent| {
+                            reason.set(event.value());
+                        },
+                        on_keypress: move |_| {},
+                        on_click: move |_| {}
+                    }
+                    div {
+                        class: "modal__report__cta",
+                        Button {
+                            text: translate!(i18, "modal.report.cta.cancel"),
+                            variant: Variant::Secondary,
+                            status: None,
+                            on_click: move |event| { props.on_close.call(event) }
+                        }
+                        Button {
+                            text: translate!(i18, "modal.report.cta.confirm"),
+                            status: None,
+                            on_click: move |_| {
+                                props.on_confirm.call(ReportForm {
+                                    reason: reason().trim().to_string(),
+                                    severity: severity(),
+                                })
+                            }
+                        }
+                    }
                 }
             }
         }
@@ -0,0 +1,121 @@
+use dioxus::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
+
+use crate::{
+    components::atoms::{button::Variant, Button, Close, Icon},
+    services::matrix::matrix::PollKind,
+};
+
+const MIN_ANSWERS: usize = 2;
+const MAX_ANSWERS: usize = 20;
+
+#[derive(Debug, Clone)]
+pub struct PollMessageEvent {
+    pub question: String,
+    pub answers: Vec<String>,
+    pub kind: PollKind,
+}
+
+#[derive(PartialEq, Props, Clone)]
+pub struct PollFormProps {
+    on_submit: EventHandler<PollMessageEvent>,
+    on_cancel: EventHandler<()>,
+}
+
+pub fn PollForm(props: PollFormProps) -> Element {
+    let i18 = use_i18();
+
+    let mut question = use_signal(String::new);
+    let mut answers = use_signal(|| vec![String::new(); MIN_ANSWERS]);
+    let mut undisclosed = use_signal(|| false);
+
+    let filled_answers = answers
+        .read()
+        .iter()
+        .map(|answer| answer.trim().to_string())
+        .filter(|answer| !answer.is_empty())
+        .collect::<Vec<_>>();
+
+    let is_valid = !question().trim().is_empty() && filled_answers.len() >= MIN_ANSWERS;
+
+    let on_handle_submit = move |_| {
+        props.on_submit.call(PollMessageEvent {
+            question: question().trim().to_string(),
+            answers: filled_answers.clone(),
+            kind: if undisclosed() {
+                PollKind::Undisclosed
+            } else {
+                PollKind::Disclosed
+            },
+        });
+    };
+
+    rsx!(
+        section { class: "poll-form",
+            h2 { class: "poll-form__title", {translate!(i18, "chat.poll_form.title")} }
+            input {
+                class: "poll-form__input",
+                r#type: "text",
+                placeholder: translate!(i18, "chat.poll_form.question"),
+                value: "{question}",
+                oninput: move |event| question.set(event.value())
+            }
+            div { class: "poll-form__answers",
+                for (index , answer) in answers().into_iter().enumerate() {
+                    div { key: "{index}", class: "poll-form__answer",
+                        input {
+                            class: "poll-form__input",
+                            r#type: "text",
+                            placeholder: format!("{} {}", translate!(i18, "chat.poll_form.answer"), index + 1),
+                            value: "{answer}",
+                            oninput: move |event| {
+                                answers.with_mut(|answers| answers[index] = event.value());
+                            }
+                        }
+                        if answers.read().len() > MIN_ANSWERS {
+                            button {
+                                class: "poll-form__remove",
+                                title: translate!(i18, "chat.poll_form.cta.remove"),
+                                onclick: move |_| {
+                                    answers.with_mut(|answers| {
+                                        answers.remove(index);
+                                    });
+                                },
+                                Icon { stroke: "var(--icon-subdued)", icon: Close }
+                            }
+                        }
+                    }
+                }
+            }
+            if answers.read().len() < MAX_ANSWERS {
+                button {
+                    class: "poll-form__add",
+                    onclick: move |_| answers.with_mut(|answers| answers.push(String::new())),
+                    {translate!(i18, "chat.poll_form.cta.add")}
+                }
+            }
+            label { class: "poll-form__kind",
+                input {
+                    r#type: "checkbox",
+                    checked: undisclosed(),
+                    onchange: move |_| undisclosed.toggle()
+                }
+                {translate!(i18, "chat.poll_form.undisclosed")}
+            }
+            div { class: "poll-form__cta",
+                Button {
+                    text: translate!(i18, "chat.poll_form.cta.cancel"),
+                    variant: Variant::Secondary,
+                    status: None,
+                    on_click: move |_| props.on_cancel.call(())
+                }
+                Button {
+                    text: translate!(i18, "chat.poll_form.cta.create"),
+                    disabled: !is_valid,
+                    status: None,
+                    on_click: on_handle_submit
+                }
+            }
+        }
+    )
+}
@@ -1,6 +1,11 @@
 use dioxus::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
+use std::collections::HashMap;
 
-use crate::components::atoms::{room::RoomItem, RoomView, RoomViewSkeleton};
+use crate::{
+    components::atoms::{room::RoomItem, RoomView, RoomViewSkeleton, Subspace},
+    services::matrix::matrix::SpaceHierarchy,
+};
 
 #[derive(Clone, Debug, PartialEq, Hash, Eq, Default)]
 pub struct CurrentRoom {
@@ -20,6 +25,8 @@ pub struct RoomsListProps {
     is_loading: bool,
     #[props(default = false)]
     wrap: bool,
+    #[props(default = HashMap::new())]
+    previews: HashMap<String, String>,
     on_submit: EventHandler<FormRoomEvent>,
 }
 
@@ -38,7 +45,7 @@ pub fn RoomsList(props: RoomsListProps) -> Element {
                     RoomView {
                         displayname: room.name.as_str(),
                         avatar_uri: room.avatar_uri.clone(),
-                        description: "",
+                        description: props.previews.get(&room.id).cloned().unwrap_or_default(),
                         wrap: props.wrap,
                         on_click: move |_| {
                             props
@@ -65,3 +72,100 @@ pub fn RoomsList(props: RoomsListProps) -> Element {
         }
     }
 }
+
+#[derive(PartialEq, Props, Clone)]
+pub struct SpaceTreeProps {
+    hierarchy: SpaceHierarchy,
+    #[props(default = false)]
+    is_nested: bool,
+    on_submit: EventHandler<FormRoomEvent>,
+    on_join: EventHandler<FormRoomEvent>,
+}
+
+pub fn SpaceTree(props: SpaceTreeProps) -> Element {
+    let i18 = use_i18();
+    let mut open_subspaces = use_signal::<Vec<String>>(|| vec![]);
+
+    let space_tree_nested = if props.is_nested {
+        "space-tree--nested"
+    } else {
+        ""
+    };
+
+    rsx! {
+        section { class: "space-tree {space_tree_nested} fade-in",
+            for child in props.hierarchy.rooms.clone() {
+                RoomView {
+                    key: "{child.room.id}",
+                    displayname: child.room.name.as_str(),
+                    avatar_uri: child.room.avatar_uri.clone(),
+                    description: {
+                        let mut description = vec![];
+
+                        if child.suggested {
+                            description.push(translate!(i18, "chat.list.space.suggested"));
+                        }
+
+                        if !child.is_joined {
+                            description.push(format!("{} {}", child.members, translate!(i18, "chat.list.space.members")));
+                            description.push(translate!(i18, "chat.list.space.join"));
+                        } else if let Some(topic) = &child.topic {
+                            description.push(topic.clone());
+                        }
+
+                        description.join(" · ")
+                    },
+                    on_click: move |_| {
+                        let event = FormRoomEvent {
+                            room: CurrentRoom {
+                                id: child.room.id.clone(),
+                                name: child.room.name.clone(),
+                                avatar_uri: child.room.avatar_uri.clone(),
+                            },
+                        };
+
+                        if child.is_joined {
+                            props.on_submit.call(event)
+                        } else {
+                            props.on_join.call(event)
+                        }
+                    }
+                }
+            }
+
+            for subspace in props.hierarchy.subspaces.clone() {
+                div {
+                    key: "{subspace.space.id}",
+                    class: "space-tree__subspace",
+                    Subspace {
+                        text: subspace.space.name.clone(),
+                        uri: subspace.space.avatar_uri.clone(),
+                        is_open: open_subspaces.read().contains(&subspace.space.id),
+                        on_click: {
+                            let id = subspace.space.id.clone();
+                            move |_| {
+                                open_subspaces.with_mut(|open| {
+                                    match open.iter().position(|s| s.eq(&id)) {
+                                        Some(position) => {
+                                            open.remove(position);
+                                        }
+                                        None => open.push(id.clone()),
+                                    }
+                                })
+                            }
+                        }
+                    }
+
+                    if open_subspaces.read().contains(&subspace.space.id) {
+                        SpaceTree {
+                            hierarchy: subspace.clone(),
+                            is_nested: true,
+                            on_submit: props.on_submit,
+                            on_join: props.on_join
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
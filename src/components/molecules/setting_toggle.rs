@@ -0,0 +1,50 @@
+use dioxus::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
+
+use crate::{
+    components::atoms::{button::Variant, Button},
+    hooks::use_notification::use_notification,
+    utils::settings::{get_bool, set_bool},
+};
+
+#[derive(PartialEq, Props, Clone)]
+pub struct SettingToggleProps {
+    /// Key of the setting in `utils::settings`
+    setting: &'static str,
+    /// Translations under it: title, description, cta_enable, cta_disable and error
+    i18n_prefix: &'static str,
+}
+
+pub fn SettingToggle(props: SettingToggleProps) -> Element {
+    let i18 = use_i18();
+    let mut notification = use_notification();
+
+    let SettingToggleProps {
+        setting,
+        i18n_prefix,
+    } = props;
+
+    let mut enabled = use_signal(|| get_bool(setting));
+    let text = move |key: &str| translate!(i18, &format!("{i18n_prefix}.{key}"));
+
+    rsx!(
+        section { class: "profile__section",
+            h2 { class: "profile__title", {text("title")} }
+            p { class: "profile__content", {text("description")} }
+            div { class: "profile__cta",
+                Button {
+                    text: if enabled() { text("cta_disable") } else { text("cta_enable") },
+                    variant: Variant::Secondary,
+                    status: None,
+                    on_click: move |_| {
+                        let value = !enabled();
+                        match set_bool(setting, value) {
+                            Ok(_) => enabled.set(value),
+                            Err(_) => notification.handle_error(&text("error")),
+                        }
+                    }
+                }
+            }
+        }
+    )
+}
@@ -0,0 +1,110 @@
+use dioxus::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
+
+use crate::{
+    components::atoms::{Close, Icon, Spinner},
+    hooks::{use_client::use_client, use_notification::use_notification, use_room::use_room},
+    services::matrix::matrix::{image_packs, ImagePack, PackImage, StickerError},
+};
+
+#[derive(PartialEq, Props, Clone)]
+pub struct StickerPickerProps {
+    on_select: EventHandler<PackImage>,
+    on_close: EventHandler<()>,
+}
+
+pub fn StickerPicker(props: StickerPickerProps) -> Element {
+    let i18 = use_i18();
+    let client = use_client();
+    let room = use_room();
+    let mut notification = use_notification();
+
+    let mut packs = use_signal::<Vec<ImagePack>>(|| vec![]);
+    let mut selected = use_signal(|| 0);
+    let mut is_loading = use_signal(|| true);
+
+    use_effect(move || {
+        let room_id = room.get().id;
+
+        spawn(async move {
+            is_loading.set(true);
+
+            match image_packs(&client.get(), &room_id).await {
+                Ok(loaded) => {
+                    packs.set(loaded);
+                    selected.set(0);
+                }
+                Err(e) => {
+                    let message = match e {
+                        StickerError::InvalidRoomId => {
+                            translate!(i18, "chat.common.error.room_id")
+                        }
+                        StickerError::RoomNotFound => {
+                            translate!(i18, "chat.common.error.room_not_found")
+                        }
+                        StickerError::RequestFailed => {
+                            translate!(i18, "chat.stickers.errors.load")
+                        }
+                    };
+
+                    notification.handle_error(&message);
+                }
+            }
+
+            is_loading.set(false);
+        });
+    });
+
+    let personal = translate!(i18, "chat.stickers.personal");
+    let current = packs.read().get(selected()).cloned();
+
+    rsx!(
+        section { class: "sticker-picker",
+            div { class: "sticker-picker__head",
+                nav { class: "sticker-picker__packs",
+                    for (index , pack) in packs.read().iter().enumerate() {
+                        button {
+                            key: "{pack.id}",
+                            class: if index == selected() { "sticker-picker__pack sticker-picker__pack--selected" } else { "sticker-picker__pack" },
+                            onclick: move |_| selected.set(index),
+                            {pack.name.clone().unwrap_or_else(|| personal.clone())}
+                        }
+                    }
+                }
+                button {
+                    class: "sticker-picker__close",
+                    title: translate!(i18, "chat.stickers.close"),
+                    onclick: move |_| props.on_close.call(()),
+                    Icon { stroke: "var(--icon-subdued)", icon: Close }
+                }
+            }
+            if is_loading() {
+                div { class: "sticker-picker__loading", Spinner {} }
+            } else if let Some(pack) = current {
+                div { class: "sticker-picker__grid",
+                    for image in pack.images.into_iter() {
+                        button {
+                            key: "{image.shortcode}",
+                            class: "sticker-picker__item",
+                            title: "{image.body}",
+                            onclick: {
+                                let image = image.clone();
+                                move |_| props.on_select.call(image.clone())
+                            },
+                            if let Some(thumbnail) = &image.thumbnail {
+                                img {
+                                    class: "sticker-picker__image",
+                                    src: "{thumbnail}",
+                                    alt: "{image.body}",
+                                    loading: "lazy"
+                                }
+                            }
+                        }
+                    }
+                }
+            } else {
+                p { class: "sticker-picker__empty", {translate!(i18, "chat.stickers.empty")} }
+            }
+        }
+    )
+}
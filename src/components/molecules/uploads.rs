@@ -0,0 +1,45 @@
+use dioxus::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
+
+use crate::{
+    components::atoms::{Close, Icon},
+    hooks::use_send_queue::PendingUpload,
+};
+
+#[derive(PartialEq, Props, Clone)]
+pub struct UploadsProps {
+    uploads: Vec<PendingUpload>,
+    on_cancel: EventHandler<String>,
+}
+
+pub fn Uploads(props: UploadsProps) -> Element {
+    let i18 = use_i18();
+
+    rsx!(
+        section { class: "uploads",
+            for upload in props.uploads.into_iter() {
+                div { key: "{upload.txn_id}", class: "uploads__item",
+                    div { class: "uploads__content",
+                        span { class: "uploads__name", "{upload.name}" }
+                        div { class: "uploads__progress",
+                            div {
+                                class: "uploads__progress-value",
+                                style: "width: {upload.progress * 100.0}%"
+                            }
+                        }
+                    }
+                    span { class: "uploads__percentage", "{(upload.progress * 100.0).round()}%" }
+                    button {
+                        class: "uploads__cancel",
+                        title: translate!(i18, "chat.uploads.cancel"),
+                        onclick: {
+                            let txn_id = upload.txn_id.clone();
+                            move |_| props.on_cancel.call(txn_id.clone())
+                        },
+                        Icon { stroke: "var(--icon-subdued)", icon: Close }
+                    }
+                }
+            }
+        }
+    )
+}
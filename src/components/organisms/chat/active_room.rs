@@ -1,22 +1,39 @@
 use dioxus::prelude::*;
 use dioxus_router::prelude::use_navigator;
 use dioxus_std::{i18n::use_i18, translate};
-use futures::TryFutureExt;
+use futures::{StreamExt, TryFutureExt};
+use matrix_sdk::{
+    room::Room,
+    ruma::{
+        events::{direct::DirectEvent, room::member::OriginalSyncRoomMemberEvent},
+        RoomId,
+    },
+};
 
 use crate::{
     components::{
         atoms::{
             header_main::{HeaderCallOptions, HeaderEvent},
-            ArrowDownCircle, ArrowUpCircle, Avatar, Close, Exit, Header, Icon,
+            ArrowDownCircle, ArrowUpCircle, Avatar, Close, Exit, Group, Header, Icon, Layers,
+            UserCircle, Warning,
         },
-        molecules::{input_message::FormMessageEvent, rooms::CurrentRoom, InputMessage, List},
+        molecules::{
+            input_message::{attach_error_message, AttachMessageEvent, FormMessageEvent},
+            poll_form::PollMessageEvent,
+            rooms::CurrentRoom,
+            InputMessage, KnockRequests, Lightbox, List,
+        },
+        organisms::chat::room_media::RoomMedia,
     },
     hooks::{
+        use_attach::use_attach,
         use_chat::{use_chat, UseChat},
         use_client::use_client,
+        use_lightbox::use_lightbox,
         use_messages::use_messages,
         use_notification::use_notification,
         use_reply::use_reply,
+        use_report::use_report,
         use_room::use_room,
         use_rooms::use_rooms,
         use_send_attach::use_send_attach,
@@ -24,9 +41,19 @@ use crate::{
         use_thread::use_thread,
     },
     pages::{chat::chat::MessageItem, route::Route},
-    services::matrix::matrix::{leave_room, Attachment, AttachmentStream, LeaveRoomError},
+    services::matrix::matrix::{
+        approve_knock, deny_knock, leave_room, pending_knocks, send_poll, send_sticker,
+        set_room_direct, AttachmentStream, DirectError, KnockError, KnockRequest, LeaveRoomError,
+        PackImage, PollError, ReportTarget, StickerError,
+    },
 };
 
+pub enum KnockAction {
+    Load,
+    Approve(String),
+    Deny(String),
+}
+
 #[derive(PartialEq, Props, Clone)]
 pub struct ActiveRoomProps {
     on_back: EventHandler<()>,
@@ -41,6 +68,9 @@ pub fn ActiveRoom(props: ActiveRoomProps) -> Element {
     let mut notification = use_notification();
     let send_message = use_send_message();
     let send_attach = use_send_attach();
+    let mut attach = use_attach();
+
+    let mut dragging = use_signal(|| false);
 
     let mut replying_to = use_reply();
     let mut threading_to = use_thread();
@@ -57,8 +87,95 @@ pub fn ActiveRoom(props: ActiveRoomProps) -> Element {
     let mut messages_lifecycle = messages.clone();
     let mut replying_to_lifecycle = replying_to.clone();
     let mut threading_to_lifecycle = threading_to.clone();
+    let mut lightbox = use_lightbox();
     let messages = messages.get();
 
+    let mut knocks = use_signal::<Vec<KnockRequest>>(|| vec![]);
+    let mut is_answering = use_signal::<bool>(|| false);
+
+    let task_knocks = use_coroutine(|mut rx: UnboundedReceiver<KnockAction>| async move {
+        while let Some(action) = rx.next().await {
+            let room_id = room.get().id;
+
+            let result = match action {
+                KnockAction::Load => Ok(()),
+                KnockAction::Approve(user_id) => {
+                    is_answering.set(true);
+                    approve_knock(&client.get(), &room_id, &user_id).await
+                }
+                KnockAction::Deny(user_id) => {
+                    is_answering.set(true);
+                    deny_knock(&client.get(), &room_id, &user_id).await
+                }
+            };
+
+            if let Err(e) = result {
+                let message = match e {
+                    KnockError::InvalidRoomId => translate!(i18, "chat.common.error.room_id"),
+                    KnockError::InvalidUserId => translate!(i18, "chat.common.error.user_id"),
+                    KnockError::RoomNotFound => translate!(i18, "chat.common.error.room_not_found"),
+                    KnockError::RequestFailed => translate!(i18, "chat.knock.errors.answer"),
+                };
+
+                notification.handle_error(&message);
+            }
+
+            match pending_knocks(&client.get(), &room_id).await {
+                Ok(pending) => knocks.set(pending),
+                Err(e) => log::info!("pending knocks not available {e:?}"),
+            }
+
+            is_answering.set(false);
+        }
+    });
+
+    use_effect(move || {
+        let _ = room.get().id;
+        task_knocks.send(KnockAction::Load);
+    });
+
+    // Knocks come and go while the room is open, and other members may
+    // answer them first
+    let knock_handler = use_hook(move || {
+        client.get().add_event_handler(
+            move |_: OriginalSyncRoomMemberEvent, member_room: Room| async move {
+                if member_room.room_id().as_str().eq(&room.get().id) {
+                    task_knocks.send(KnockAction::Load);
+                }
+            },
+        )
+    });
+
+    use_drop(move || client.get().remove_event_handler(knock_handler));
+
+    let mut is_direct = use_signal(|| false);
+
+    let task_direct = use_coroutine(|mut rx: UnboundedReceiver<()>| async move {
+        while let Some(_) = rx.next().await {
+            let direct = RoomId::parse(&room.get().id)
+                .ok()
+                .and_then(|id| client.get().get_joined_room(&id))
+                .is_some_and(|joined| joined.is_direct());
+
+            is_direct.set(direct);
+        }
+    });
+
+    use_effect(move || {
+        let _ = room.get().id;
+        task_direct.send(());
+    });
+
+    // The store only learns about a change of m.direct once it is synced back,
+    // from this device or any other
+    let direct_handler = use_hook(move || {
+        client
+            .get()
+            .add_event_handler(move |_: DirectEvent| async move { task_direct.send(()) })
+    });
+
+    use_drop(move || client.get().remove_event_handler(direct_handler));
+
     let input_placeholder =
         use_signal::<String>(|| translate!(i18, "chat.inputs.plain_message.placeholder"));
 
@@ -66,6 +183,7 @@ pub fn ActiveRoom(props: ActiveRoomProps) -> Element {
         messages_lifecycle.set(vec![]);
         replying_to_lifecycle.set(None);
         threading_to_lifecycle.set(None);
+        lightbox.close();
     });
 
     let header_event = move |evt: HeaderEvent| match evt.value {
@@ -95,13 +213,67 @@ pub fn ActiveRoom(props: ActiveRoomProps) -> Element {
         });
     };
 
-    let on_handle_attach = move |attachment: Attachment, send_to_thread: bool| {
+    let on_handle_attach = move |event: AttachMessageEvent, send_to_thread: bool| {
         send_attach.send(AttachmentStream {
-            attachment,
+            attachment: event.attachment,
+            caption: event.caption,
             send_to_thread,
         });
     };
 
+    let on_handle_poll = move |event: PollMessageEvent| {
+        spawn(async move {
+            let result = send_poll(
+                &client.get(),
+                &room.get().id,
+                &event.question,
+                &event.answers,
+                event.kind,
+            )
+            .await;
+
+            if let Err(e) = result {
+                let message = match e {
+                    PollError::InvalidRoomId => translate!(i18, "chat.common.error.room_id"),
+                    PollError::RoomNotFound => translate!(i18, "chat.common.error.room_not_found"),
+                    PollError::RequestFailed => translate!(i18, "chat.poll.errors.create"),
+                };
+
+                notification.handle_error(&message);
+            }
+        });
+    };
+
+    let on_handle_sticker = move |image: PackImage| {
+        spawn(async move {
+            if let Err(e) = send_sticker(&client.get(), &room.get().id, &image).await {
+                let message = match e {
+                    StickerError::InvalidRoomId => translate!(i18, "chat.common.error.room_id"),
+                    StickerError::RoomNotFound => {
+                        translate!(i18, "chat.common.error.room_not_found")
+                    }
+                    StickerError::RequestFailed => translate!(i18, "chat.stickers.errors.send"),
+                };
+
+                notification.handle_error(&message);
+            }
+        });
+    };
+
+    let on_handle_drop = move |event: DragEvent| {
+        dragging.set(false);
+
+        spawn(async move {
+            let Some(files) = event.files() else {
+                return;
+            };
+
+            for error in attach.push_files(files).await {
+                notification.handle_error(&attach_error_message(&i18, &error));
+            }
+        });
+    };
+
     let on_handle_leave = move |_| {
         spawn({
             async move {
@@ -127,16 +299,53 @@ pub fn ActiveRoom(props: ActiveRoomProps) -> Element {
         });
     };
 
+    let mut report = use_report();
+
     let mut show_room_menu = use_signal(|| false);
+    let mut show_media = use_signal(|| false);
     let on_handle_menu = move |_| {
         spawn(async move {
             show_room_menu.toggle();
         });
     };
 
+    let on_handle_direct = move |_| {
+        show_room_menu.set(false);
+
+        spawn(async move {
+            match set_room_direct(&client.get(), &room.get().id, !is_direct()).await {
+                Ok(_) => is_direct.toggle(),
+                Err(e) => {
+                    let message = match e {
+                        DirectError::InvalidRoomId => translate!(i18, "chat.common.error.room_id"),
+                        DirectError::RoomNotFound => {
+                            translate!(i18, "chat.common.error.room_not_found")
+                        }
+                        DirectError::InvalidUserId | DirectError::MembersNotFound => {
+                            translate!(i18, "chat.direct.errors.members")
+                        }
+                        DirectError::RequestFailed => translate!(i18, "chat.direct.errors.update"),
+                    };
+
+                    notification.handle_error(&message);
+                }
+            }
+        });
+    };
+
     rsx! {
         div {
             class: "active-room",
+            prevent_default: "ondragover ondrop",
+            ondragover: move |_| dragging.set(true),
+            ondragleave: move |_| dragging.set(false),
+            ondrop: on_handle_drop,
+            if dragging() {
+                div {
+                    class: "active-room__dropzone",
+                    {translate!(i18, "chat.attach_preview.drop")}
+                }
+            }
             Header {
                 text: "{room.get().name.clone()}",
                 avatar_element: rsx!(
@@ -185,6 +394,64 @@ pub fn ActiveRoom(props: ActiveRoomProps) -> Element {
                                             }
                                         }
                                     }
+                                    li {
+                                        class: "room-menu__item",
+                                        button {
+                                            class: "room-menu__cta",
+                                            onclick: on_handle_direct,
+                                            if is_direct() {
+                                                Icon {
+                                                    stroke: "var(--text-1)",
+                                                    icon: Group
+                                                }
+                                                span {
+                                                    {translate!(i18, "chat.room-menu.undirect")}
+                                                }
+                                            } else {
+                                                Icon {
+                                                    stroke: "var(--text-1)",
+                                                    icon: UserCircle
+                                                }
+                                                span {
+                                                    {translate!(i18, "chat.room-menu.direct")}
+                                                }
+                                            }
+                                        }
+                                    }
+                                    li {
+                                        class: "room-menu__item",
+                                        button {
+                                            class: "room-menu__cta",
+                                            onclick: move |_| {
+                                                show_room_menu.set(false);
+                                                show_media.set(true);
+                                            },
+                                            Icon {
+                                                stroke: "var(--text-1)",
+                                                icon: Layers
+                                            }
+                                            span {
+                                                {translate!(i18, "chat.room-menu.media")}
+                                            }
+                                        }
+                                    }
+                                    li {
+                                        class: "room-menu__item",
+                                        button {
+                                            class: "room-menu__cta",
+                                            onclick: move |_| {
+                                                show_room_menu.set(false);
+                                                report.set(ReportTarget::Room { room_id: room.get().id });
+                                            },
+                                            Icon {
+                                                stroke: "var(--text-1)",
+                                                icon: Warning
+                                            }
+                                            span {
+                                                {translate!(i18, "chat.room-menu.report")}
+                                            }
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -192,6 +459,14 @@ pub fn ActiveRoom(props: ActiveRoomProps) -> Element {
                 ),
                 on_event: header_event
             }
+            if !knocks.read().is_empty() {
+                KnockRequests {
+                    requests: knocks(),
+                    disabled: is_answering(),
+                    on_approve: move |user_id| task_knocks.send(KnockAction::Approve(user_id)),
+                    on_deny: move |user_id| task_knocks.send(KnockAction::Deny(user_id))
+                }
+            }
             List {
                 messages: messages.clone(),
                 thread: None,
@@ -209,7 +484,9 @@ pub fn ActiveRoom(props: ActiveRoomProps) -> Element {
                 on_event: input_message_event,
                 on_attach: move |event|{
                     on_handle_attach(event, false);
-                }
+                },
+                on_poll: on_handle_poll,
+                on_sticker: on_handle_sticker
             }
         }
 
@@ -258,5 +535,13 @@ pub fn ActiveRoom(props: ActiveRoomProps) -> Element {
                 }
             }
         }
+
+        if show_media() {
+            RoomMedia {
+                on_close: move |_| show_media.set(false)
+            }
+        }
+
+        Lightbox {}
     }
 }
@@ -1,6 +1,7 @@
 pub mod active_room;
 pub mod preview_room;
 pub mod public_rooms;
+pub mod room_media;
 pub mod utils;
 
 pub use public_rooms::PublicRooms;
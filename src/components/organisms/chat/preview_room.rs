@@ -3,7 +3,7 @@ use std::rc::Rc;
 use dioxus::prelude::*;
 use dioxus_router::prelude::use_navigator;
 use dioxus_std::{i18n::use_i18, translate};
-use futures::TryFutureExt;
+use futures::{StreamExt, TryFutureExt};
 use ruma::RoomId;
 
 use crate::{
@@ -11,25 +11,41 @@ use crate::{
         atoms::{
             button::Variant,
             header_main::{HeaderCallOptions, HeaderEvent},
-            Avatar, Button, Header,
+            room::RoomItem,
+            Avatar, Button, Header, MessageInput,
         },
-        molecules::rooms::CurrentRoom,
+        molecules::{rooms::CurrentRoom, List},
     },
     hooks::{
         use_client::use_client,
+        use_ignored_users::use_ignored_users,
+        use_messages::use_messages,
         use_notification::use_notification,
         use_room::use_room,
         use_room_preview::{use_room_preview, PreviewRoom},
         use_rooms::use_rooms,
     },
     pages::route::Route,
-    services::matrix::matrix::join_room,
+    services::matrix::matrix::{
+        join_room, knock_room, peek_messages, reject_invitation, room_summary, InvitationError,
+        KnockError, RoomJoinRule, RoomSummary, TimelineRelation,
+    },
+    utils::sync_room::sync_created_room,
 };
 
+const PEEK_PAGE_SIZE: u32 = 20;
+
+pub enum PeekAction {
+    Load(String),
+    More,
+}
+
 pub enum PreviewRoomError {
     InvalidRoomId,
     InvitationNotFound,
     AcceptFailed,
+    RejectFailed,
+    IgnoreFailed,
     JoinFailed,
 }
 
@@ -45,6 +61,77 @@ pub fn PreviewRoom(props: PreviewRoomProps) -> Element {
     let mut rooms = use_rooms();
     let client = use_client();
     let mut notification = use_notification();
+    let mut messages = use_messages();
+    let mut ignored = use_ignored_users();
+
+    let mut summary = use_signal::<Option<RoomSummary>>(|| None);
+    let mut summary_failed = use_signal::<bool>(|| false);
+    let mut peeked = use_signal::<Vec<TimelineRelation>>(|| vec![]);
+    let mut peek_from = use_signal::<Option<String>>(|| None);
+    let mut is_peeking = use_signal::<bool>(|| false);
+    let mut knock_reason = use_signal::<String>(|| String::new());
+    let mut is_knocked = use_signal::<bool>(|| false);
+    let mut reject_reason = use_signal::<String>(|| String::new());
+
+    // Reads world readable rooms before joining them
+    let task_peek = use_coroutine(|mut rx: UnboundedReceiver<PeekAction>| async move {
+        let mut room_id = String::new();
+
+        while let Some(action) = rx.next().await {
+            let from = match action {
+                PeekAction::Load(id) => {
+                    room_id = id;
+                    summary.set(None);
+                    summary_failed.set(false);
+                    knock_reason.set(String::new());
+                    is_knocked.set(false);
+                    peeked.set(vec![]);
+                    peek_from.set(None);
+
+                    match room_summary(&client.get(), &room_id).await {
+                        Ok(s) => summary.set(Some(s)),
+                        Err(e) => {
+                            log::info!("room summary not available {e:?}");
+                            summary_failed.set(true);
+                            continue;
+                        }
+                    }
+
+                    None
+                }
+                PeekAction::More => match peek_from() {
+                    Some(from) => Some(from),
+                    None => continue,
+                },
+            };
+
+            if !summary.read().as_ref().is_some_and(|s| s.world_readable) {
+                continue;
+            }
+
+            is_peeking.set(true);
+
+            match peek_messages(&client.get(), &room_id, PEEK_PAGE_SIZE, from.as_deref()).await {
+                Ok((end, older)) => {
+                    peeked.with_mut(|m| {
+                        m.splice(0..0, older);
+                    });
+                    peek_from.set(end);
+                }
+                Err(_) => notification.handle_error(&translate!(i18, "chat.preview.peek.error")),
+            }
+
+            is_peeking.set(false);
+        }
+    });
+
+    use_effect(move || {
+        reject_reason.set(String::new());
+
+        if let PreviewRoom::Joining(r) = preview.get() {
+            task_peek.send(PeekAction::Load(r.id));
+        }
+    });
 
     let header_event = move |evt: HeaderEvent| match evt.value {
         HeaderCallOptions::CLOSE => {
@@ -60,6 +147,8 @@ pub fn PreviewRoom(props: PreviewRoomProps) -> Element {
             PreviewRoomError::InvalidRoomId => translate!(i18, "chat.common.error.room_id"),
             PreviewRoomError::InvitationNotFound => translate!(i18, "chat.preview_error_not_found"),
             PreviewRoomError::AcceptFailed => translate!(i18, "chat.preview_error_accept"),
+            PreviewRoomError::RejectFailed => translate!(i18, "chat.preview.error.reject"),
+            PreviewRoomError::IgnoreFailed => translate!(i18, "chat.ignore.errors.ignore"),
             PreviewRoomError::JoinFailed => translate!(i18, "chat.preview_error_join"),
         };
 
@@ -91,7 +180,7 @@ pub fn PreviewRoom(props: PreviewRoomProps) -> Element {
                     .remove_invited(&room_id.to_string())
                     .map_err(|_| PreviewRoomError::InvitationNotFound)?;
 
-                rooms.push_joined(item);
+                rooms.push_joined(item.room);
 
                 Ok::<(), PreviewRoomError>(())
             }
@@ -99,27 +188,33 @@ pub fn PreviewRoom(props: PreviewRoomProps) -> Element {
         })
     };
 
-    let on_handle_reject_invitation = move |r: Rc<CurrentRoom>| {
+    let on_handle_reject_invitation = move |r: Rc<CurrentRoom>, ignore_inviter: bool| {
         spawn({
             async move {
-                let room_id = RoomId::parse(&*r.id).map_err(|_| PreviewRoomError::InvalidRoomId)?;
-                let invitation = client
-                    .get()
-                    .get_invited_room(&room_id)
-                    .ok_or(PreviewRoomError::InvitationNotFound)?;
+                let reason = reject_reason();
+                let reason = reason.trim();
 
-                invitation
-                    .reject_invitation()
+                reject_invitation(&client.get(), &r.id, (!reason.is_empty()).then_some(reason))
                     .await
-                    .map_err(|_| PreviewRoomError::AcceptFailed)?;
+                    .map_err(|e| match e {
+                        InvitationError::InvalidRoomId => PreviewRoomError::InvalidRoomId,
+                        InvitationError::RequestFailed => PreviewRoomError::RejectFailed,
+                    })?;
 
                 preview.default();
                 room.default();
 
-                rooms
-                    .remove_invited(&room_id.to_string())
+                let item = rooms
+                    .remove_invited(&r.id)
                     .map_err(|_| PreviewRoomError::InvitationNotFound)?;
 
+                if let (true, Some(inviter_id)) = (ignore_inviter, item.inviter_id) {
+                    ignored
+                        .ignore(&inviter_id)
+                        .await
+                        .map_err(|_| PreviewRoomError::IgnoreFailed)?;
+                }
+
                 Ok::<(), PreviewRoomError>(())
             }
             .unwrap_or_else(on_handle_error)
@@ -135,6 +230,23 @@ pub fn PreviewRoom(props: PreviewRoomProps) -> Element {
                     .await
                     .map_err(|_| PreviewRoomError::JoinFailed)?;
 
+                // The timeline needs the room in the store, then the preview
+                // becomes the active room keeping what was already read
+                sync_created_room(&room_id, &client.get()).await;
+
+                rooms.push_joined(RoomItem {
+                    avatar_uri: r.avatar_uri.clone(),
+                    id: r.id.to_string(),
+                    name: r.name.to_string(),
+                    is_public: summary
+                        .read()
+                        .as_ref()
+                        .is_some_and(|s| s.join_rule.eq(&RoomJoinRule::Public)),
+                    is_direct: false,
+                });
+
+                messages.set(peeked());
+                preview.default();
                 room.set(CurrentRoom {
                     id: r.id.to_string(),
                     name: r.name.to_string(),
@@ -147,6 +259,29 @@ pub fn PreviewRoom(props: PreviewRoomProps) -> Element {
         });
     };
 
+    let on_handle_knock = move |r: Rc<CurrentRoom>| {
+        spawn({
+            async move {
+                let reason = knock_reason();
+                let reason = reason.trim();
+
+                knock_room(&client.get(), &r.id, (!reason.is_empty()).then_some(reason)).await?;
+
+                is_knocked.set(true);
+
+                Ok::<(), KnockError>(())
+            }
+            .unwrap_or_else(move |e: KnockError| {
+                let message = match e {
+                    KnockError::InvalidRoomId => translate!(i18, "chat.common.error.room_id"),
+                    _ => translate!(i18, "chat.knock.errors.request"),
+                };
+
+                notification.handle_error(&message);
+            })
+        });
+    };
+
     let on_handle_back = move || {
         spawn(async move {
             preview.default();
@@ -160,9 +295,15 @@ pub fn PreviewRoom(props: PreviewRoomProps) -> Element {
                 PreviewRoom::Invited(room) => {
                     let room = Rc::new(room);
                     let room_to_header = room.clone();
-                    let room_to_avatar = room.clone();
                     let room_action_accept = room.clone();
                     let room_action_reject = room.clone();
+                    let room_action_ignore = room.clone();
+                    let room_to_avatar = room.clone();
+
+                    let invitation = rooms
+                        .get_invited()
+                        .into_iter()
+                        .find(|invited| invited.room.id.eq(&room.id));
 
                     rsx!(
                         Header {
@@ -188,6 +329,24 @@ pub fn PreviewRoom(props: PreviewRoomProps) -> Element {
                                 size: 32,
                                 uri: room_to_avatar.avatar_uri.clone()
                             }
+                            if let Some(invitation) = invitation.clone() {
+                                if let Some(inviter_name) = invitation.inviter_name {
+                                    div {
+                                        class: "preview-room__inviter",
+                                        Avatar {
+                                            name: inviter_name.clone(),
+                                            size: 32,
+                                            uri: invitation.inviter_avatar.clone()
+                                        }
+                                        span {
+                                            {translate!(i18, "chat.preview.invited.by")} " {inviter_name}"
+                                        }
+                                    }
+                                }
+                                if let Some(reason) = invitation.reason {
+                                    p { class: "preview-room__topic", "“{reason}”" }
+                                }
+                            }
                             div {
                                 class: "preview-room__content",
                                 Button {
@@ -198,14 +357,36 @@ pub fn PreviewRoom(props: PreviewRoomProps) -> Element {
                                     status: None
                                 }
 
+                                MessageInput {
+                                    message: "{reject_reason()}",
+                                    placeholder: translate!(i18, "chat.preview.invited.reason.placeholder"),
+                                    error: None,
+                                    on_input: move |event: FormEvent| {
+                                        reject_reason.set(event.value());
+                                    },
+                                    on_keypress: move |_| {},
+                                    on_click: move |_| {}
+                                }
+
                                 Button {
                                     text: translate!(i18, "chat.preview.invited.cta.reject"),
                                     variant: Variant::Tertiary,
                                     on_click: move |_| {
-                                        on_handle_reject_invitation(room_action_reject.clone());
+                                        on_handle_reject_invitation(room_action_reject.clone(), false);
                                     },
                                     status: None
                                 }
+
+                                if invitation.is_some_and(|invitation| invitation.inviter_id.is_some()) {
+                                    Button {
+                                        text: translate!(i18, "chat.preview.invited.cta.reject_ignore"),
+                                        variant: Variant::Tertiary,
+                                        on_click: move |_| {
+                                            on_handle_reject_invitation(room_action_ignore.clone(), true);
+                                        },
+                                        status: None
+                                    }
+                                }
                             }
 
                         }
@@ -230,19 +411,95 @@ pub fn PreviewRoom(props: PreviewRoomProps) -> Element {
                         }
 
                         section {
-                            class: "preview-room",
+                            class: "preview-room preview-room--peek",
                             h3 {
                                 class: "preview-room__title",
                                 {translate!(i18, "chat.preview.join.title")}
                             }
+                            if let Some(s) = summary() {
+                                div {
+                                    class: "preview-room__summary",
+                                    if let Some(topic) = &s.topic {
+                                        p { class: "preview-room__topic", "{topic}" }
+                                    }
+                                    div {
+                                        class: "public-room__badges",
+                                        span {
+                                            class: "public-room__badge",
+                                            "{s.members} " {translate!(i18, "chat.public.members")}
+                                        }
+                                        span {
+                                            class: "public-room__badge",
+                                            match s.join_rule {
+                                                RoomJoinRule::Public => translate!(i18, "chat.preview.join_rule.public"),
+                                                RoomJoinRule::Invite => translate!(i18, "chat.preview.join_rule.invite"),
+                                                RoomJoinRule::Knock => translate!(i18, "chat.preview.join_rule.knock"),
+                                                RoomJoinRule::Restricted => translate!(i18, "chat.preview.join_rule.restricted"),
+                                            }
+                                        }
+                                    }
+                                }
+                                if s.world_readable {
+                                    div {
+                                        class: "preview-room__history",
+                                        List {
+                                            messages: peeked(),
+                                            thread: None,
+                                            is_loading: is_peeking(),
+                                            read_only: true,
+                                            on_scroll: move |_| {
+                                                task_peek.send(PeekAction::More)
+                                            }
+                                        }
+                                    }
+                                } else {
+                                    p {
+                                        class: "preview-room__hint",
+                                        {translate!(i18, "chat.preview.peek.not_readable")}
+                                    }
+                                }
+                            }
                             div {
                                 class: "preview-room__content",
-                                Button {
-                                    text: translate!(i18, "chat.preview.join.cta.accept"),
-                                    on_click: move |_| {
-                                        on_handle_join(room_action_join.clone());
-                                    },
-                                    status: None
+                                if summary_failed() {
+                                    p {
+                                        class: "preview-room__hint",
+                                        {translate!(i18, "chat.preview.error.summary")}
+                                    }
+                                } else if summary.read().as_ref().is_some_and(|s| s.join_rule.eq(&RoomJoinRule::Knock)) {
+                                    if is_knocked() {
+                                        p {
+                                            class: "preview-room__hint",
+                                            {translate!(i18, "chat.knock.sent")}
+                                        }
+                                    } else {
+                                        MessageInput {
+                                            message: "{knock_reason()}",
+                                            placeholder: translate!(i18, "chat.knock.reason.placeholder"),
+                                            label: translate!(i18, "chat.knock.reason.label"),
+                                            error: None,
+                                            on_input: move |event: FormEvent| {
+                                                knock_reason.set(event.value());
+                                            },
+                                            on_keypress: move |_| {},
+                                            on_click: move |_| {}
+                                        }
+                                        Button {
+                                            text: translate!(i18, "chat.knock.cta"),
+                                            on_click: move |_| {
+                                                on_handle_knock(room_action_join.clone());
+                                            },
+                                            status: None
+                                        }
+                                    }
+                                } else {
+                                    Button {
+                                        text: translate!(i18, "chat.preview.join.cta.accept"),
+                                        on_click: move |_| {
+                                            on_handle_join(room_action_join.clone());
+                                        },
+                                        status: None
+                                    }
                                 }
 
                                 Button {
@@ -1,30 +1,45 @@
-use dioxus::prelude::*;
+use dioxus::{html::input_data::keyboard_types, prelude::*};
 use dioxus_router::prelude::use_navigator;
 use dioxus_std::{i18n::use_i18, translate};
+use futures_util::StreamExt;
+use gloo::events::EventListener;
+use wasm_bindgen::JsCast;
 
 use crate::{
     components::{
         atoms::{
             header_main::{HeaderCallOptions, HeaderEvent},
-            Header,
+            input::InputType,
+            Header, MessageInput, PublicRoomView, RoomViewSkeleton,
         },
-        molecules::{rooms::FormRoomEvent, RoomsList},
+        molecules::rooms::CurrentRoom,
     },
     hooks::{
+        use_client::use_client,
         use_messages::use_messages,
+        use_notification::use_notification,
         use_public::use_public,
         use_room_preview::{use_room_preview, PreviewRoom},
-        use_rooms::use_rooms,
     },
     pages::route::Route,
+    services::matrix::matrix::{public_rooms_directory, PublicRoomEntry, PublicRoomsError},
 };
 
+const DIRECTORY_PAGE_SIZE: u32 = 30;
+// Distance to the end of the list, in pixels, that loads the next page
+const SCROLL_THRESHOLD: i32 = 200;
+
 pub enum PreviewRoomError {
     InvalidRoomId,
     InvitationNotFound,
     AcceptFailed,
 }
 
+pub enum DirectoryAction {
+    Reset,
+    LoadMore,
+}
+
 #[derive(PartialEq, Props, Clone)]
 pub struct PublicRoomProps {
     on_back: EventHandler<()>,
@@ -32,11 +47,73 @@ pub struct PublicRoomProps {
 pub fn PublicRooms(props: PublicRoomProps) -> Element {
     let i18 = use_i18();
     let nav = use_navigator();
+    let client = use_client();
+    let mut notification = use_notification();
     let mut preview = use_room_preview();
-    let rooms = use_rooms();
     let mut messages = use_messages();
     let mut public = use_public();
 
+    let mut entries = use_signal::<Vec<PublicRoomEntry>>(|| vec![]);
+    let mut next_batch = use_signal::<Option<String>>(|| None);
+    let mut search = use_signal::<String>(|| String::new());
+    let mut server = use_signal::<String>(|| String::new());
+    let mut is_loading = use_signal::<bool>(|| false);
+    // Owned by the component, the listener is removed when it unmounts
+    let mut scroll_listener = use_signal::<Option<EventListener>>(|| None);
+
+    let task_directory = use_coroutine(|mut rx: UnboundedReceiver<DirectoryAction>| async move {
+        while let Some(action) = rx.next().await {
+            let since = match action {
+                DirectoryAction::Reset => {
+                    entries.set(vec![]);
+                    next_batch.set(None);
+                    None
+                }
+                DirectoryAction::LoadMore => match next_batch() {
+                    Some(batch) => Some(batch),
+                    None => continue,
+                },
+            };
+
+            is_loading.set(true);
+
+            let server = server().trim().to_string();
+            let search = search().trim().to_string();
+
+            let result = public_rooms_directory(
+                &client.get(),
+                DIRECTORY_PAGE_SIZE,
+                since.as_deref(),
+                (!server.is_empty()).then_some(server.as_str()),
+                (!search.is_empty()).then_some(search.as_str()),
+            )
+            .await;
+
+            match result {
+                Ok(page) => {
+                    entries.with_mut(|e| e.extend(page.rooms));
+                    next_batch.set(page.next_batch);
+                }
+                Err(e) => {
+                    let message = match e {
+                        PublicRoomsError::InvalidServer => {
+                            translate!(i18, "chat.public.errors.server")
+                        }
+                        PublicRoomsError::RequestFailed => {
+                            translate!(i18, "chat.list.errors.public_rooms")
+                        }
+                    };
+
+                    notification.handle_error(&message);
+                }
+            }
+
+            is_loading.set(false);
+        }
+    });
+
+    use_effect(move || task_directory.send(DirectoryAction::Reset));
+
     let header_event = move |evt: HeaderEvent| match evt.value {
         HeaderCallOptions::CLOSE => {
             nav.push(Route::ChatList {});
@@ -46,20 +123,89 @@ pub fn PublicRooms(props: PublicRoomProps) -> Element {
         _ => {}
     };
 
-    let on_click_room = move |evt: FormRoomEvent| {
+    let mut on_click_room = move |room: CurrentRoom| {
         messages.reset();
-        preview.set(PreviewRoom::Joining(evt.room.clone()));
+        preview.set(PreviewRoom::Joining(room));
         public.default();
     };
 
     rsx! {
         div { class: "active-room",
             Header { text: translate!(i18, "chat.public.title"), on_event: header_event }
-            RoomsList {
-                rooms: rooms.get_public().clone(),
-                is_loading: false,
-                on_submit: on_click_room,
-                wrap: true
+            div { class: "public-rooms__filters",
+                MessageInput {
+                    message: "{search}",
+                    placeholder: translate!(i18, "chat.public.search"),
+                    itype: InputType::Search,
+                    error: None,
+                    on_input: move |event: FormEvent| {
+                        search.set(event.value());
+                    },
+                    on_keypress: move |event: KeyboardEvent| {
+                        if event.code() == keyboard_types::Code::Enter {
+                            task_directory.send(DirectoryAction::Reset)
+                        }
+                    },
+                    on_click: move |_| { task_directory.send(DirectoryAction::Reset) }
+                }
+                MessageInput {
+                    message: "{server}",
+                    placeholder: translate!(i18, "chat.public.server.placeholder"),
+                    label: translate!(i18, "chat.public.server.label"),
+                    error: None,
+                    on_input: move |event: FormEvent| {
+                        server.set(event.value());
+                    },
+                    on_keypress: move |event: KeyboardEvent| {
+                        if event.code() == keyboard_types::Code::Enter {
+                            task_directory.send(DirectoryAction::Reset)
+                        }
+                    },
+                    on_click: move |_| {}
+                }
+            }
+            section {
+                class: "public-rooms__list",
+                onmounted: move |event| {
+                    let Some(container) = event
+                        .data
+                        .downcast::<web_sys::Element>()
+                        .and_then(|element| element.clone().dyn_into::<web_sys::HtmlElement>().ok())
+                    else {
+                        return;
+                    };
+
+                    let listener = EventListener::new(&container.clone(), "scroll", move |_| {
+                        let scrolled = container.scroll_top() + container.client_height();
+
+                        if scrolled >= container.scroll_height() - SCROLL_THRESHOLD
+                            && !*is_loading.peek()
+                            && next_batch.peek().is_some()
+                        {
+                            task_directory.send(DirectoryAction::LoadMore);
+                        }
+                    });
+
+                    scroll_listener.set(Some(listener));
+                },
+                for entry in entries() {
+                    PublicRoomView {
+                        key: "{entry.room.id}",
+                        entry: entry.clone(),
+                        on_click: move |_| {
+                            on_click_room(CurrentRoom {
+                                id: entry.room.id.clone(),
+                                name: entry.room.name.clone(),
+                                avatar_uri: entry.room.avatar_uri.clone(),
+                            })
+                        }
+                    }
+                }
+                if is_loading() {
+                    {(0..5).map(|i| rsx!(RoomViewSkeleton { key: "{i}" }))}
+                } else if entries.read().is_empty() {
+                    p { class: "public-rooms__empty", {translate!(i18, "chat.public.empty")} }
+                }
             }
         }
     }
@@ -0,0 +1,221 @@
+use dioxus::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
+use futures::StreamExt;
+
+use crate::{
+    components::atoms::{
+        button::Variant, AudioMessage, Button, Close, File, Icon, Spinner, VideoMessage,
+    },
+    hooks::{
+        use_client::use_client, use_lightbox::use_lightbox, use_notification::use_notification,
+        use_room::use_room,
+    },
+    services::matrix::matrix::{
+        room_media, FileContent, ImageType, RoomMediaError, TimelineMessage, TimelineMessageType,
+    },
+    utils::vec_to_url::vec_to_url,
+};
+
+const PAGE_SIZE: u64 = 50;
+
+pub enum RoomMediaAction {
+    Reset,
+    LoadMore,
+}
+
+#[derive(PartialEq, Props, Clone)]
+pub struct RoomMediaProps {
+    on_close: EventHandler<()>,
+}
+
+pub fn RoomMedia(props: RoomMediaProps) -> Element {
+    let i18 = use_i18();
+    let client = use_client();
+    let room = use_room();
+    let mut lightbox = use_lightbox();
+    let mut notification = use_notification();
+
+    let mut media = use_signal::<Vec<TimelineMessage>>(|| vec![]);
+    let mut from = use_signal::<Option<String>>(|| None);
+    let mut has_more = use_signal(|| true);
+    let mut is_loading = use_signal(|| false);
+
+    let task_media = use_coroutine(|mut rx: UnboundedReceiver<RoomMediaAction>| async move {
+        while let Some(action) = rx.next().await {
+            if let RoomMediaAction::Reset = action {
+                media.set(vec![]);
+                from.set(None);
+                has_more.set(true);
+            }
+
+            if !has_more() {
+                continue;
+            }
+
+            is_loading.set(true);
+
+            match room_media(&client.get(), &room.get().id, PAGE_SIZE, from()).await {
+                Ok((end, page)) => {
+                    media.with_mut(|media| media.extend(page));
+                    has_more.set(end.is_some());
+                    from.set(end);
+                }
+                Err(e) => {
+                    let message = match e {
+                        RoomMediaError::InvalidRoomId => {
+                            translate!(i18, "chat.common.error.room_id")
+                        }
+                        RoomMediaError::RoomNotFound => {
+                            translate!(i18, "chat.common.error.room_not_found")
+                        }
+                        RoomMediaError::InvalidLimit | RoomMediaError::RequestFailed => {
+                            translate!(i18, "chat.room_media.errors.load")
+                        }
+                    };
+
+                    notification.handle_error(&message);
+                }
+            }
+
+            is_loading.set(false);
+        }
+    });
+
+    use_effect(move || {
+        let _ = room.get().id;
+        task_media.send(RoomMediaAction::Reset);
+    });
+
+    let images = media
+        .read()
+        .iter()
+        .filter_map(|message| match &message.body {
+            TimelineMessageType::Image(image) => Some(image.clone()),
+            _ => None,
+        })
+        .collect::<Vec<FileContent>>();
+
+    let visual = media
+        .read()
+        .iter()
+        .filter(|message| {
+            matches!(
+                message.body,
+                TimelineMessageType::Image(_) | TimelineMessageType::Video(_)
+            )
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let files = media
+        .read()
+        .iter()
+        .filter(|message| {
+            matches!(
+                message.body,
+                TimelineMessageType::File(_) | TimelineMessageType::Audio(_)
+            )
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    rsx!(
+        div { class: "room-media",
+            div { class: "room-media__head",
+                p { class: "room-media__title", {translate!(i18, "chat.room_media.title")} }
+                button {
+                    class: "room-media__close",
+                    onclick: move |_| props.on_close.call(()),
+                    Icon { stroke: "var(--icon-subdued)", icon: Close, height: 24, width: 24 }
+                }
+            }
+            div { class: "room-media__content",
+                if !visual.is_empty() {
+                    h3 { class: "room-media__subtitle", {translate!(i18, "chat.room_media.media")} }
+                    div { class: "room-media__grid",
+                        for message in visual.into_iter() {
+                            div { key: "{message.event_id}", class: "room-media__tile",
+                                match message.body {
+                                    TimelineMessageType::Image(image) => {
+                                        let images = images.clone();
+
+                                        rsx!(
+                                            RoomMediaImage {
+                                                image: image.clone(),
+                                                on_click: move |_| lightbox.open(images.clone(), &image)
+                                            }
+                                        )
+                                    }
+                                    TimelineMessageType::Video(video) => rsx!(
+                                        VideoMessage { body: video, is_reply: false }
+                                    ),
+                                    _ => None,
+                                }
+                            }
+                        }
+                    }
+                }
+                if !files.is_empty() {
+                    h3 { class: "room-media__subtitle", {translate!(i18, "chat.room_media.files")} }
+                    div { class: "room-media__files",
+                        for message in files.into_iter() {
+                            div { key: "{message.event_id}", class: "room-media__file",
+                                match message.body {
+                                    TimelineMessageType::File(file) => rsx!(
+                                        File { body: file, is_reply: false }
+                                    ),
+                                    TimelineMessageType::Audio(audio) => rsx!(
+                                        AudioMessage { body: audio, is_reply: false }
+                                    ),
+                                    _ => None,
+                                }
+                                span { class: "room-media__sender", "{message.sender.name} · {message.time}" }
+                            }
+                        }
+                    }
+                }
+                if is_loading() {
+                    div { class: "room-media__loading", Spinner {} }
+                } else if media.read().is_empty() && !has_more() {
+                    p { class: "room-media__empty", {translate!(i18, "chat.room_media.empty")} }
+                }
+                if has_more() && !is_loading() {
+                    div { class: "room-media__cta",
+                        Button {
+                            text: translate!(i18, "chat.room_media.cta.load_more"),
+                            variant: Variant::Secondary,
+                            status: None,
+                            on_click: move |_| task_media.send(RoomMediaAction::LoadMore)
+                        }
+                    }
+                }
+            }
+        }
+    )
+}
+
+#[derive(PartialEq, Props, Clone)]
+struct RoomMediaImageProps {
+    image: FileContent,
+    on_click: EventHandler<()>,
+}
+
+fn RoomMediaImage(props: RoomMediaImageProps) -> Element {
+    // Decrypted images get a single object url
+    let url = use_hook(|| match &props.image.source {
+        Some(ImageType::URL(url)) => Some(url.clone()),
+        Some(ImageType::Media(content)) => vec_to_url(content.clone()).ok(),
+        None => None,
+    });
+
+    match url {
+        Some(url) => rsx!(img {
+            class: "room-media__image",
+            src: "{url}",
+            alt: "{props.image.body}",
+            loading: "lazy",
+            onclick: move |_| props.on_click.call(())
+        }),
+        None => rsx!( span { class: "room-media__missing", "{props.image.body}" } ),
+    }
+}
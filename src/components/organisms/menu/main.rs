@@ -1,5 +1,6 @@
 use dioxus::prelude::*;
 use dioxus_router::prelude::Outlet;
+use dioxus_std::{i18n::use_i18, translate};
 
 use crate::{
     components::{
@@ -9,7 +10,13 @@ use crate::{
         },
         molecules::{rooms::CurrentRoom, Menu},
     },
-    hooks::{use_client::use_client, use_modal::use_modal},
+    hooks::{
+        use_client::use_client,
+        use_modal::use_modal,
+        use_send_queue::use_send_queue_worker,
+        use_sliding_sync::use_sliding_sync_loop,
+        use_sync::{use_sync, use_sync_loop},
+    },
     pages::route::Route,
     services::matrix::matrix::{account, AccountInfo},
 };
@@ -26,8 +33,14 @@ pub fn IndexMenu() -> Element {
         })
     });
 
+    let i18 = use_i18();
     let mut modal = use_modal();
     let client = use_client();
+    let sync = use_sync();
+
+    use_sync_loop();
+    use_sliding_sync_loop();
+    use_send_queue_worker();
 
     let mut show_menu = use_signal(|| false);
     let mut profile = use_signal::<AccountInfo>(|| AccountInfo {
@@ -56,6 +69,13 @@ pub fn IndexMenu() -> Element {
         article {
             HeaderMain { on_event: header_event }
 
+            if sync.is_reconnecting() {
+                div { class: "sync-banner",
+                    span { class: "sync-banner__title", {translate!(i18, "sync.connection_lost")} }
+                    span { class: "sync-banner__description", {translate!(i18, "sync.reconnecting")} }
+                }
+            }
+
             if *show_menu.read() {
                 Menu {
                     on_click: move |_| {
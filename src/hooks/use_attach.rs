@@ -1,8 +1,17 @@
-use dioxus::prelude::*;
+use std::{ops::Deref, sync::Arc};
+
+use dioxus::{html::FileEngine, prelude::*};
 use gloo::file::ObjectUrl;
 use mime::Mime;
+use wasm_bindgen_futures::JsFuture;
 
-use crate::utils::get_element::GetElement;
+use crate::{
+    services::matrix::matrix::Attachment,
+    utils::{
+        get_element::GetElement,
+        media::{compress_image, is_compressible},
+    },
+};
 
 #[derive(Clone)]
 pub struct AttachFile {
@@ -11,6 +20,100 @@ pub struct AttachFile {
     pub data: Vec<u8>,
     pub content_type: Mime,
     pub size: u64,
+    pub caption: String,
+    /// Smaller re-encoding of images, sent instead of the original when
+    /// compression is on
+    pub compressed: Option<CompressedFile>,
+}
+
+#[derive(Clone)]
+pub struct CompressedFile {
+    pub data: Vec<u8>,
+    pub content_type: Mime,
+    pub size: u64,
+}
+
+impl AttachFile {
+    pub fn new(name: &str, content: Vec<u8>) -> Result<Self, AttachError> {
+        let infered_type = infer::get(content.deref()).ok_or(AttachError::UncoverType)?;
+
+        let content_type: Result<mime::Mime, _> = infered_type.mime_type().parse();
+        let content_type = content_type.map_err(|_| AttachError::UnknownContent)?;
+
+        let blob = match content_type.type_() {
+            mime::IMAGE => gloo::file::Blob::new(content.deref()),
+            mime::VIDEO | mime::AUDIO => {
+                gloo::file::Blob::new_with_options(content.deref(), Some(infered_type.mime_type()))
+            }
+            _ => gloo::file::Blob::new(content.deref()),
+        };
+
+        let size = blob.size().clone();
+        let object_url = gloo::file::ObjectUrl::from(blob);
+
+        Ok(AttachFile {
+            name: name.to_string(),
+            preview_url: object_url,
+            data: content,
+            content_type,
+            size,
+            caption: String::new(),
+            compressed: None,
+        })
+    }
+
+    /// Compresses the file if it's an image, keeping the result only when it
+    /// is actually smaller
+    pub async fn compress(&mut self) {
+        if self.compressed.is_some() || !is_compressible(&self.content_type) {
+            return;
+        }
+
+        let Ok(image) = compress_image(&self.data).await else {
+            return;
+        };
+
+        let size = image.data.len() as u64;
+        if size < self.size {
+            self.compressed = Some(CompressedFile {
+                data: image.data,
+                content_type: image.content_type,
+                size,
+            });
+        }
+    }
+
+    pub fn upload_size(&self, compress: bool) -> u64 {
+        match &self.compressed {
+            Some(compressed) if compress => compressed.size,
+            _ => self.size,
+        }
+    }
+
+    pub fn attachment(&self, compress: bool) -> Attachment {
+        match &self.compressed {
+            Some(compressed) if compress => {
+                let stem = self
+                    .name
+                    .rsplit_once('.')
+                    .map(|(stem, _)| stem)
+                    .unwrap_or(&self.name);
+
+                Attachment {
+                    body: format!("{stem}.{}", compressed.content_type.subtype()),
+                    data: compressed.data.clone(),
+                    content_type: compressed.content_type.clone(),
+                    info: None,
+                }
+            }
+            _ => Attachment {
+                body: self.name.clone(),
+                data: self.data.clone(),
+                content_type: self.content_type.clone(),
+                info: None,
+            },
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -18,32 +121,171 @@ pub enum AttachError {
     NotFound,
     UncoverType,
     UnknownContent,
+    TooLarge(String),
+}
+
+#[derive(Clone, Default)]
+pub struct Attachments {
+    pub files: Vec<AttachFile>,
+    /// `m.upload.size` of the homeserver, once known
+    pub upload_limit: Option<u64>,
+    /// Send images downscaled and re-encoded
+    pub compress_images: bool,
 }
 
 pub fn use_attach() -> UseAttachState {
-    let attach = consume_context::<Signal<Option<AttachFile>>>();
+    let attach = consume_context::<Signal<Attachments>>();
 
     use_hook(move || UseAttachState { inner: attach })
 }
 
 #[derive(Clone, Copy)]
 pub struct UseAttachState {
-    inner: Signal<Option<AttachFile>>,
+    inner: Signal<Attachments>,
 }
 
 impl UseAttachState {
+    /// First attached file, for single file pickers like avatars
     pub fn get(&self) -> Option<AttachFile> {
-        self.inner.read().as_ref().cloned()
+        self.inner.read().files.first().cloned()
+    }
+
+    pub fn get_all(&self) -> Vec<AttachFile> {
+        self.inner.read().files.clone()
     }
 
     pub fn set(&mut self, value: Option<AttachFile>) {
-        let mut inner = self.inner.write();
-        *inner = value;
+        self.inner
+            .with_mut(|attach| attach.files = value.into_iter().collect());
+    }
+
+    /// Adds a file to the ones waiting to be sent, unless it goes over the
+    /// homeserver upload limit
+    pub fn push(&mut self, file: AttachFile) -> Result<(), AttachError> {
+        if let Some(limit) = self.upload_limit() {
+            if file.upload_size(self.compress_images()) > limit {
+                return Err(AttachError::TooLarge(file.name));
+            }
+        }
+
+        self.inner.with_mut(|attach| attach.files.push(file));
+
+        Ok(())
+    }
+
+    /// Reads and adds every picked or dropped file, returning the ones that
+    /// could not be attached
+    pub async fn push_files(&mut self, files: Arc<dyn FileEngine>) -> Vec<AttachError> {
+        let mut errors = vec![];
+
+        for name in files.files() {
+            let result = match files.read_file(&name).await {
+                Some(content) => match AttachFile::new(&name, content) {
+                    Ok(file) => self.push_prepared(file).await,
+                    Err(e) => Err(e),
+                },
+                None => Err(AttachError::NotFound),
+            };
+
+            if let Err(e) = result {
+                errors.push(e);
+            }
+        }
+
+        errors
+    }
+
+    /// Same as `push_files`, for files pasted from the clipboard
+    pub async fn push_clipboard(&mut self, files: web_sys::FileList) -> Vec<AttachError> {
+        let mut errors = vec![];
+
+        for file in (0..files.length()).filter_map(|i| files.get(i)) {
+            let content = JsFuture::from(file.array_buffer())
+                .await
+                .map(|buffer| js_sys::Uint8Array::new(&buffer).to_vec());
+
+            let result = match content.map(|content| AttachFile::new(&file.name(), content)) {
+                Ok(Ok(file)) => self.push_prepared(file).await,
+                Ok(Err(e)) => Err(e),
+                Err(_) => Err(AttachError::NotFound),
+            };
+
+            if let Err(e) = result {
+                errors.push(e);
+            }
+        }
+
+        errors
+    }
+
+    /// Compresses the file first when needed, so the upload limit is
+    /// checked against what will be sent
+    async fn push_prepared(&mut self, mut file: AttachFile) -> Result<(), AttachError> {
+        if self.compress_images() {
+            file.compress().await;
+        }
+
+        self.push(file)
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.inner.with_mut(|attach| {
+            if index < attach.files.len() {
+                attach.files.remove(index);
+            }
+        });
+    }
+
+    pub fn set_caption(&mut self, index: usize, caption: String) {
+        self.inner.with_mut(|attach| {
+            if let Some(file) = attach.files.get_mut(index) {
+                file.caption = caption;
+            }
+        });
+    }
+
+    pub fn upload_limit(&self) -> Option<u64> {
+        self.inner.read().upload_limit
+    }
+
+    pub fn set_upload_limit(&mut self, limit: Option<u64>) {
+        self.inner.with_mut(|attach| attach.upload_limit = limit);
+    }
+
+    pub fn compress_images(&self) -> bool {
+        self.inner.read().compress_images
+    }
+
+    /// Turning compression on compresses the images already attached
+    pub async fn set_compress_images(&mut self, value: bool) {
+        self.inner.with_mut(|attach| attach.compress_images = value);
+
+        if !value {
+            return;
+        }
+
+        for mut file in self.get_all() {
+            if file.compressed.is_some() {
+                continue;
+            }
+
+            file.compress().await;
+
+            // Files could have been removed while compressing
+            self.inner.with_mut(|attach| {
+                if let Some(attached) = attach
+                    .files
+                    .iter_mut()
+                    .find(|attached| attached.preview_url.deref() == file.preview_url.deref())
+                {
+                    attached.compressed = file.compressed;
+                }
+            });
+        }
     }
 
     pub fn get_file(&self) -> Result<ObjectUrl, AttachError> {
-        let attach_read = self.inner.read().as_ref().cloned();
-        match attach_read {
+        match self.get() {
             Some(file) => Ok(file.preview_url),
             None => Err(AttachError::NotFound),
         }
@@ -55,6 +297,6 @@ impl UseAttachState {
         element.set_files(None);
         element.set_value("");
 
-        self.set(None)
+        self.inner.with_mut(|attach| attach.files.clear());
     }
 }
@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::pages::login::LoggedIn;
+use crate::services::timeline_cache::evict_timeline_cache;
 
 use super::use_client::UseClientState;
 
@@ -253,6 +254,10 @@ impl UseAuthState {
         client: &mut UseClientState,
         is_guest: bool,
     ) -> Result<(), LogoutError> {
+        if let Err(_) = evict_timeline_cache(&client.get()).await {
+            log::info!("Failed to evict timeline cache");
+        }
+
         if !is_guest {
             client
                 .get()
@@ -3,11 +3,17 @@ use std::{collections::HashMap, ops::Deref};
 use dioxus::prelude::*;
 use dioxus_std::{i18n::use_i18, translate};
 use futures_util::StreamExt;
+use log::info;
 use matrix_sdk::ruma::RoomId;
 
 use crate::{
     components::atoms::message::Messages,
-    services::matrix::matrix::{timeline, TimelineError, TimelineRelation, TimelineThread},
+    services::{
+        matrix::matrix::{timeline, TimelineError, TimelineRelation, TimelineThread},
+        timeline_cache::{
+            cache_timeline, get_cached_timeline, reconcile, relation_event_id, CachedTimeline,
+        },
+    },
 };
 
 use super::{
@@ -15,6 +21,7 @@ use super::{
     use_messages::{use_messages, UseMessagesState},
     use_notification::use_notification,
     use_room::use_room,
+    use_send_queue::{use_send_queue, UseSendQueueState},
     use_session::{use_session, UseSessionState},
     use_thread::use_thread,
 };
@@ -33,10 +40,13 @@ pub fn use_chat() -> UseChatState {
     let room = use_room();
     let mut messages = use_messages();
     let mut threading_to = use_thread();
+    let send_queue = use_send_queue();
 
     let mut messages_loading = use_signal::<bool>(|| false);
     let limit_events_by_room = use_signal::<HashMap<String, u64>>(|| HashMap::new());
     let mut from = use_signal::<Option<String>>(|| None);
+    // `from` is also None once back pagination reaches the start of the room
+    let mut is_first_load = use_signal(|| true);
 
     let task_timeline = use_coroutine(|mut rx: UnboundedReceiver<()>| async move {
         while let Some(_) = rx.next().await {
@@ -54,7 +64,9 @@ pub fn use_chat() -> UseChatState {
                 &session,
                 &mut messages,
                 &client,
+                &send_queue,
                 &mut from,
+                &mut is_first_load,
                 &current_room_id,
             )
             .await
@@ -108,6 +120,7 @@ pub fn use_chat() -> UseChatState {
 
     use_effect(use_reactive((&room.get().id,), move |(_,)| {
         from.set(None);
+        is_first_load.set(true);
         task_timeline.send(());
     }));
 
@@ -182,25 +195,104 @@ async fn process(
     session: &UseSessionState,
     messages: &mut UseMessagesState,
     client: &UseClientState,
+    send_queue: &UseSendQueueState,
     from: &mut Signal<Option<String>>,
+    is_first_load: &mut Signal<bool>,
     current_room_id: &str,
 ) -> Result<(), ChatError> {
     let session_data = session.get().ok_or(ChatError::InvalidSession)?;
     let room_id = RoomId::parse(&current_room_id).map_err(|_| ChatError::InvalidRoom)?;
 
-    let (f, msg) = timeline(
+    // Opening a room renders the cached timeline first, then reconciles it
+    // with the latest page from the server
+    let is_initial = *is_first_load.peek();
+    let cached = if is_initial {
+        get_cached_timeline(&client.get(), current_room_id).await
+    } else {
+        None
+    };
+
+    if let Some(cached) = &cached {
+        if messages.get().is_empty() {
+            messages.set(cached.messages.clone());
+        }
+    }
+
+    let old_messages = if is_initial {
+        vec![]
+    } else {
+        messages.get().clone().to_vec()
+    };
+
+    let result = timeline(
         &client.get(),
         &room_id,
         current_events,
         from.read().clone(),
-        messages.get().clone().to_vec(),
+        old_messages,
         session_data,
     )
-    .await
-    .map_err(|e| ChatError::TimelineError(e))?;
+    .await;
+
+    let (f, msg) = match (result, cached) {
+        (Ok((f, msg)), Some(cached)) => {
+            let CachedTimeline { from, messages } = reconcile(
+                cached,
+                CachedTimeline {
+                    from: f,
+                    messages: msg,
+                },
+            );
+
+            (from, messages)
+        }
+        (Ok((f, msg)), None) => (f, msg),
+        // Offline, keep showing what was cached
+        (Err(_), Some(cached)) => {
+            from.set(cached.from);
+            is_first_load.set(false);
+
+            return Ok(());
+        }
+        (Err(e), None) => return Err(ChatError::TimelineError(e)),
+    };
 
-    from.set(f);
-    messages.set(msg);
+    from.set(f.clone());
+    is_first_load.set(false);
+
+    // Messages still in the send queue are not part of the server timeline yet
+    let mut rendered = msg.clone();
+    for echo in send_queue.pending_for(current_room_id) {
+        let is_rendered = match &echo {
+            TimelineRelation::None(message) => rendered
+                .iter()
+                .any(|m| relation_event_id(m).eq(&message.event_id)),
+            TimelineRelation::Reply(reply) => rendered
+                .iter()
+                .any(|m| relation_event_id(m).eq(&reply.event.event_id)),
+            // Thread echoes live inside their thread, they show up once it reloads
+            _ => true,
+        };
+
+        if !is_rendered {
+            rendered.push(echo);
+        }
+    }
+
+    messages.set(rendered);
+
+    if let Err(_) = cache_timeline(
+        &client.get(),
+        current_room_id,
+        CachedTimeline {
+            from: f,
+            messages: msg,
+        },
+    )
+    .await
+    {
+        info!("Failed to cache timeline of {current_room_id}");
+    }
 
     Ok(())
 }
@@ -0,0 +1,101 @@
+use dioxus::prelude::*;
+
+use crate::services::matrix::matrix::{
+    ignored_users, set_ignored_users, IgnoreError, TimelineRelation,
+};
+
+use super::{
+    use_client::{use_client, UseClientState},
+    use_messages::{use_messages, UseMessagesState},
+};
+
+#[derive(Default, Debug, Clone)]
+pub struct IgnoredUsers {
+    pub users: Vec<String>,
+}
+
+pub fn use_ignored_users() -> UseIgnoredUsersState {
+    let ignored = consume_context::<Signal<IgnoredUsers>>();
+    let client = use_client();
+    let messages = use_messages();
+
+    use_hook(move || UseIgnoredUsersState {
+        inner: ignored,
+        client,
+        messages,
+    })
+}
+
+#[derive(Clone, Copy)]
+pub struct UseIgnoredUsersState {
+    inner: Signal<IgnoredUsers>,
+    client: UseClientState,
+    messages: UseMessagesState,
+}
+
+impl UseIgnoredUsersState {
+    pub fn get(&self) -> Vec<String> {
+        self.inner.read().users.clone()
+    }
+
+    pub fn is_ignored(&self, user_id: &str) -> bool {
+        self.inner.read().users.iter().any(|u| u.eq(user_id))
+    }
+
+    pub async fn load(&mut self) {
+        let users = ignored_users(&self.client.get()).await;
+
+        self.inner.set(IgnoredUsers { users });
+    }
+
+    /// Keeps the list in line with the account data synced from other devices
+    pub fn set(&mut self, users: Vec<String>) {
+        self.inner.set(IgnoredUsers { users });
+    }
+
+    pub async fn ignore(&mut self, user_id: &str) -> Result<(), IgnoreError> {
+        if self.is_ignored(user_id) {
+            return Ok(());
+        }
+
+        let mut users = self.get();
+        users.push(user_id.to_string());
+
+        set_ignored_users(&self.client.get(), &users).await?;
+        self.inner.set(IgnoredUsers { users });
+
+        // What they already said stays out of sight too
+        let messages = self
+            .messages
+            .get()
+            .into_iter()
+            .filter(|m| !sender_of(m).is_some_and(|sender| sender.eq(user_id)))
+            .collect();
+
+        self.messages.set(messages);
+
+        Ok(())
+    }
+
+    pub async fn unignore(&mut self, user_id: &str) -> Result<(), IgnoreError> {
+        let users = self
+            .get()
+            .into_iter()
+            .filter(|u| !u.eq(user_id))
+            .collect::<Vec<_>>();
+
+        set_ignored_users(&self.client.get(), &users).await?;
+        self.inner.set(IgnoredUsers { users });
+
+        Ok(())
+    }
+}
+
+fn sender_of(message: &TimelineRelation) -> Option<&str> {
+    match message {
+        TimelineRelation::None(m) => Some(&m.sender.id),
+        TimelineRelation::Reply(m) => Some(&m.event.sender.id),
+        TimelineRelation::CustomThread(t) => t.thread.first().map(|m| m.sender.id.as_str()),
+        TimelineRelation::Thread(t) => t.thread.first().map(|m| m.sender.id.as_str()),
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::components::atoms::message::Messages;
-use crate::services::matrix::matrix::TimelineThread;
+use crate::services::matrix::matrix::{ReportTarget, TimelineThread};
 use crate::{
     components::molecules::{input_message::ReplyingTo, rooms::CurrentRoom},
     pages::login::LoggedIn,
@@ -12,13 +12,19 @@ use matrix_sdk::encryption::verification::SasVerification;
 use ruma::api::client::uiaa::AuthType;
 
 use super::use_auth::CacheLogin;
+use super::use_ignored_users::IgnoredUsers;
+use super::use_lightbox::Lightbox;
 use super::use_notification::NotificationItem;
 use super::use_public::PublicState;
 use super::use_room_preview::PreviewRoom;
 use super::use_rooms::RoomsList;
 use super::use_send_attach::SendAttachStatus;
+use super::use_send_queue::SendQueue;
 use super::use_session::UserSession;
-use super::{use_attach::AttachFile, use_modal::ModalState};
+use super::use_sliding_sync::SlidingSyncState;
+use super::use_sync::SyncStatus;
+use super::use_url_preview::UrlPreviews;
+use super::{use_attach::Attachments, use_modal::ModalState};
 
 #[derive(Clone)]
 pub enum BeforeSession {
@@ -51,7 +57,7 @@ pub fn use_init_app() {
     use_context_provider::<Signal<PreviewRoom>>(|| Signal::new(PreviewRoom::default()));
     use_context_provider::<Signal<RoomsList>>(|| Signal::new(RoomsList::default()));
     use_context_provider::<Signal<Messages>>(|| Signal::new(Vec::new()));
-    use_context_provider::<Signal<Option<AttachFile>>>(|| Signal::new(None));
+    use_context_provider::<Signal<Attachments>>(|| Signal::new(Attachments::default()));
     use_context_provider::<Signal<Option<ReplyingTo>>>(|| Signal::new(None));
     use_context_provider::<Signal<NotificationItem>>(|| Signal::new(NotificationItem::default()));
 
@@ -63,6 +69,13 @@ pub fn use_init_app() {
     use_context_provider::<Signal<Vec<AuthType>>>(|| Signal::new(vec![]));
 
     use_context_provider::<Signal<Option<UserSession>>>(|| Signal::new(None));
+    use_context_provider::<Signal<SyncStatus>>(|| Signal::new(SyncStatus::default()));
+    use_context_provider::<Signal<SlidingSyncState>>(|| Signal::new(SlidingSyncState::new()));
+    use_context_provider::<Signal<SendQueue>>(|| Signal::new(SendQueue::default()));
+    use_context_provider::<Signal<IgnoredUsers>>(|| Signal::new(IgnoredUsers::default()));
+    use_context_provider::<Signal<Option<ReportTarget>>>(|| Signal::new(None));
+    use_context_provider::<Signal<Option<Lightbox>>>(|| Signal::new(None));
+    use_context_provider::<Signal<UrlPreviews>>(|| Signal::new(UrlPreviews::default()));
 
     use_context_provider::<Signal<MessageDispatchId>>(|| {
         Signal::new(MessageDispatchId {
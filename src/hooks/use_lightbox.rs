@@ -0,0 +1,85 @@
+use dioxus::prelude::*;
+
+use crate::services::matrix::matrix::{FileContent, TimelineMessageType, TimelineRelation};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lightbox {
+    pub images: Vec<FileContent>,
+    pub index: usize,
+}
+
+pub fn use_lightbox() -> UseLightboxState {
+    let lightbox = consume_context::<Signal<Option<Lightbox>>>();
+
+    use_hook(move || UseLightboxState { inner: lightbox })
+}
+
+#[derive(Clone, Copy)]
+pub struct UseLightboxState {
+    inner: Signal<Option<Lightbox>>,
+}
+
+impl UseLightboxState {
+    pub fn get(&self) -> Option<Lightbox> {
+        self.inner.read().clone()
+    }
+
+    /// Opens `image` browsing through `images`, on its own when it isn't
+    /// one of them
+    pub fn open(&mut self, images: Vec<FileContent>, image: &FileContent) {
+        let lightbox = match images.iter().position(|i| i.source == image.source) {
+            Some(index) => Lightbox { images, index },
+            None => Lightbox {
+                images: vec![image.clone()],
+                index: 0,
+            },
+        };
+
+        let mut inner = self.inner.write();
+        *inner = Some(lightbox);
+    }
+
+    pub fn next(&mut self) {
+        if let Some(lightbox) = self.inner.write().as_mut() {
+            if lightbox.index + 1 < lightbox.images.len() {
+                lightbox.index += 1;
+            }
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if let Some(lightbox) = self.inner.write().as_mut() {
+            lightbox.index = lightbox.index.saturating_sub(1);
+        }
+    }
+
+    pub fn close(&mut self) {
+        let mut inner = self.inner.write();
+        *inner = None;
+    }
+}
+
+/// Images of the loaded timeline, oldest first, including the ones inside
+/// threads and replies
+pub fn timeline_images(messages: &[TimelineRelation]) -> Vec<FileContent> {
+    let mut images = vec![];
+
+    for relation in messages.iter() {
+        let bodies = match relation {
+            TimelineRelation::None(message) => vec![&message.body],
+            TimelineRelation::Reply(reply) => vec![&reply.event.body],
+            TimelineRelation::CustomThread(thread) => {
+                thread.thread.iter().map(|m| &m.body).collect()
+            }
+            TimelineRelation::Thread(thread) => thread.thread.iter().map(|m| &m.body).collect(),
+        };
+
+        for body in bodies {
+            if let TimelineMessageType::Image(image) = body {
+                images.push(image.clone());
+            }
+        }
+    }
+
+    images
+}
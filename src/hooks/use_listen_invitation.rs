@@ -3,7 +3,7 @@ use futures::StreamExt;
 use matrix_sdk::{room::Room, Client};
 use ruma::events::room::member::StrippedRoomMemberEvent;
 
-use crate::services::matrix::matrix::format_invited_room;
+use crate::services::matrix::matrix::{format_invited_room, ignored_users, is_invited_by};
 
 use super::{use_client::use_client, use_rooms::use_rooms};
 
@@ -15,6 +15,16 @@ pub fn use_listen_invitation() -> UseListenInvitationState {
     let task_push_invited = use_coroutine(|mut rx: UnboundedReceiver<Room>| async move {
         while let Some(room) = rx.next().await {
             if let Room::Invited(room) = room {
+                let ignored = ignored_users(&client.get()).await;
+
+                if is_invited_by(&room, &ignored).await {
+                    if let Err(e) = room.reject_invitation().await {
+                        log::info!("failed to reject the invitation of an ignored user {e:?}");
+                    }
+
+                    continue;
+                }
+
                 let Ok(item) = format_invited_room(&client.get(), room).await else {
                     continue;
                 };
@@ -3,7 +3,14 @@ use dioxus_std::{i18n::use_i18, translate};
 use futures_util::{StreamExt, TryFutureExt};
 use log::info;
 use matrix_sdk::{
-    config::SyncSettings, room::Room, ruma::events::room::message::OriginalSyncRoomMessageEvent,
+    room::Room,
+    ruma::{
+        events::{
+            ignored_user_list::IgnoredUserListEvent, room::message::OriginalSyncRoomMessageEvent,
+            AnySyncMessageLikeEvent, AnySyncStateEvent, SyncMessageLikeEvent,
+        },
+        serde::Raw,
+    },
 };
 use ruma::events::room::message::Relation;
 
@@ -11,15 +18,17 @@ use crate::{
     hooks::use_notification::{NotificationHandle, NotificationItem, NotificationType},
     pages::chat::chat::MessageEvent,
     services::matrix::matrix::{
-        format_original_any_room_message_event, format_relation_from_event, room_member,
-        TimelineMessageType, TimelineRelation, TimelineThread,
+        format_beacon_info, format_original_any_room_message_event, format_poll_start,
+        format_relation_from_event, format_sticker_event, room_member, TimelineMessageType,
+        TimelineRelation, TimelineThread, BEACON_INFO_TYPES, BEACON_TYPES, POLL_END_TYPES,
+        POLL_RESPONSE_TYPES, POLL_START_TYPES,
     },
 };
 
 use super::{
-    use_client::use_client, use_init_app::MessageDispatchId, use_messages::use_messages,
-    use_notification::use_notification, use_room::use_room, use_session::use_session,
-    use_thread::use_thread,
+    use_client::use_client, use_ignored_users::use_ignored_users, use_init_app::MessageDispatchId,
+    use_messages::use_messages, use_notification::use_notification, use_room::use_room,
+    use_session::use_session, use_thread::use_thread,
 };
 
 pub fn use_listen_message() -> UseListenMessagesState {
@@ -29,6 +38,7 @@ pub fn use_listen_message() -> UseListenMessagesState {
     let session = use_session();
     let room = use_room();
     let mut messages = use_messages();
+    let mut ignored = use_ignored_users();
 
     let mut handler_added = use_signal(|| false);
 
@@ -105,10 +115,13 @@ pub fn use_listen_message() -> UseListenMessagesState {
                                     } else {
                                         plain_message = Some(message_to_plain_content(
                                             &timeline_message.body.clone(),
+                                            &timeline_message.sender.name,
                                             &translate!(i18, "chat.listen.message.image"),
                                             &translate!(i18, "chat.listen.message.file"),
                                             &translate!(i18, "chat.listen.message.video"),
+                                            &translate!(i18, "chat.listen.message.audio"),
                                             &translate!(i18, "chat.listen.message.html"),
+                                            &translate!(i18, "chat.listen.message.location"),
                                         ));
                                     }
                                 }
@@ -124,10 +137,13 @@ pub fn use_listen_message() -> UseListenMessagesState {
                             } else {
                                 plain_message = Some(message_to_plain_content(
                                     &timeline_message.event.body.clone(),
+                                    &timeline_message.event.sender.name,
                                     &translate!(i18, "chat.listen.message.image"),
                                     &translate!(i18, "chat.listen.message.file"),
                                     &translate!(i18, "chat.listen.message.video"),
+                                    &translate!(i18, "chat.listen.message.audio"),
                                     &translate!(i18, "chat.listen.message.html"),
+                                    &translate!(i18, "chat.listen.message.location"),
                                 ));
                             }
                         }
@@ -252,23 +268,82 @@ pub fn use_listen_message() -> UseListenMessagesState {
         },
     );
 
-    // After logging is mandatory to perform a client sync,
-    // since the chat needs sync to listen for new messages
-    // this coroutine is necesary
+    // Votes and ends of polls, and positions of live locations, update the
+    // event they relate to
+    let task_references = use_coroutine(
+        |mut rx: UnboundedReceiver<(String, serde_json::Value)>| async move {
+            while let Some((room_id, event)) = rx.next().await {
+                if !room.get().id.eq(&room_id) {
+                    continue;
+                }
+
+                let event_type = event["type"].as_str().unwrap_or_default();
+
+                // Stopping a live location replaces the beacon info of its sender
+                if BEACON_INFO_TYPES.contains(&event_type) {
+                    let is_live = event["content"]["live"].as_bool().unwrap_or(false);
+
+                    if let (false, Some(user_id)) = (is_live, event["state_key"].as_str()) {
+                        messages.stop_beacons(user_id);
+                    }
+
+                    continue;
+                }
+
+                let Some(event_id) = event["content"]["m.relates_to"]["event_id"].as_str() else {
+                    continue;
+                };
+
+                if BEACON_TYPES.contains(&event_type) {
+                    messages.update_beacon(event_id, |beacon| beacon.apply_location(&event));
+                } else {
+                    messages.update_poll(event_id, |poll| poll.apply_event(&event));
+                }
+            }
+        },
+    );
+
+    // Handlers are fed by the background sync loop started in the main layout
     use_coroutine(|_: UnboundedReceiver<String>| {
         async move {
             let me = session.get().ok_or(ListenMessageError::SessionNotFound)?;
 
             if !*handler_added.read() {
+                // Every event is checked against the list, read it once and
+                // keep it updated from the account data
+                ignored.load().await;
+
+                client.add_event_handler(move |ev: IgnoredUserListEvent| {
+                    let mut ignored = ignored;
+
+                    async move {
+                        ignored.set(
+                            ev.content
+                                .ignored_users
+                                .keys()
+                                .map(|user_id| user_id.to_string())
+                                .collect(),
+                        );
+                    }
+                });
+
                 client.add_event_handler(
                     move |ev: OriginalSyncRoomMessageEvent,
                           room: Room,
                           client: matrix_sdk::Client| {
                         let task_sender = task_sender.clone();
                         let me = me.clone();
-                        task_replacer.send(ev.clone());
+                        let is_ignored = ignored.is_ignored(ev.sender.as_str());
+
+                        if !is_ignored {
+                            task_replacer.send(ev.clone());
+                        }
 
                         async move {
+                            if is_ignored {
+                                return;
+                            }
+
                             let message_type = &ev.content.msgtype;
                             let event_id = ev.event_id;
                             let Ok(member) = room_member(ev.sender, &room).await else {
@@ -328,19 +403,87 @@ pub fn use_listen_message() -> UseListenMessagesState {
                     },
                 );
 
+                let me_polls = me.clone();
+                client.add_event_handler(move |ev: Raw<AnySyncMessageLikeEvent>, room: Room| {
+                    let task_sender = task_sender.clone();
+                    let me = me_polls.clone();
+
+                    async move {
+                        let Ok(event) = ev.deserialize_as::<serde_json::Value>() else {
+                            return;
+                        };
+
+                        let event_type = event["type"].as_str().unwrap_or_default();
+
+                        if POLL_START_TYPES.contains(&event_type) || event_type.eq("m.sticker") {
+                            let sender = event["sender"].as_str().unwrap_or_default();
+
+                            if ignored.is_ignored(sender) {
+                                return;
+                            }
+
+                            let message = if event_type.eq("m.sticker") {
+                                match ev.deserialize() {
+                                    Ok(AnySyncMessageLikeEvent::Sticker(
+                                        SyncMessageLikeEvent::Original(sticker),
+                                    )) => format_sticker_event(&sticker, &room, &me.user_id).await,
+                                    _ => None,
+                                }
+                            } else {
+                                format_poll_start(&event, &[], &room, &me.user_id).await
+                            };
+
+                            task_sender.send(MessageEvent {
+                                room,
+                                mgs: message.map(TimelineRelation::None),
+                            });
+                        } else if POLL_RESPONSE_TYPES.contains(&event_type)
+                            || POLL_END_TYPES.contains(&event_type)
+                            || BEACON_TYPES.contains(&event_type)
+                        {
+                            task_references.send((room.room_id().to_string(), event));
+                        }
+                    }
+                });
+
+                let me_beacons = me.clone();
+                client.add_event_handler(move |ev: Raw<AnySyncStateEvent>, room: Room| {
+                    let task_sender = task_sender.clone();
+                    let me = me_beacons.clone();
+
+                    async move {
+                        let Ok(event) = ev.deserialize_as::<serde_json::Value>() else {
+                            return;
+                        };
+
+                        let event_type = event["type"].as_str().unwrap_or_default();
+                        if !BEACON_INFO_TYPES.contains(&event_type) {
+                            return;
+                        }
+
+                        let sender = event["sender"].as_str().unwrap_or_default();
+
+                        if ignored.is_ignored(sender) {
+                            return;
+                        }
+
+                        match format_beacon_info(&event, &[], &room, &me.user_id).await {
+                            Some(message) => task_sender.send(MessageEvent {
+                                room,
+                                mgs: Some(TimelineRelation::None(message)),
+                            }),
+                            None => task_references.send((room.room_id().to_string(), event)),
+                        }
+                    }
+                });
+
                 handler_added.set(true);
             }
 
-            client
-                .sync(SyncSettings::default())
-                .await
-                .map_err(|_| ListenMessageError::FailedSync)?;
-
             Ok::<(), ListenMessageError>(())
         }
         .unwrap_or_else(move |e: ListenMessageError| {
             let message = match e {
-                ListenMessageError::FailedSync => translate!(i18, "chat.common.error.sync"),
                 ListenMessageError::SessionNotFound => translate!(i18, "chat.common.error.user_id"),
             };
             notification.handle_error(&message);
@@ -351,7 +494,6 @@ pub fn use_listen_message() -> UseListenMessagesState {
 }
 
 pub enum ListenMessageError {
-    FailedSync,
     SessionNotFound,
 }
 
@@ -364,16 +506,27 @@ impl UseListenMessagesState {
 
 pub fn message_to_plain_content(
     content: &TimelineMessageType,
+    sender: &str,
     key_image: &str,
     key_file: &str,
     key_video: &str,
+    key_audio: &str,
     key_html: &str,
+    key_location: &str,
 ) -> String {
     match &content {
         TimelineMessageType::Image(_) => key_image.to_owned(),
         TimelineMessageType::Text(t) => t.to_owned(),
         TimelineMessageType::File(_) => key_file.to_owned(),
         TimelineMessageType::Video(_) => key_video.to_owned(),
+        TimelineMessageType::Audio(_) => key_audio.to_owned(),
+        TimelineMessageType::Emote(content) => format!("* {sender} {}", content.body),
+        TimelineMessageType::Notice(content) => content.body.to_owned(),
+        TimelineMessageType::Poll(poll) => poll.question.to_owned(),
+        TimelineMessageType::Sticker(sticker) => sticker.body.to_owned(),
+        TimelineMessageType::Location(_) | TimelineMessageType::Beacon(_) => {
+            key_location.to_owned()
+        }
         TimelineMessageType::Html(_) => key_html.to_owned(),
     }
 }
@@ -1,6 +1,9 @@
 use dioxus::prelude::*;
 
-use crate::{components::atoms::message::Messages, services::matrix::matrix::TimelineRelation};
+use crate::{
+    components::atoms::message::Messages,
+    services::matrix::matrix::{BeaconContent, PollContent, TimelineMessageType, TimelineRelation},
+};
 
 pub fn use_messages() -> UseMessagesState {
     let messages = consume_context::<Signal<Messages>>();
@@ -27,6 +30,57 @@ impl UseMessagesState {
         self.inner.write().push(message);
     }
 
+    /// Changes the poll started by `event_id`, if it's loaded
+    pub fn update_poll(&mut self, event_id: &str, update: impl FnOnce(&mut PollContent)) {
+        let mut inner = self.inner.write();
+
+        let poll = inner.iter_mut().find_map(|relation| match relation {
+            TimelineRelation::None(message) if message.event_id.eq(event_id) => {
+                match &mut message.body {
+                    TimelineMessageType::Poll(poll) => Some(poll),
+                    _ => None,
+                }
+            }
+            _ => None,
+        });
+
+        if let Some(poll) = poll {
+            update(poll);
+        }
+    }
+
+    /// Changes the live location shared by `event_id`, if it's loaded
+    pub fn update_beacon(&mut self, event_id: &str, update: impl FnOnce(&mut BeaconContent)) {
+        let mut inner = self.inner.write();
+
+        let beacon = inner.iter_mut().find_map(|relation| match relation {
+            TimelineRelation::None(message) if message.event_id.eq(event_id) => {
+                match &mut message.body {
+                    TimelineMessageType::Beacon(beacon) => Some(beacon),
+                    _ => None,
+                }
+            }
+            _ => None,
+        });
+
+        if let Some(beacon) = beacon {
+            update(beacon);
+        }
+    }
+
+    /// Ends every live location shared by `user_id`
+    pub fn stop_beacons(&mut self, user_id: &str) {
+        self.inner.write().iter_mut().for_each(|relation| {
+            if let TimelineRelation::None(message) = relation {
+                if let TimelineMessageType::Beacon(beacon) = &mut message.body {
+                    if message.sender.id.eq(user_id) {
+                        beacon.stop();
+                    }
+                }
+            }
+        });
+    }
+
     pub fn reset(&mut self) {
         self.inner.write().clear();
     }
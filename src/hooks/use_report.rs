@@ -0,0 +1,30 @@
+use dioxus::prelude::*;
+
+use crate::services::matrix::matrix::ReportTarget;
+
+pub fn use_report() -> UseReportState {
+    let report = consume_context::<Signal<Option<ReportTarget>>>();
+
+    use_hook(move || UseReportState { inner: report })
+}
+
+#[derive(Clone, Copy)]
+pub struct UseReportState {
+    inner: Signal<Option<ReportTarget>>,
+}
+
+impl UseReportState {
+    pub fn get(&self) -> Option<ReportTarget> {
+        self.inner.read().clone()
+    }
+
+    pub fn set(&mut self, target: ReportTarget) {
+        let mut inner = self.inner.write();
+        *inner = Some(target);
+    }
+
+    pub fn close(&mut self) {
+        let mut inner = self.inner.write();
+        *inner = None;
+    }
+}
@@ -1,11 +1,10 @@
 use dioxus::prelude::*;
 
-use crate::components::atoms::room::RoomItem;
+use crate::components::atoms::room::{InvitedRoom, RoomItem};
 
 #[derive(Clone, Debug, PartialEq, Hash, Eq, Default)]
 pub struct RoomsList {
-    pub public: Vec<RoomItem>,
-    pub invited: Vec<RoomItem>,
+    pub invited: Vec<InvitedRoom>,
     pub joined: Vec<RoomItem>,
 }
 
@@ -25,14 +24,10 @@ impl UseRoomsListState {
         self.inner.read().clone()
     }
 
-    pub fn get_invited(&self) -> Vec<RoomItem> {
+    pub fn get_invited(&self) -> Vec<InvitedRoom> {
         self.inner.read().invited.clone()
     }
 
-    pub fn get_public(&self) -> Vec<RoomItem> {
-        self.inner.read().public.clone()
-    }
-
     pub fn get_joined(&self) -> Vec<RoomItem> {
         self.inner.read().joined.clone()
     }
@@ -43,7 +38,7 @@ impl UseRoomsListState {
             .read()
             .invited
             .iter()
-            .position(|r| r.id.eq(&id))
+            .position(|r| r.room.id.eq(&id))
             .ok_or("Not found".to_string())?;
 
         Ok(position)
@@ -61,7 +56,7 @@ impl UseRoomsListState {
         Ok(position)
     }
 
-    pub fn remove_invited(&mut self, id: &str) -> Result<RoomItem, String> {
+    pub fn remove_invited(&mut self, id: &str) -> Result<InvitedRoom, String> {
         let position = self.find_invited(id)?;
         let room = self.inner.write().invited.remove(position);
 
@@ -77,8 +72,8 @@ impl UseRoomsListState {
         Ok(room)
     }
 
-    pub fn push_invited(&mut self, room: RoomItem) {
-        let Err(_) = self.find_invited(&room.id) else {
+    pub fn push_invited(&mut self, room: InvitedRoom) {
+        let Err(_) = self.find_invited(&room.room.id) else {
             return;
         };
 
@@ -96,16 +91,11 @@ impl UseRoomsListState {
         *inner = room;
     }
 
-    pub fn set_invited(&mut self, rooms: Vec<RoomItem>) {
+    pub fn set_invited(&mut self, rooms: Vec<InvitedRoom>) {
         let mut inner = self.inner.write();
         inner.invited = rooms;
     }
 
-    pub fn set_public(&mut self, rooms: Vec<RoomItem>) {
-        let inner = &mut self.inner.write();
-        inner.public = rooms;
-    }
-
     pub fn set_joined(&mut self, rooms: Vec<RoomItem>) {
         let mut inner = self.inner.write();
         inner.joined = rooms;
@@ -1,30 +1,25 @@
 use dioxus::prelude::*;
 use dioxus_std::{i18n::use_i18, translate};
 use futures_util::StreamExt;
-use matrix_sdk::ruma::{EventId, RoomId};
-use ruma::{OwnedEventId, OwnedRoomId};
 use uuid::Uuid;
 
 use crate::{
     hooks::{factory::message_factory::MessageFactory, use_send_message::get_current_time},
     services::matrix::matrix::{
-        send_attachment, upload_attachment, Attachment, AttachmentStream, FileContent,
-        TimelineMessageType, TimelineRelation,
+        AttachmentStream, AudioContent, FileContent, TimelineMessageType, TimelineRelation,
     },
 };
 
 use super::{
     factory::message_factory::use_message_factory,
-    use_attach::{use_attach, UseAttachState},
-    use_client::{use_client, UseClientState},
     use_init_app::MessageDispatchId,
     use_messages::use_messages,
     use_notification::use_notification,
-    use_reply::{use_reply, UseReplyState},
+    use_reply::use_reply,
     use_room::use_room,
-    use_send_message::SendMessageError,
+    use_send_queue::{use_send_queue, QueueState, QueuedContent, QueuedMessage},
     use_session::use_session,
-    use_thread::{use_thread, UseThreadState},
+    use_thread::use_thread,
 };
 
 pub enum SendAttachStatus {
@@ -37,13 +32,12 @@ pub enum SendAttachStatus {
 
 pub fn use_send_attach() -> UseSendMessageState {
     let i18 = use_i18();
-    let client = use_client();
     let room = use_room().get();
     let mut notification = use_notification();
     let mut messages = use_messages();
-    let mut attach = use_attach();
     let session = use_session();
     let message_factory = use_message_factory();
+    let mut send_queue = use_send_queue();
 
     let mut send_attach_status = consume_context::<Signal<SendAttachStatus>>();
     let mut message_dispatch_id = consume_context::<Signal<MessageDispatchId>>();
@@ -60,120 +54,168 @@ pub fn use_send_attach() -> UseSendMessageState {
 
                 *send_attach_status.write() = SendAttachStatus::Loading(25);
 
-                if let Some(file) = attach.get() {
-                    // build message relation
-                    let content_type = file.content_type.type_();
-
-                    match content_type {
-                        mime::IMAGE => {
-                            crate::services::matrix::matrix::ImageType::Media(file.data.clone())
-                        }
-                        mime::VIDEO => {
-                            crate::services::matrix::matrix::ImageType::Media(file.data.clone())
-                        }
-                        mime::APPLICATION => {
-                            crate::services::matrix::matrix::ImageType::Media(file.data.clone())
-                        }
-                        _ => {
-                            notification
-                                .handle_error(&translate!(i18, "chat.common.error.file_type"));
-                            return;
-                        }
-                    };
-
-                    let content = FileContent {
-                        size: Some(file.size),
-                        body: file.name,
-                        source: Some(crate::services::matrix::matrix::ImageType::Media(file.data)),
-                    };
-
-                    let attach_type = match content_type {
-                        mime::IMAGE => TimelineMessageType::Image(content),
-                        mime::VIDEO => TimelineMessageType::Video(content),
-                        mime::APPLICATION => TimelineMessageType::File(content),
-                        _ => {
-                            notification
-                                .handle_error(&translate!(i18, "chat.common.error.file_type"));
-                            return;
-                        }
-                    };
-                    let message_to_push = if let Some(r) = replying_to.clone().get().clone() {
-                        message_factory.reply(r).create_message(
-                            &attach_type,
-                            &uuid.to_string(),
-                            &timestamp,
-                            &session.get().unwrap(),
-                        )
-                    } else if let Some(thread) = thread_to.to_owned() {
-                        message_factory.thread(thread).create_message(
-                            &attach_type,
-                            &uuid.to_string(),
+                let file = &message_item.attachment;
+
+                // build message relation
+                let content_type = file.content_type.type_();
+
+                match content_type {
+                    mime::IMAGE => {
+                        crate::services::matrix::matrix::ImageType::Media(file.data.clone())
+                    }
+                    mime::VIDEO => {
+                        crate::services::matrix::matrix::ImageType::Media(file.data.clone())
+                    }
+                    mime::AUDIO => {
+                        crate::services::matrix::matrix::ImageType::Media(file.data.clone())
+                    }
+                    mime::APPLICATION => {
+                        crate::services::matrix::matrix::ImageType::Media(file.data.clone())
+                    }
+                    _ => {
+                        notification.handle_error(&translate!(i18, "chat.common.error.file_type"));
+                        return;
+                    }
+                };
+
+                let content = FileContent {
+                    size: Some(file.data.len() as u64),
+                    body: file.body.clone(),
+                    source: Some(crate::services::matrix::matrix::ImageType::Media(
+                        file.data.clone(),
+                    )),
+                    info: None,
+                };
+
+                let attach_type = match content_type {
+                    mime::IMAGE => TimelineMessageType::Image(content),
+                    mime::VIDEO => TimelineMessageType::Video(content),
+                    mime::AUDIO => TimelineMessageType::Audio(AudioContent {
+                        file: content,
+                        duration: None,
+                        waveform: None,
+                        is_voice: false,
+                    }),
+                    mime::APPLICATION => TimelineMessageType::File(content),
+                    _ => {
+                        notification.handle_error(&translate!(i18, "chat.common.error.file_type"));
+                        return;
+                    }
+                };
+                let message_to_push = if let Some(r) = replying_to.clone().get().clone() {
+                    message_factory.reply(r).create_message(
+                        &attach_type,
+                        &uuid.to_string(),
+                        &timestamp,
+                        &session.get().unwrap(),
+                    )
+                } else if let Some(thread) = thread_to.to_owned() {
+                    message_factory.thread(thread).create_message(
+                        &attach_type,
+                        &uuid.to_string(),
+                        &timestamp,
+                        &session.get().unwrap(),
+                    )
+                } else {
+                    message_factory.text().create_message(
+                        &attach_type,
+                        &uuid.to_string(),
+                        &timestamp,
+                        &session.get().unwrap(),
+                    )
+                };
+
+                let echo = message_to_push.clone();
+
+                if let TimelineRelation::None(_) | TimelineRelation::Reply(_) = message_to_push {
+                    messages.push(message_to_push);
+                } else if let TimelineRelation::CustomThread(ref t) = message_to_push {
+                    let position = back_messages.iter().position(|m| {
+                        let TimelineRelation::CustomThread(n) = m else {
+                            return false;
+                        };
+
+                        n.event_id.eq(&t.event_id)
+                    });
+
+                    if let Some(p) = position {
+                        threading_to.set(Some(t.clone()));
+                        back_messages[p] = message_to_push
+                    }
+                };
+
+                let thread = thread_to.clone();
+                let (thread_to, latest_event) = match thread_to {
+                    Some(thread) if message_item.send_to_thread => {
+                        (Some(thread.event_id), Some(thread.latest_event))
+                    }
+                    _ => (None, None),
+                };
+
+                message_dispatch_id.write().value.insert(uuid.clone(), None);
+
+                send_queue.enqueue(QueuedMessage {
+                    txn_id: uuid.clone(),
+                    room_id: room.id.clone(),
+                    content: QueuedContent::Attachment {
+                        body: message_item.attachment.body.clone(),
+                        content_type: message_item.attachment.content_type.to_string(),
+                        data: message_item.attachment.data.clone(),
+                        uploaded: None,
+                        info: None,
+                    },
+                    reply_to: replying_to.get().map(|r| r.event_id),
+                    thread_to: thread_to.clone(),
+                    latest_event: latest_event.clone(),
+                    echo,
+                    state: QueueState::Sending,
+                    caption_of: None,
+                });
+
+                // The caption goes right after its file, as a plain message
+                if let Some(caption) = message_item.caption.filter(|c| !c.trim().is_empty()) {
+                    let caption_uuid = Uuid::new_v4().to_string();
+                    let caption_content = TimelineMessageType::Text(caption.clone());
+
+                    let caption_echo = match thread {
+                        Some(thread) => message_factory.thread(thread).create_message(
+                            &caption_content,
+                            &caption_uuid,
                             &timestamp,
                             &session.get().unwrap(),
-                        )
-                    } else {
-                        message_factory.text().create_message(
-                            &attach_type,
-                            &uuid.to_string(),
+                        ),
+                        None => message_factory.text().create_message(
+                            &caption_content,
+                            &caption_uuid,
                             &timestamp,
                             &session.get().unwrap(),
-                        )
+                        ),
                     };
 
-                    if let TimelineRelation::None(_) | TimelineRelation::Reply(_) = message_to_push
-                    {
-                        messages.push(message_to_push);
-                    } else if let TimelineRelation::CustomThread(ref t) = message_to_push {
-                        let position = back_messages.iter().position(|m| {
-                            let TimelineRelation::CustomThread(n) = m else {
-                                return false;
-                            };
-
-                            n.event_id.eq(&t.event_id)
-                        });
-
-                        if let Some(p) = position {
-                            threading_to.set(Some(t.clone()));
-                            back_messages[p] = message_to_push
-                        }
-                    };
+                    if let TimelineRelation::None(_) = caption_echo {
+                        messages.push(caption_echo.clone());
+                    }
+
+                    message_dispatch_id
+                        .write()
+                        .value
+                        .insert(caption_uuid.clone(), None);
+
+                    send_queue.enqueue(QueuedMessage {
+                        txn_id: caption_uuid,
+                        room_id: room.id.clone(),
+                        content: QueuedContent::Text(caption),
+                        reply_to: None,
+                        thread_to,
+                        latest_event,
+                        echo: caption_echo,
+                        state: QueueState::Sending,
+                        caption_of: Some(uuid),
+                    });
                 }
 
-                let event_id = process(
-                    &client,
-                    &room.id,
-                    &mut replying_to,
-                    &mut threading_to,
-                    &message_item.attachment,
-                    message_item.send_to_thread,
-                    &mut attach,
-                )
-                .await
-                .map_err(|e| {
-                    let message = match e {
-                        SendMessageError::RoomNotFound | SendMessageError::InvalidRoom => {
-                            translate!(i18, "chat.common.error.room_id")
-                        }
-                        SendMessageError::InvalidReplyEventId => {
-                            translate!(i18, "chat.common.error.event_id")
-                        }
-                        SendMessageError::InvalidThreadEventId => {
-                            translate!(i18, "chat.common.error.thread_id")
-                        }
-                        SendMessageError::DispatchMessage => {
-                            translate!(i18, "chat.attach.error.send_message")
-                        }
-                        SendMessageError::InvalidFile => {
-                            translate!(i18, "chat.common.error.file_type")
-                        }
-                    };
-
-                    notification.handle_error(&message);
-                    return;
-                })
-                .ok();
-
-                message_dispatch_id.write().value.insert(uuid, event_id);
+                *send_attach_status.write() = SendAttachStatus::Sending;
+                replying_to.set(None);
             }
         }
     });
@@ -193,83 +235,3 @@ impl UseSendMessageState {
         self.inner.send(message)
     }
 }
-
-pub async fn process(
-    client: &UseClientState,
-    room_id: &str,
-    reply_to: &mut UseReplyState,
-    thread_to: &mut UseThreadState,
-    content: &Attachment,
-    send_to_thread: bool,
-    attach: &mut UseAttachState,
-) -> Result<String, SendMessageError> {
-    let thread_to = thread_to.get();
-    let room_id = RoomId::parse(room_id).map_err(|_| SendMessageError::InvalidRoom)?;
-
-    let reply_event_id = reply_to
-        .get()
-        .map(|e| EventId::parse(&e.event_id))
-        .transpose()
-        .map_err(|_| SendMessageError::InvalidReplyEventId)?;
-
-    let mut thread_event_id = None;
-    let mut latest_event_id = None;
-
-    if send_to_thread {
-        thread_event_id = thread_to
-            .as_ref()
-            .map(|e| EventId::parse(&e.event_id))
-            .transpose()
-            .map_err(|_| SendMessageError::InvalidThreadEventId)?;
-
-        latest_event_id = thread_to
-            .as_ref()
-            .map(|e| EventId::parse(&e.latest_event))
-            .transpose()
-            .map_err(|_| SendMessageError::InvalidThreadEventId)?;
-    }
-
-    reply_to.set(None);
-    attach.reset();
-
-    let event_id = dispatch_message(
-        &client,
-        room_id,
-        reply_event_id,
-        thread_event_id,
-        latest_event_id,
-        &content,
-    )
-    .await?;
-
-    Ok(event_id)
-}
-
-async fn dispatch_message(
-    client: &UseClientState,
-    room_id: OwnedRoomId,
-    reply_event_id: Option<OwnedEventId>,
-    thread_event_id: Option<OwnedEventId>,
-    latest_event_id: Option<OwnedEventId>,
-    content: &Attachment,
-) -> Result<String, SendMessageError> {
-    let client = client.get();
-    let response = upload_attachment(&client, &content)
-        .await
-        .map_err(|_| SendMessageError::DispatchMessage)?;
-
-    let response = send_attachment(
-        &client,
-        &room_id,
-        &response.content_uri,
-        &content,
-        reply_event_id,
-        thread_event_id.clone(),
-        latest_event_id,
-    )
-    .await;
-
-    let r = response.map_err(|_| SendMessageError::DispatchMessage)?;
-
-    Ok(r.event_id.to_string())
-}
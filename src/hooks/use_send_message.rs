@@ -2,11 +2,6 @@ use chrono::{DateTime, Local};
 use dioxus::prelude::*;
 use dioxus_std::{i18n::use_i18, translate};
 use futures_util::StreamExt;
-use matrix_sdk::ruma::{
-    events::room::message::{MessageType, TextMessageEventContent},
-    EventId, RoomId,
-};
-use ruma::{OwnedEventId, OwnedRoomId};
 use std::time::{Duration, UNIX_EPOCH};
 use uuid::Uuid;
 
@@ -14,19 +9,20 @@ use crate::{
     components::organisms::chat::utils::handle_command::{self, Command},
     hooks::{factory::message_factory::MessageFactory, use_public::PublicState},
     pages::chat::chat::MessageItem,
-    services::matrix::matrix::{send_message, TimelineMessageType, TimelineRelation},
+    services::matrix::matrix::{TimelineMessageType, TimelineRelation},
 };
 
 use super::{
     factory::message_factory::use_message_factory,
-    use_client::{use_client, UseClientState},
+    use_client::use_client,
     use_init_app::MessageDispatchId,
     use_messages::use_messages,
     use_notification::use_notification,
     use_public::use_public,
     use_reply::use_reply,
+    use_send_queue::{use_send_queue, QueueState, QueuedContent, QueuedMessage},
     use_session::use_session,
-    use_thread::{use_thread, UseThreadState},
+    use_thread::use_thread,
 };
 
 pub enum SendMessageError {
@@ -55,14 +51,9 @@ pub fn use_send_message() -> UseSendMessageState {
     let mut public = use_public();
     let mut threading_to = use_thread();
     let message_factory = use_message_factory();
+    let mut send_queue = use_send_queue();
 
-    let key_common_error_thread_id = translate!(i18, "chat.common.error.thread_id");
-    let key_common_error_event_id = translate!(i18, "chat.common.error.event_id");
-    let key_common_error_room_id = translate!(i18, "chat.common.error.room_id");
     let key_common_error_user_id = translate!(i18, "chat.common.error.user_id");
-    let key_common_error_file_type = translate!(i18, "chat.common.error.file_type");
-
-    let key_message_error_send_message = translate!(i18, "chat.message.error.send_message");
 
     let key_commands_join_errors_room_not_found =
         translate!(i18, "chat.commands.join.errors.room_not_found");
@@ -146,6 +137,7 @@ pub fn use_send_message() -> UseSendMessageState {
                     };
 
                     replying_to.set(None);
+                    let echo = message_to_push.clone();
 
                     if let TimelineRelation::None(_) | TimelineRelation::Reply(_) = message_to_push
                     {
@@ -165,32 +157,26 @@ pub fn use_send_message() -> UseSendMessageState {
                         }
                     };
 
-                    let event_id = process(
-                        &client,
-                        &message_item.room_id,
-                        message_item.reply_to.as_ref(),
-                        &threading_to,
-                        &message_item.msg,
-                        message_item.send_to_thread,
-                    )
-                    .await
-                    .map_err(|e| {
-                        let message = match e {
-                            SendMessageError::RoomNotFound | SendMessageError::InvalidRoom => {
-                                &key_common_error_room_id
-                            }
-                            SendMessageError::InvalidReplyEventId => &key_common_error_event_id,
-                            SendMessageError::InvalidThreadEventId => &key_common_error_thread_id,
-                            SendMessageError::DispatchMessage => &key_message_error_send_message,
-                            SendMessageError::InvalidFile => &key_common_error_file_type,
-                        };
-
-                        notification.handle_error(message);
-                        return;
-                    })
-                    .ok();
+                    let (thread_to, latest_event) = match threading_to.get() {
+                        Some(thread) if message_item.send_to_thread => {
+                            (Some(thread.event_id), Some(thread.latest_event))
+                        }
+                        _ => (None, None),
+                    };
 
-                    message_dispatch_id.write().value.insert(uuid, event_id);
+                    // The uuid of the local echo doubles as transaction id,
+                    // retries reuse it so the server drops duplicates
+                    send_queue.enqueue(QueuedMessage {
+                        txn_id: uuid,
+                        room_id: message_item.room_id.clone(),
+                        content: QueuedContent::Text(message_item.msg.clone()),
+                        reply_to: message_item.reply_to.clone(),
+                        thread_to,
+                        latest_event,
+                        echo,
+                        state: QueueState::Sending,
+                        caption_of: None,
+                    });
                 }
             }
         }
@@ -229,72 +215,3 @@ pub fn get_current_time() -> String {
 
     timestamp
 }
-
-pub async fn process(
-    client: &UseClientState,
-    room_id: &str,
-    reply_to: Option<&String>,
-    thread_to: &UseThreadState,
-    content: &str,
-    send_to_thread: bool,
-) -> Result<String, SendMessageError> {
-    let thread_to = thread_to.get();
-    let room_id = RoomId::parse(room_id).map_err(|_| SendMessageError::InvalidRoom)?;
-
-    let reply_event_id = reply_to
-        .map(|e| EventId::parse(&e))
-        .transpose()
-        .map_err(|_| SendMessageError::InvalidReplyEventId)?;
-
-    let mut thread_event_id = None;
-    let mut latest_event_id = None;
-
-    if send_to_thread {
-        thread_event_id = thread_to
-            .as_ref()
-            .map(|e| EventId::parse(&e.event_id))
-            .transpose()
-            .map_err(|_| SendMessageError::InvalidThreadEventId)?;
-
-        latest_event_id = thread_to
-            .as_ref()
-            .map(|e| EventId::parse(&e.latest_event))
-            .transpose()
-            .map_err(|_| SendMessageError::InvalidThreadEventId)?;
-    }
-
-    let event_id = dispatch_message(
-        &client,
-        room_id,
-        reply_event_id,
-        thread_event_id,
-        latest_event_id,
-        &content,
-    )
-    .await?;
-
-    Ok(event_id)
-}
-
-async fn dispatch_message(
-    client: &UseClientState,
-    room_id: OwnedRoomId,
-    reply_event_id: Option<OwnedEventId>,
-    thread_event_id: Option<OwnedEventId>,
-    latest_event_id: Option<OwnedEventId>,
-    content: &str,
-) -> Result<String, SendMessageError> {
-    let response = send_message(
-        &client.get(),
-        &room_id,
-        MessageType::Text(TextMessageEventContent::plain(content)),
-        reply_event_id,
-        thread_event_id,
-        latest_event_id,
-    )
-    .await;
-
-    let r = response.map_err(|_| SendMessageError::DispatchMessage)?;
-
-    Ok(r.event_id.to_string())
-}
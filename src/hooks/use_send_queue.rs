@@ -0,0 +1,649 @@
+use std::collections::{HashMap, HashSet};
+
+use dioxus::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
+use futures_util::StreamExt;
+use log::info;
+use matrix_sdk::{
+    ruma::{
+        events::room::message::{MessageType, TextMessageEventContent},
+        EventId, OwnedMxcUri, OwnedTransactionId, RoomId,
+    },
+    Client,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    services::{
+        matrix::matrix::{
+            attachment_info, send_attachment, send_message, upload_attachment_with_progress,
+            Attachment, AttachmentInfo, TimelineRelation,
+        },
+        timeline_cache::strip_media,
+    },
+    utils::upload::Upload,
+};
+
+use super::{
+    use_client::use_client,
+    use_init_app::MessageDispatchId,
+    use_messages::{use_messages, UseMessagesState},
+    use_notification::use_notification,
+    use_send_message::SendMessageError,
+    use_sync::use_sync,
+};
+
+const SEND_QUEUE_KEY: &[u8] = b"fido.send_queue";
+const SEND_QUEUE_DATA_PREFIX: &str = "fido.send_queue.data.";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum QueueState {
+    Sending,
+    Sent(String),
+    Failed,
+    Cancelled,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum QueuedContent {
+    Text(String),
+    Attachment {
+        body: String,
+        content_type: String,
+        /// Stored as raw bytes under its own key, not inside the queue JSON
+        #[serde(skip)]
+        data: Vec<u8>,
+        /// Set once the media is uploaded, so a retry only resends the event
+        uploaded: Option<String>,
+        /// Dimensions and thumbnail worked out along with the upload
+        #[serde(default)]
+        info: Option<AttachmentInfo>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueuedMessage {
+    /// Transaction id of the event, also the event id of its local echo
+    pub txn_id: String,
+    pub room_id: String,
+    pub content: QueuedContent,
+    pub reply_to: Option<String>,
+    pub thread_to: Option<String>,
+    pub latest_event: Option<String>,
+    pub echo: TimelineRelation,
+    pub state: QueueState,
+    /// Attachment this message is the caption of, it waits for the file
+    /// and goes away with it
+    #[serde(default)]
+    pub caption_of: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SendQueue {
+    pub items: Vec<QueuedMessage>,
+    /// Attachments being uploaded, by transaction id
+    pub uploads: HashMap<String, UploadProgress>,
+}
+
+#[derive(Clone, Debug)]
+pub struct UploadProgress {
+    pub upload: Option<Upload>,
+    pub progress: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingUpload {
+    pub txn_id: String,
+    pub name: String,
+    pub progress: f64,
+}
+
+pub fn use_send_queue() -> UseSendQueueState {
+    let queue = consume_context::<Signal<SendQueue>>();
+    let messages = use_messages();
+
+    use_hook(move || UseSendQueueState {
+        inner: queue,
+        messages,
+    })
+}
+
+#[derive(Clone, Copy)]
+pub struct UseSendQueueState {
+    inner: Signal<SendQueue>,
+    messages: UseMessagesState,
+}
+
+impl UseSendQueueState {
+    pub fn get(&self) -> Vec<QueuedMessage> {
+        self.inner.read().items.clone()
+    }
+
+    pub fn state_of(&self, txn_id: &str) -> Option<QueueState> {
+        self.inner
+            .read()
+            .items
+            .iter()
+            .find(|item| item.txn_id.eq(txn_id))
+            .map(|item| item.state.clone())
+    }
+
+    /// Local echoes of the messages of a room that are not sent yet
+    pub fn pending_for(&self, room_id: &str) -> Vec<TimelineRelation> {
+        self.inner
+            .read()
+            .items
+            .iter()
+            .filter(|item| {
+                item.room_id.eq(room_id)
+                    && matches!(item.state, QueueState::Sending | QueueState::Failed)
+            })
+            .map(|item| item.echo.clone())
+            .collect()
+    }
+
+    /// Attachments of a room still waiting to be sent, with their upload progress
+    pub fn uploads_for(&self, room_id: &str) -> Vec<PendingUpload> {
+        let queue = self.inner.read();
+
+        queue
+            .items
+            .iter()
+            .filter(|item| item.room_id.eq(room_id) && item.state.eq(&QueueState::Sending))
+            .filter_map(|item| match &item.content {
+                QueuedContent::Attachment { body, .. } => Some(PendingUpload {
+                    txn_id: item.txn_id.clone(),
+                    name: body.clone(),
+                    progress: queue
+                        .uploads
+                        .get(&item.txn_id)
+                        .map(|u| u.progress)
+                        .unwrap_or_default(),
+                }),
+                QueuedContent::Text(_) => None,
+            })
+            .collect()
+    }
+
+    /// Aborts the upload of an attachment, if started, and drops it from the queue
+    pub fn cancel(&mut self, txn_id: &str) {
+        let upload = self.inner.with_mut(|queue| queue.uploads.remove(txn_id));
+
+        if let Some(upload) = upload.and_then(|u| u.upload) {
+            upload.cancel();
+        }
+
+        self.discard(txn_id);
+    }
+
+    pub fn enqueue(&mut self, item: QueuedMessage) {
+        self.inner.with_mut(|queue| queue.items.push(item));
+    }
+
+    pub fn retry(&mut self, txn_id: &str) {
+        if let Some(QueueState::Failed) = self.state_of(txn_id) {
+            self.set_state(txn_id, QueueState::Sending);
+        }
+    }
+
+    fn retry_failed(&mut self) {
+        self.inner.with_mut(|queue| {
+            queue
+                .items
+                .iter_mut()
+                .filter(|item| item.state.eq(&QueueState::Failed))
+                .for_each(|item| item.state = QueueState::Sending)
+        });
+    }
+
+    // Sent messages are already replaced by the server events in the timeline
+    fn prune_sent(&mut self) {
+        self.inner.with_mut(|queue| {
+            queue
+                .items
+                .retain(|item| !matches!(item.state, QueueState::Sent(_)))
+        });
+    }
+
+    pub fn discard(&mut self, txn_id: &str) {
+        let captions = self
+            .inner
+            .read()
+            .items
+            .iter()
+            .filter(|item| item.caption_of.as_deref().eq(&Some(txn_id)))
+            .map(|item| item.txn_id.clone())
+            .collect::<Vec<_>>();
+
+        self.set_state(txn_id, QueueState::Cancelled);
+        for caption in captions.iter() {
+            self.set_state(caption, QueueState::Cancelled);
+        }
+
+        self.inner.with_mut(|queue| {
+            queue
+                .items
+                .retain(|item| !item.state.eq(&QueueState::Cancelled))
+        });
+
+        let messages = self
+            .messages
+            .get()
+            .into_iter()
+            .filter_map(|relation| remove_echo(relation, txn_id))
+            .filter_map(|relation| {
+                captions
+                    .iter()
+                    .try_fold(relation, |relation, caption| remove_echo(relation, caption))
+            })
+            .collect::<Vec<_>>();
+
+        self.messages.set(messages);
+    }
+
+    fn next_sending(&self) -> Option<QueuedMessage> {
+        let queue = self.inner.read();
+
+        queue
+            .items
+            .iter()
+            .find(|item| {
+                // A caption never goes out before its file
+                let waits_for_file = item.caption_of.as_ref().is_some_and(|file| {
+                    queue.items.iter().any(|i| {
+                        i.txn_id.eq(file)
+                            && matches!(i.state, QueueState::Sending | QueueState::Failed)
+                    })
+                });
+
+                item.state.eq(&QueueState::Sending) && !waits_for_file
+            })
+            .cloned()
+    }
+
+    fn set_state(&mut self, txn_id: &str, state: QueueState) {
+        self.inner.with_mut(|queue| {
+            if let Some(item) = queue.items.iter_mut().find(|item| item.txn_id.eq(txn_id)) {
+                item.state = state;
+            }
+        });
+    }
+
+    fn set_upload(&mut self, txn_id: &str, upload: Upload) {
+        self.inner.with_mut(|queue| {
+            queue.uploads.insert(
+                txn_id.to_string(),
+                UploadProgress {
+                    upload: Some(upload),
+                    progress: 0.0,
+                },
+            );
+        });
+    }
+
+    fn set_progress(&mut self, txn_id: &str, progress: f64) {
+        self.inner.with_mut(|queue| {
+            if let Some(upload) = queue.uploads.get_mut(txn_id) {
+                upload.progress = progress;
+            }
+        });
+    }
+
+    fn clear_upload(&mut self, txn_id: &str) {
+        self.inner.with_mut(|queue| queue.uploads.remove(txn_id));
+    }
+
+    fn set_uploaded(&mut self, txn_id: &str, uri: String, media_info: Option<AttachmentInfo>) {
+        self.inner.with_mut(|queue| {
+            if let Some(item) = queue.items.iter_mut().find(|item| item.txn_id.eq(txn_id)) {
+                if let QueuedContent::Attachment { uploaded, info, .. } = &mut item.content {
+                    *uploaded = Some(uri);
+                    *info = media_info;
+                }
+            }
+        });
+    }
+
+    fn restore(&mut self, items: Vec<QueuedMessage>) {
+        self.inner.with_mut(|queue| {
+            let restored = items
+                .into_iter()
+                .filter(|item| !queue.items.iter().any(|i| i.txn_id.eq(&item.txn_id)));
+
+            queue.items.splice(0..0, restored.collect::<Vec<_>>());
+        });
+    }
+}
+
+/// Sends the queued messages in order, holding them while the connection is lost.
+/// Must be started once, from the layout shared by every logged in route.
+pub fn use_send_queue_worker() {
+    let i18 = use_i18();
+    let client = use_client();
+    let sync = use_sync();
+    let mut notification = use_notification();
+    let mut queue = use_send_queue();
+    let mut message_dispatch_id = consume_context::<Signal<MessageDispatchId>>();
+
+    let mut restored = use_signal(|| false);
+    let mut was_reconnecting = use_signal(|| false);
+
+    let task_flush = use_coroutine(|mut rx: UnboundedReceiver<()>| async move {
+        queue.restore(load_queue(&client.get()).await);
+        restored.set(true);
+
+        while let Some(_) = rx.next().await {
+            while let Some(item) = queue.next_sending() {
+                if sync.is_reconnecting() {
+                    break;
+                }
+
+                match dispatch(&client.get(), &item, &mut queue).await {
+                    Ok(event_id) => {
+                        message_dispatch_id
+                            .write()
+                            .value
+                            .insert(item.txn_id.clone(), Some(event_id.clone()));
+
+                        queue.set_state(&item.txn_id, QueueState::Sent(event_id));
+                    }
+                    // Cancelled while in flight, nothing left to report
+                    Err(_) if queue.state_of(&item.txn_id).is_none() => {}
+                    Err(e) => {
+                        queue.set_state(&item.txn_id, QueueState::Failed);
+
+                        let message = match e {
+                            SendMessageError::RoomNotFound | SendMessageError::InvalidRoom => {
+                                translate!(i18, "chat.common.error.room_id")
+                            }
+                            SendMessageError::InvalidReplyEventId => {
+                                translate!(i18, "chat.common.error.event_id")
+                            }
+                            SendMessageError::InvalidThreadEventId => {
+                                translate!(i18, "chat.common.error.thread_id")
+                            }
+                            SendMessageError::InvalidFile => {
+                                translate!(i18, "chat.common.error.file_type")
+                            }
+                            SendMessageError::DispatchMessage => match item.content {
+                                QueuedContent::Text(_) => {
+                                    translate!(i18, "chat.message.error.send_message")
+                                }
+                                QueuedContent::Attachment { .. } => {
+                                    translate!(i18, "chat.attach.error.send_message")
+                                }
+                            },
+                        };
+
+                        notification.handle_error(&message);
+                    }
+                }
+            }
+
+            queue.prune_sent();
+        }
+    });
+
+    let task_persist = use_coroutine(|mut rx: UnboundedReceiver<Vec<QueuedMessage>>| async move {
+        let mut persisted = String::new();
+        let mut stored_data = HashSet::<String>::new();
+
+        while let Some(items) = rx.next().await {
+            persist_queue(&client.get(), &items, &mut persisted, &mut stored_data).await;
+        }
+    });
+
+    // Runs on every queue change and when the connection comes back
+    use_effect(move || {
+        let items = queue.get();
+        let is_reconnecting = sync.is_reconnecting();
+
+        if !restored() {
+            return;
+        }
+
+        // Messages that failed while offline get another chance once back
+        if *was_reconnecting.peek() && !is_reconnecting {
+            queue.retry_failed();
+        }
+        was_reconnecting.set(is_reconnecting);
+
+        task_persist.send(items);
+        task_flush.send(());
+    });
+}
+
+async fn dispatch(
+    client: &Client,
+    item: &QueuedMessage,
+    queue: &mut UseSendQueueState,
+) -> Result<String, SendMessageError> {
+    let room_id = RoomId::parse(&item.room_id).map_err(|_| SendMessageError::InvalidRoom)?;
+
+    let reply_to = item
+        .reply_to
+        .as_ref()
+        .map(|e| EventId::parse(e))
+        .transpose()
+        .map_err(|_| SendMessageError::InvalidReplyEventId)?;
+
+    let thread_to = item
+        .thread_to
+        .as_ref()
+        .map(|e| EventId::parse(e))
+        .transpose()
+        .map_err(|_| SendMessageError::InvalidThreadEventId)?;
+
+    let latest_event = item
+        .latest_event
+        .as_ref()
+        .map(|e| EventId::parse(e))
+        .transpose()
+        .map_err(|_| SendMessageError::InvalidThreadEventId)?;
+
+    let txn_id = OwnedTransactionId::from(item.txn_id.clone());
+
+    let response = match &item.content {
+        QueuedContent::Text(body) => {
+            send_message(
+                client,
+                &room_id,
+                MessageType::Text(TextMessageEventContent::plain(body)),
+                reply_to,
+                thread_to,
+                latest_event,
+                &txn_id,
+            )
+            .await?
+        }
+        QueuedContent::Attachment {
+            body,
+            content_type,
+            data,
+            uploaded,
+            info,
+        } => {
+            let mut attachment = Attachment {
+                body: body.clone(),
+                data: data.clone(),
+                content_type: content_type
+                    .parse()
+                    .map_err(|_| SendMessageError::InvalidFile)?,
+                info: info.clone(),
+            };
+
+            let uri = match uploaded {
+                Some(uri) => OwnedMxcUri::from(uri.as_str()),
+                None => {
+                    attachment.info = attachment_info(client, &attachment).await;
+
+                    let mut progress_queue = *queue;
+                    let txn = item.txn_id.clone();
+
+                    let (upload, response) =
+                        upload_attachment_with_progress(client, &attachment, move |progress| {
+                            progress_queue.set_progress(&txn, progress)
+                        })
+                        .await
+                        .map_err(|_| SendMessageError::DispatchMessage)?;
+
+                    queue.set_upload(&item.txn_id, upload);
+                    let content_uri = response.await;
+                    queue.clear_upload(&item.txn_id);
+
+                    let content_uri = content_uri.map_err(|_| SendMessageError::DispatchMessage)?;
+
+                    queue.set_uploaded(
+                        &item.txn_id,
+                        content_uri.to_string(),
+                        attachment.info.clone(),
+                    );
+                    content_uri
+                }
+            };
+
+            send_attachment(
+                client,
+                &room_id,
+                &uri,
+                &attachment,
+                reply_to,
+                thread_to,
+                latest_event,
+                &txn_id,
+            )
+            .await?
+        }
+    };
+
+    Ok(response.event_id.to_string())
+}
+
+fn data_key(txn_id: &str) -> Vec<u8> {
+    format!("{SEND_QUEUE_DATA_PREFIX}{txn_id}").into_bytes()
+}
+
+async fn load_queue(client: &Client) -> Vec<QueuedMessage> {
+    let items: Vec<QueuedMessage> = client
+        .store()
+        .get_custom_value(SEND_QUEUE_KEY)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_slice(&value).ok())
+        .unwrap_or_default();
+
+    let mut restored = vec![];
+
+    for mut item in items {
+        if let QueuedContent::Attachment { data, .. } = &mut item.content {
+            match client
+                .store()
+                .get_custom_value(&data_key(&item.txn_id))
+                .await
+            {
+                Ok(Some(value)) if !value.is_empty() => *data = value,
+                // The file is gone, there is nothing left to send
+                _ => continue,
+            }
+        }
+
+        restored.push(item);
+    }
+
+    restored
+}
+
+/// Stores the messages still waiting to be sent, skipping the write when
+/// nothing but the upload progress changed since the last one.
+async fn persist_queue(
+    client: &Client,
+    items: &[QueuedMessage],
+    persisted: &mut String,
+    stored_data: &mut HashSet<String>,
+) {
+    let pending = items
+        .iter()
+        .filter(|item| matches!(item.state, QueueState::Sending | QueueState::Failed))
+        .collect::<Vec<_>>();
+
+    for item in pending.iter() {
+        let QueuedContent::Attachment { data, .. } = &item.content else {
+            continue;
+        };
+
+        if stored_data.contains(&item.txn_id) {
+            continue;
+        }
+
+        match client
+            .store()
+            .set_custom_value(&data_key(&item.txn_id), data.clone())
+            .await
+        {
+            Ok(_) => {
+                stored_data.insert(item.txn_id.clone());
+            }
+            Err(_) => info!("Failed to persist attachment {}", item.txn_id),
+        }
+    }
+
+    // Files of sent or cancelled attachments are overwritten with an empty value
+    let released = stored_data
+        .iter()
+        .filter(|txn_id| !pending.iter().any(|item| item.txn_id.eq(*txn_id)))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    for txn_id in released {
+        if let Err(_) = client
+            .store()
+            .set_custom_value(&data_key(&txn_id), vec![])
+            .await
+        {
+            info!("Failed to release attachment {txn_id}");
+        }
+
+        stored_data.remove(&txn_id);
+    }
+
+    let pending = pending
+        .into_iter()
+        .cloned()
+        .map(|item| QueuedMessage {
+            echo: strip_media(item.echo.clone()),
+            ..item
+        })
+        .collect::<Vec<_>>();
+
+    let Ok(value) = serde_json::to_string(&pending) else {
+        return;
+    };
+
+    if value.eq(persisted) {
+        return;
+    }
+
+    match client
+        .store()
+        .set_custom_value(SEND_QUEUE_KEY, value.clone().into_bytes())
+        .await
+    {
+        Ok(_) => *persisted = value,
+        Err(_) => info!("Failed to persist send queue"),
+    }
+}
+
+fn remove_echo(relation: TimelineRelation, txn_id: &str) -> Option<TimelineRelation> {
+    match relation {
+        TimelineRelation::None(message) if message.event_id.eq(txn_id) => None,
+        TimelineRelation::Reply(reply) if reply.event.event_id.eq(txn_id) => None,
+        TimelineRelation::CustomThread(mut thread) => {
+            thread.thread.retain(|message| !message.event_id.eq(txn_id));
+            thread.count = thread.thread.len();
+
+            Some(TimelineRelation::CustomThread(thread))
+        }
+        relation => Some(relation),
+    }
+}
@@ -2,14 +2,21 @@ use dioxus::prelude::*;
 use gloo::storage::{errors::StorageError, LocalStorage};
 use log::info;
 use matrix_sdk::HttpError;
-use matrix_sdk::{config::SyncSettings, Client};
+use matrix_sdk::{config::SyncSettings, Client, Session};
 
-use ruma::api::client::filter::{FilterDefinition, RoomEventFilter};
 use ruma::api::client::sync::sync_events;
-use ruma::events::RoomEventType;
 use std::time::Duration;
 
-use crate::services::matrix::matrix::FullSession;
+use crate::{
+    services::{
+        filter::{
+            limited_sync_filter_definition, sync_filter_definition, timeline_event_types,
+            upload_sync_filter, SYNC_FILTER_NAME,
+        },
+        matrix::matrix::FullSession,
+    },
+    utils::settings::{get_bool, SLIDING_SYNC},
+};
 
 pub fn use_session() -> UseSessionState {
     let user = consume_context::<Signal<Option<UserSession>>>();
@@ -86,25 +93,30 @@ impl UseSessionState {
         client: Client,
         initial_sync_token: Option<String>,
     ) -> Result<(), SessionError> {
-        let mut room_event_filter = RoomEventFilter::empty();
-        room_event_filter.rooms = Some(&[]);
-
-        let filter_event_type = vec![RoomEventType::RoomMessage.to_string()];
-        room_event_filter.types = Some(&filter_event_type);
-
-        let mut filter = FilterDefinition::empty();
-        filter.room.include_leave = false;
-        filter.room.account_data = room_event_filter.clone();
-        filter.room.timeline = room_event_filter.clone();
-        filter.room.ephemeral = room_event_filter.clone();
-        filter.room.state = room_event_filter.clone();
+        let filter_id = Self::get_sync_filter(&client).await;
+        let types = timeline_event_types();
+
+        // Sliding sync lists the rooms, the store only gets the ones opened later
+        let is_light = get_bool(SLIDING_SYNC);
+
+        // Servers that refuse the upload (e.g. for guests) still get the same filter inline
+        let filter = match (&filter_id, is_light) {
+            (_, true) => sync_events::v3::Filter::FilterDefinition(limited_sync_filter_definition(
+                &types,
+                &[],
+            )),
+            (Some(id), false) => sync_events::v3::Filter::FilterId(id),
+            (None, false) => {
+                sync_events::v3::Filter::FilterDefinition(sync_filter_definition(&types))
+            }
+        };
 
         let mut sync_settings = SyncSettings::default()
-            .filter(sync_events::v3::Filter::FilterDefinition(filter))
+            .filter(filter)
             .timeout(Duration::from_millis(1000))
-            .full_state(true);
+            .full_state(!is_light);
 
-        if let Some(sync_token) = initial_sync_token {
+        if let (Some(sync_token), false) = (initial_sync_token, is_light) {
             sync_settings = sync_settings.token(sync_token);
         }
 
@@ -113,7 +125,10 @@ impl UseSessionState {
                 Self::whoami(self, client)
                     .await
                     .map_err(|_| SessionError::WhoamiFailed)?;
-                Self::persist_sync_token(&response.next_batch).await?;
+
+                if !is_light {
+                    Self::persist_sync_token(&response.next_batch).await?;
+                }
 
                 Ok(())
             }
@@ -126,12 +141,80 @@ impl UseSessionState {
         }
     }
 
+    pub async fn refresh_guest(&mut self, client: &Client) -> Result<(), HttpError> {
+        let user = client.whoami().await?;
+
+        self.data.with_mut(|data| {
+            if let Some(session) = data {
+                session.is_guest = user.is_guest;
+            }
+        });
+
+        Ok(())
+    }
+
     pub fn persist_session_file(&self, session_file: &str) -> Result<(), SessionError> {
         <LocalStorage as gloo::storage::Storage>::set("session_file", session_file)
             .map_err(|_| SessionError::SaveFailed)
     }
 
-    async fn persist_sync_token(sync_token: &str) -> anyhow::Result<(), SessionError> {
+    pub fn persist_user_session(&self, user_session: Session) -> Result<String, SessionError> {
+        let serialized_session: String =
+            <LocalStorage as gloo::storage::Storage>::get("session_file")
+                .map_err(|_| SessionError::GetFailed)?;
+
+        let mut full_session: FullSession =
+            serde_json::from_str(&serialized_session).map_err(|_| SessionError::GetFailed)?;
+
+        full_session.user_session = user_session;
+        let serialized_session =
+            serde_json::to_string(&full_session).map_err(|_| SessionError::GetFailed)?;
+
+        self.persist_session_file(&serialized_session)?;
+
+        Ok(serialized_session)
+    }
+
+    /// Returns the id of the uploaded sync filter, uploading it the first time
+    /// and caching the id in the session file for the next ones.
+    pub async fn get_sync_filter(client: &Client) -> Option<String> {
+        let serialized_session: String =
+            <LocalStorage as gloo::storage::Storage>::get("session_file").ok()?;
+
+        let mut full_session: FullSession = serde_json::from_str(&serialized_session).ok()?;
+
+        if let Some(filter) = &full_session.sync_filter {
+            if filter.name.eq(SYNC_FILTER_NAME) {
+                return Some(filter.id.clone());
+            }
+        }
+
+        let filter = upload_sync_filter(client).await.ok()?;
+        let filter_id = filter.id.clone();
+
+        full_session.sync_filter = Some(filter);
+
+        if let Ok(serialized_session) = serde_json::to_string(&full_session) {
+            if let Err(_) =
+                <LocalStorage as gloo::storage::Storage>::set("session_file", serialized_session)
+            {
+                info!("Failed to persist sync filter");
+            }
+        }
+
+        Some(filter_id)
+    }
+
+    pub fn get_sync_token() -> Option<String> {
+        let serialized_session: String =
+            <LocalStorage as gloo::storage::Storage>::get("session_file").ok()?;
+
+        let full_session: FullSession = serde_json::from_str(&serialized_session).ok()?;
+
+        full_session.sync_token
+    }
+
+    pub async fn persist_sync_token(sync_token: &str) -> anyhow::Result<(), SessionError> {
         let serialized_session: Result<String, StorageError> =
             <LocalStorage as gloo::storage::Storage>::get("session_file");
 
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+
+use dioxus::prelude::*;
+use futures::{
+    future::{select, Either},
+    pin_mut, StreamExt,
+};
+use gloo::timers::future::TimeoutFuture;
+use log::info;
+use matrix_sdk::Client;
+
+use crate::{
+    services::{
+        matrix::matrix::Conversations,
+        sliding_sync::{
+            sliding_sync_once, sync_room_state, SlidingSync, SlidingSyncError, ROOMS_WINDOW,
+        },
+    },
+    utils::settings::{get_bool, SLIDING_SYNC},
+};
+
+use super::{
+    use_client::use_client,
+    use_room::use_room,
+    use_sync::{is_document_hidden, HIDDEN_POLL_MS, MAX_BACKOFF_MS, MIN_BACKOFF_MS},
+};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SlidingSyncStatus {
+    /// The user didn't opt in, classic sync does everything
+    #[default]
+    Disabled,
+    Connecting,
+    Running,
+    /// The server has no sliding sync, classic sync took over
+    Unsupported,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SlidingSyncState {
+    pub status: SlidingSyncStatus,
+    pub conversations: Conversations,
+    pub previews: HashMap<String, String>,
+    /// Joined rooms on the server, the window may hold fewer
+    pub count: u64,
+    /// Last index of the room list window
+    pub range_end: u32,
+    pub invites: Vec<String>,
+    /// Rooms brought into the store, the only ones classic sync follows
+    pub synced_rooms: Vec<String>,
+}
+
+impl SlidingSyncState {
+    pub fn new() -> Self {
+        Self {
+            status: if get_bool(SLIDING_SYNC) {
+                SlidingSyncStatus::Connecting
+            } else {
+                SlidingSyncStatus::Disabled
+            },
+            range_end: ROOMS_WINDOW - 1,
+            ..Default::default()
+        }
+    }
+}
+
+pub fn use_sliding_sync() -> UseSlidingSyncState {
+    let state = consume_context::<Signal<SlidingSyncState>>();
+
+    use_hook(move || UseSlidingSyncState { inner: state })
+}
+
+#[derive(Clone, Copy)]
+pub struct UseSlidingSyncState {
+    inner: Signal<SlidingSyncState>,
+}
+
+impl UseSlidingSyncState {
+    pub fn get(&self) -> SlidingSyncState {
+        self.inner.read().clone()
+    }
+
+    /// Whether sliding sync is in charge of the room list
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.inner.peek().status,
+            SlidingSyncStatus::Connecting | SlidingSyncStatus::Running
+        )
+    }
+
+    pub fn load_more(&mut self) {
+        self.inner.with_mut(|state| state.range_end += ROOMS_WINDOW);
+    }
+
+    /// Rooms listed by sliding sync reach the store before being opened,
+    /// once per session
+    pub async fn open_room(
+        &mut self,
+        client: &Client,
+        room_id: &str,
+    ) -> Result<(), SlidingSyncError> {
+        if !self.is_active()
+            || self
+                .inner
+                .peek()
+                .synced_rooms
+                .iter()
+                .any(|id| id.eq(room_id))
+        {
+            return Ok(());
+        }
+
+        sync_room_state(client, room_id).await?;
+
+        self.inner
+            .with_mut(|state| state.synced_rooms.push(room_id.to_string()));
+
+        Ok(())
+    }
+
+    fn update(&mut self, connection: &SlidingSync) {
+        self.inner.with_mut(|state| {
+            state.status = SlidingSyncStatus::Running;
+            state.conversations = connection.conversations(state.range_end);
+            state.previews = connection.previews();
+            state.count = connection.count();
+            state.invites = connection.invites();
+        });
+    }
+
+    fn set_status(&mut self, status: SlidingSyncStatus) {
+        self.inner.with_mut(|state| state.status = status);
+    }
+}
+
+/// Keeps the sliding sync connection open when the user opted in, following
+/// the room list window and the active room. Started next to `use_sync_loop`.
+pub fn use_sliding_sync_loop() {
+    let client = use_client();
+    let room = use_room();
+    let mut sliding = use_sliding_sync();
+
+    let range_end = use_memo(move || sliding.get().range_end);
+    let active_room = use_memo(move || room.get().id);
+
+    let task = use_coroutine(|mut rx: UnboundedReceiver<()>| async move {
+        if !sliding.is_active() {
+            return;
+        }
+
+        let mut connection = SlidingSync::default();
+        let mut backoff = MIN_BACKOFF_MS;
+
+        loop {
+            if is_document_hidden() {
+                TimeoutFuture::new(HIDDEN_POLL_MS).await;
+                continue;
+            }
+
+            let matrix_client = client.get();
+            let active_room = active_room.peek().clone();
+            let subscriptions = if active_room.is_empty() {
+                vec![]
+            } else {
+                vec![active_room]
+            };
+
+            let request = sliding_sync_once(
+                &matrix_client,
+                connection.pos(),
+                connection.request_body(*range_end.peek(), &subscriptions),
+            );
+            let changed = rx.next();
+            pin_mut!(request);
+
+            let result = match select(request, changed).await {
+                Either::Left((result, _)) => result,
+                // The window or the active room changed, asked again with the same `pos`
+                Either::Right(_) => continue,
+            };
+
+            match result {
+                Ok(response) => {
+                    backoff = MIN_BACKOFF_MS;
+                    connection.apply(response);
+
+                    // Invitations are answered from the store, they get there first
+                    for room_id in connection.invites() {
+                        if let Err(e) = sliding.open_room(&matrix_client, &room_id).await {
+                            info!("Failed to sync the invited room {room_id}: {e:?}");
+                        }
+                    }
+
+                    sliding.update(&connection);
+                }
+                Err(SlidingSyncError::UnknownPos) => connection.reset(),
+                Err(SlidingSyncError::Unsupported) => {
+                    info!("Sliding sync unavailable, using classic sync");
+                    sliding.set_status(SlidingSyncStatus::Unsupported);
+                    break;
+                }
+                Err(e) => {
+                    info!("Sliding sync failed, retrying in {backoff}ms: {e:?}");
+                    TimeoutFuture::new(backoff).await;
+
+                    backoff = (backoff * 2).min(MAX_BACKOFF_MS);
+                }
+            }
+        }
+    });
+
+    use_effect(move || {
+        let _ = range_end();
+        let _ = active_room();
+
+        task.send(());
+    });
+}
@@ -0,0 +1,180 @@
+use dioxus::prelude::*;
+use futures::{
+    future::{select, Either},
+    pin_mut, StreamExt,
+};
+use gloo::timers::future::TimeoutFuture;
+use log::info;
+use matrix_sdk::config::SyncSettings;
+use ruma::{api::client::sync::sync_events, OwnedRoomId};
+use std::time::Duration;
+use web_sys::window;
+
+use crate::services::filter::{
+    limited_sync_filter_definition, sync_filter_definition, timeline_event_types,
+};
+
+use super::{
+    use_client::use_client,
+    use_session::UseSessionState,
+    use_sliding_sync::{use_sliding_sync, SlidingSyncStatus},
+};
+
+// Long-poll timeout sent to the server, it answers earlier when there are new events
+const SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+pub const MIN_BACKOFF_MS: u32 = 1_000;
+pub const MAX_BACKOFF_MS: u32 = 30_000;
+pub const HIDDEN_POLL_MS: u32 = 1_000;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SyncStatus {
+    #[default]
+    Idle,
+    Running,
+    Reconnecting {
+        attempt: u32,
+    },
+    Paused,
+}
+
+pub fn use_sync() -> UseSyncState {
+    let status = consume_context::<Signal<SyncStatus>>();
+
+    use_hook(move || UseSyncState { inner: status })
+}
+
+#[derive(Clone, Copy)]
+pub struct UseSyncState {
+    inner: Signal<SyncStatus>,
+}
+
+impl UseSyncState {
+    pub fn get(&self) -> SyncStatus {
+        self.inner.read().clone()
+    }
+
+    pub fn is_reconnecting(&self) -> bool {
+        matches!(self.get(), SyncStatus::Reconnecting { .. })
+    }
+
+    fn set(&mut self, status: SyncStatus) {
+        if !self.inner.peek().eq(&status) {
+            *self.inner.write() = status;
+        }
+    }
+}
+
+/// Keeps the client synced for as long as the calling component is mounted.
+/// Must be started once, from the layout shared by every logged in route.
+pub fn use_sync_loop() {
+    let client = use_client();
+    let mut sync = use_sync();
+    let sliding = use_sliding_sync();
+
+    let synced_rooms = use_memo(move || sliding.get().synced_rooms);
+    let status = use_memo(move || sliding.get().status);
+
+    let task = use_coroutine(|mut rx: UnboundedReceiver<()>| async move {
+        // While sliding sync lists the rooms, classic sync only follows the opened
+        // ones. Its token misses every other room, so it's kept in memory only.
+        let mut is_light = sliding.is_active();
+        let mut sync_token = if is_light {
+            None
+        } else {
+            UseSessionState::get_sync_token()
+        };
+        let filter_id = UseSessionState::get_sync_filter(&client.get()).await;
+        let types = timeline_event_types();
+        let mut backoff = MIN_BACKOFF_MS;
+        let mut attempt = 0;
+
+        loop {
+            if is_document_hidden() {
+                sync.set(SyncStatus::Paused);
+                TimeoutFuture::new(HIDDEN_POLL_MS).await;
+                continue;
+            }
+
+            // Back from a light sync, the store catches up from the last full one
+            if is_light && !sliding.is_active() {
+                is_light = false;
+                sync_token = UseSessionState::get_sync_token();
+            }
+
+            let rooms = synced_rooms
+                .peek()
+                .iter()
+                .filter_map(|room_id| OwnedRoomId::try_from(room_id.as_str()).ok())
+                .collect::<Vec<_>>();
+
+            let filter = match (&filter_id, is_light) {
+                (_, true) => sync_events::v3::Filter::FilterDefinition(
+                    limited_sync_filter_definition(&types, &rooms),
+                ),
+                (Some(id), false) => sync_events::v3::Filter::FilterId(id),
+                (None, false) => {
+                    sync_events::v3::Filter::FilterDefinition(sync_filter_definition(&types))
+                }
+            };
+
+            let mut sync_settings = SyncSettings::default().filter(filter).timeout(SYNC_TIMEOUT);
+
+            if let Some(token) = &sync_token {
+                sync_settings = sync_settings.token(token.clone());
+            }
+
+            let matrix_client = client.get();
+            let request = matrix_client.sync_once(sync_settings);
+            let changed = rx.next();
+            pin_mut!(request);
+
+            let result = match select(request, changed).await {
+                Either::Left((result, _)) => result,
+                // A room was opened from sliding sync, asked again with it in the filter
+                Either::Right(_) => continue,
+            };
+
+            match result {
+                Ok(response) => {
+                    attempt = 0;
+                    backoff = MIN_BACKOFF_MS;
+                    sync.set(SyncStatus::Running);
+
+                    if !is_light {
+                        if let Err(_) =
+                            UseSessionState::persist_sync_token(&response.next_batch).await
+                        {
+                            info!("Failed to persist sync token");
+                        }
+                    }
+
+                    sync_token = Some(response.next_batch);
+                }
+                Err(err) => {
+                    attempt += 1;
+                    info!("Sync failed, retrying in {backoff}ms: {err}");
+
+                    sync.set(SyncStatus::Reconnecting { attempt });
+                    TimeoutFuture::new(backoff).await;
+
+                    backoff = (backoff * 2).min(MAX_BACKOFF_MS);
+                }
+            }
+        }
+    });
+
+    use_effect(move || {
+        // A room opened from sliding sync joins the filter of the next request,
+        // and falling back to classic sync switches to the full one
+        if !synced_rooms().is_empty() || status().eq(&SlidingSyncStatus::Unsupported) {
+            task.send(());
+        }
+    });
+}
+
+pub fn is_document_hidden() -> bool {
+    window()
+        .and_then(|w| w.document())
+        .map(|d| d.hidden())
+        .unwrap_or(false)
+}
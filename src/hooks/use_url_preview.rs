@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use dioxus::prelude::*;
+use matrix_sdk::ruma::RoomId;
+
+use crate::{
+    services::matrix::matrix::{url_preview, UrlPreview},
+    utils::settings::{get_bool, ENCRYPTED_URL_PREVIEWS},
+};
+
+use super::{
+    use_client::{use_client, UseClientState},
+    use_room::{use_room, UseRoomState},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PreviewState {
+    Loading,
+    Loaded(UrlPreview),
+    Unavailable,
+}
+
+/// Previews already asked for, by url
+#[derive(Default, Debug, Clone)]
+pub struct UrlPreviews {
+    pub previews: HashMap<String, PreviewState>,
+}
+
+pub fn use_url_preview() -> UseUrlPreviewState {
+    let previews = consume_context::<Signal<UrlPreviews>>();
+    let client = use_client();
+    let room = use_room();
+
+    use_hook(move || UseUrlPreviewState {
+        inner: previews,
+        client,
+        room,
+    })
+}
+
+#[derive(Clone, Copy)]
+pub struct UseUrlPreviewState {
+    inner: Signal<UrlPreviews>,
+    client: UseClientState,
+    room: UseRoomState,
+}
+
+impl UseUrlPreviewState {
+    pub fn get(&self, url: &str) -> Option<PreviewState> {
+        self.inner.read().previews.get(url).cloned()
+    }
+
+    /// Fetches the preview once per url. Links of encrypted rooms are kept
+    /// from the homeserver unless the user allowed it
+    pub async fn load(&mut self, url: &str) {
+        if self.get(url).is_some() || !self.is_allowed().await {
+            return;
+        }
+
+        self.set(url, PreviewState::Loading);
+
+        let state = match url_preview(&self.client.get(), url).await {
+            Ok(preview) => PreviewState::Loaded(preview),
+            Err(_) => PreviewState::Unavailable,
+        };
+
+        self.set(url, state);
+    }
+
+    async fn is_allowed(&self) -> bool {
+        if get_bool(ENCRYPTED_URL_PREVIEWS) {
+            return true;
+        }
+
+        let Ok(room_id) = RoomId::parse(self.room.get().id) else {
+            return false;
+        };
+
+        // Rooms not joined, like previewed ones, are public
+        match self.client.get().get_room(&room_id) {
+            Some(room) => !room.is_encrypted().await.unwrap_or(true),
+            None => true,
+        }
+    }
+
+    fn set(&mut self, url: &str, state: PreviewState) {
+        self.inner.with_mut(|previews| {
+            previews.previews.insert(url.to_string(), state);
+        });
+    }
+}
@@ -13,7 +13,9 @@ pub mod hooks {
     pub mod use_auth;
     pub mod use_chat;
     pub mod use_client;
+    pub mod use_ignored_users;
     pub mod use_init_app;
+    pub mod use_lightbox;
     pub mod use_listen_invitation;
     pub mod use_listen_message;
     pub mod use_messages;
@@ -21,17 +23,26 @@ pub mod hooks {
     pub mod use_notification;
     pub mod use_public;
     pub mod use_reply;
+    pub mod use_report;
     pub mod use_room;
     pub mod use_room_preview;
     pub mod use_rooms;
     pub mod use_send_attach;
     pub mod use_send_message;
+    pub mod use_send_queue;
     pub mod use_session;
+    pub mod use_sliding_sync;
+    pub mod use_sync;
     pub mod use_thread;
+    pub mod use_url_preview;
 }
 
 pub mod services {
+    pub mod filter;
     pub mod matrix;
+    pub mod media;
+    pub mod sliding_sync;
+    pub mod timeline_cache;
 }
 
 pub mod utils {
@@ -40,8 +51,12 @@ pub mod utils {
     pub mod get_param;
     pub mod i18n_get_key_value;
     pub mod matrix;
+    pub mod media;
     pub mod nice_bytes;
+    pub mod settings;
     pub mod sync_room;
+    pub mod upload;
+    pub mod url_preview;
     pub mod vec_to_url;
 }
 
@@ -52,6 +67,7 @@ pub mod pages {
     pub mod profile;
     pub mod route;
     pub mod signup;
+    pub mod upgrade;
     pub mod welcome;
 }
 
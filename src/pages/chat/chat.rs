@@ -1,14 +1,22 @@
 use dioxus::prelude::*;
 use dioxus_router::prelude::*;
+use dioxus_std::{i18n::use_i18, translate};
 
-use crate::components::molecules::modal::{ModalForm, RoomType};
-use crate::components::molecules::Modal;
+use crate::components::molecules::modal::{ModalForm, ReportForm, RoomType};
+use crate::components::molecules::{Modal, ReportModal};
+use crate::hooks::use_client::use_client;
 use crate::hooks::use_listen_invitation::use_listen_invitation;
 use crate::hooks::use_listen_message::use_listen_message;
 use crate::hooks::use_modal::use_modal;
+use crate::hooks::use_notification::{
+    use_notification, NotificationHandle, NotificationItem, NotificationType,
+};
+use crate::hooks::use_report::use_report;
 use crate::pages::route::Route;
 
-use crate::services::matrix::matrix::TimelineRelation;
+use crate::services::matrix::matrix::{
+    report_content, ReportError, ReportTarget, TimelineRelation,
+};
 
 use matrix_sdk::room::Room;
 
@@ -26,12 +34,54 @@ pub struct MessageEvent {
 
 #[component]
 pub fn Chat() -> Element {
+    let i18 = use_i18();
     let mut modal = use_modal();
     let navigator = use_navigator();
+    let client = use_client();
+    let mut notification = use_notification();
+    let mut report = use_report();
 
     use_listen_message();
     use_listen_invitation();
 
+    let on_handle_report = move |form: ReportForm| {
+        let Some(target) = report.get() else {
+            return;
+        };
+
+        report.close();
+
+        spawn(async move {
+            let reason = (!form.reason.is_empty()).then_some(form.reason.as_str());
+
+            match report_content(&client.get(), &target, reason, form.severity.score()).await {
+                Ok(_) => notification.handle_notification(NotificationItem {
+                    title: translate!(i18, "modal.report.sent.title"),
+                    body: translate!(i18, "modal.report.sent.body"),
+                    show: true,
+                    handle: NotificationHandle {
+                        value: NotificationType::None,
+                    },
+                }),
+                Err(e) => {
+                    let message = match e {
+                        ReportError::InvalidRoomId => translate!(i18, "chat.common.error.room_id"),
+                        ReportError::InvalidUserId => translate!(i18, "chat.common.error.user_id"),
+                        ReportError::InvalidEventId => translate!(i18, "modal.report.errors.event"),
+                        ReportError::Unsupported => {
+                            translate!(i18, "modal.report.errors.unsupported")
+                        }
+                        ReportError::SessionNotFound | ReportError::RequestFailed => {
+                            translate!(i18, "modal.report.errors.request")
+                        }
+                    };
+
+                    notification.handle_error(&message);
+                }
+            }
+        });
+    };
+
     rsx! {
         if modal.get().show {
             Modal {
@@ -46,11 +96,26 @@ pub fn Chat() -> Element {
                             navigator.push(Route::RoomGroup {});
                         }
                         RoomType::CHANNEL => modal.hide(),
+                        RoomType::SPACE => {
+                            modal.hide();
+                            navigator.push(Route::SpaceNew {});
+                        }
                     }
                 },
                 on_close: move |_| { modal.hide() }
             }
         }
+        if let Some(target) = report.get() {
+            ReportModal {
+                title: match target {
+                    ReportTarget::Event { .. } => translate!(i18, "modal.report.title.event"),
+                    ReportTarget::Room { .. } => translate!(i18, "modal.report.title.room"),
+                    ReportTarget::User { .. } => translate!(i18, "modal.report.title.user"),
+                },
+                on_confirm: on_handle_report,
+                on_close: move |_| { report.close() }
+            }
+        }
         Outlet::<Route> {}
     }
 }
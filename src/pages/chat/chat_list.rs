@@ -1,6 +1,8 @@
 use dioxus::prelude::*;
+use dioxus_router::prelude::use_navigator;
 use dioxus_std::{i18n::use_i18, translate};
-use futures::TryFutureExt;
+use futures::{StreamExt, TryFutureExt};
+use matrix_sdk::ruma::RoomId;
 use std::collections::HashMap;
 use wasm_bindgen::JsCast;
 use web_sys::HtmlElement;
@@ -8,12 +10,16 @@ use web_sys::HtmlElement;
 use crate::{
     components::{
         atoms::{
-            helper::HelperData, input::InputType, message::Messages, room::RoomItem, Helper,
-            MessageInput, Space, SpaceSkeleton,
+            button::Variant,
+            helper::HelperData,
+            input::InputType,
+            message::Messages,
+            room::{InvitedRoom, RoomItem},
+            Button, Helper, MessageInput, Space, SpaceSkeleton,
         },
         molecules::{
             rooms::{CurrentRoom, FormRoomEvent},
-            RoomsList,
+            RoomsList, SpaceTree,
         },
         organisms::{
             chat::{
@@ -30,19 +36,20 @@ use crate::{
         use_public::{use_public, PublicState},
         use_room::use_room,
         use_room_preview::{use_room_preview, PreviewRoom},
-        use_rooms::{use_rooms, RoomsList},
+        use_rooms::use_rooms,
         use_session::use_session,
+        use_sliding_sync::{use_sliding_sync, SlidingSyncStatus},
     },
-    pages::chat::chat::MessageItem,
+    pages::{chat::chat::MessageItem, route::Route},
     services::matrix::matrix::{
-        invited_rooms, list_rooms_and_spaces, public_rooms_and_spaces, Conversations,
+        invited_rooms, join_room, list_rooms_and_spaces, reject_invitation, space_hierarchy,
+        Conversations, SpaceHierarchy,
     },
 };
 
 pub enum ChatListError {
     SessionNotFound,
     InvitedRooms,
-    PublicRooms,
 }
 
 #[component]
@@ -56,6 +63,7 @@ pub fn ChatList() -> Element {
     let mut rooms_list = use_rooms();
     let mut preview = use_room_preview();
     let mut messages = use_messages();
+    let mut sliding = use_sliding_sync();
 
     let mut room_tabs = use_signal::<HashMap<CurrentRoom, Messages>>(|| HashMap::new());
 
@@ -64,66 +72,58 @@ pub fn ChatList() -> Element {
     let mut spaces = use_signal::<HashMap<RoomItem, Vec<RoomItem>>>(|| HashMap::new());
     let mut pattern = use_signal(|| String::new());
     let mut rooms_filtered = use_signal(|| Vec::new());
-    let mut selected_space = use_signal::<String>(|| String::new());
+    // Room id of the selected space, None while on Home
+    let mut selected_space = use_signal::<Option<String>>(|| None);
+    let mut space_tree = use_signal::<Option<SpaceHierarchy>>(|| None);
     let mut is_loading = use_signal(|| false);
     let mut chat_list_wrapper_ref = use_signal::<Option<Box<HtmlElement>>>(|| None);
 
+    let navigator = use_navigator();
     let mut title_header = consume_context::<Signal<TitleHeaderMain>>();
 
     let mut room_lifecycle = room.clone();
 
     use_drop(move || room_lifecycle.default());
 
+    let mut set_conversations = move |Conversations { rooms: r, spaces: s }: Conversations| {
+        all_rooms.set(
+            s.values()
+                .flatten()
+                .chain(r.iter())
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+
+        // The rooms of a selected space stay until going back Home
+        if selected_space.peek().is_none() {
+            rooms_list.set_joined(r.clone());
+
+            if pattern.peek().is_empty() {
+                rooms_filtered.set(r.clone());
+            }
+        }
+
+        rooms.set(r);
+        spaces.set(s);
+    };
+
     use_coroutine(|_: UnboundedReceiver<()>| {
         async move {
             is_loading.set(true);
 
-            let session_data = session.get().ok_or(ChatListError::SessionNotFound)?;
-
             let invited = invited_rooms(&client.get())
                 .await
                 .map_err(|_| ChatListError::InvitedRooms)?;
 
-            let Conversations {
-                rooms: r,
-                spaces: s,
-            } = list_rooms_and_spaces(&client.get(), session_data).await;
+            rooms_list.set_invited(invited);
 
-            let public_rooms = public_rooms_and_spaces(&client.get(), None, None, None)
-                .await
-                .map_err(|_| ChatListError::PublicRooms)?;
-
-            rooms.set(r.clone());
-            spaces.set(s.clone());
-
-            s.iter().for_each(|space| {
-                all_rooms.with_mut(|all_r| {
-                    all_r.extend_from_slice(&space.1);
-                })
-            });
-
-            all_rooms.with_mut(|all_r| {
-                all_r.extend_from_slice(&r.clone());
-            });
-
-            rooms_list.set(RoomsList {
-                public: public_rooms.rooms,
-                invited,
-                joined: r.clone(),
-            });
-            rooms_filtered.set(r);
-
-            selected_space.set(translate!(i18, "chat.list.home"));
             title_header.write().title = translate!(i18, "chat.list.home");
 
-            is_loading.set(false);
-
             Ok::<(), ChatListError>(())
         }
         .unwrap_or_else(move |e: ChatListError| {
             let message = match e {
                 ChatListError::SessionNotFound => translate!(i18, "chat.session.error.not_found"),
-                ChatListError::PublicRooms => translate!(i18, "chat.list.errors.public_rooms"),
                 ChatListError::InvitedRooms => translate!(i18, "chat.list.errors.invited_rooms"),
             };
 
@@ -131,6 +131,69 @@ pub fn ChatList() -> Element {
         })
     });
 
+    // Walks every room of the store, when sliding sync is off or missing on the server
+    let task_classic_rooms = use_coroutine(|mut rx: UnboundedReceiver<()>| async move {
+        while rx.next().await.is_some() {
+            let Some(session_data) = session.get() else {
+                notification.handle_error(&translate!(i18, "chat.session.error.not_found"));
+                continue;
+            };
+
+            set_conversations(list_rooms_and_spaces(&client.get(), session_data).await);
+
+            is_loading.set(false);
+        }
+    });
+
+    // Follows the window of sliding sync as it changes
+    use_effect(move || {
+        let state = sliding.get();
+
+        match state.status {
+            SlidingSyncStatus::Running => {
+                set_conversations(state.conversations);
+                is_loading.set(false);
+            }
+            SlidingSyncStatus::Disabled | SlidingSyncStatus::Unsupported => {
+                task_classic_rooms.send(())
+            }
+            SlidingSyncStatus::Connecting => {}
+        }
+    });
+
+    // Sliding sync brings invitations into the store, listed from there
+    let invites = use_memo(move || sliding.get().invites);
+
+    use_effect(move || {
+        let _ = invites();
+
+        if sliding.is_active() {
+            spawn(async move {
+                if let Ok(invited) = invited_rooms(&client.get()).await {
+                    rooms_list.set_invited(invited);
+                }
+            });
+        }
+    });
+
+    // Loads the nested rooms and subspaces of the selected space
+    let task_space_tree = use_coroutine(|mut rx: UnboundedReceiver<String>| async move {
+        while let Some(space_id) = rx.next().await {
+            match space_hierarchy(&client.get(), &space_id).await {
+                Ok(hierarchy) => {
+                    // The user may have moved to another space meanwhile
+                    if selected_space().eq(&Some(space_id.clone())) {
+                        space_tree.set(Some(hierarchy));
+                    }
+                }
+                Err(e) => {
+                    log::info!("failed to load space hierarchy {e:?}");
+                    notification.handle_error(&translate!(i18, "chat.list.errors.space_hierarchy"));
+                }
+            }
+        }
+    });
+
     enum ScrollToPosition {
         Top,
         Bottom,
@@ -156,13 +219,76 @@ pub fn ChatList() -> Element {
         room.default();
     };
 
+    let on_decline_all_invitations = move |_| {
+        spawn(async move {
+            let mut failed = false;
+
+            for invitation in rooms_list.get_invited() {
+                match reject_invitation(&client.get(), &invitation.room.id, None).await {
+                    Ok(_) => {
+                        let _ = rooms_list.remove_invited(&invitation.room.id);
+                    }
+                    Err(_) => failed = true,
+                }
+            }
+
+            if let PreviewRoom::Invited(_) = preview.get() {
+                preview.default();
+            }
+
+            if failed {
+                notification.handle_error(&translate!(i18, "chat.list.errors.decline_all"));
+            }
+        });
+    };
+
     let on_click_room = move |evt: FormRoomEvent| {
-        room.set(evt.room.clone());
-        room_tabs.with_mut(|tabs| tabs.insert(evt.room, vec![]));
-        messages.reset();
-        preview.default();
+        spawn(async move {
+            // Rooms listed by sliding sync may not be in the store yet
+            if let Err(e) = sliding.open_room(&client.get(), &evt.room.id).await {
+                log::info!("failed to sync the opened room {e:?}");
+                notification.handle_error(&translate!(i18, "chat.list.errors.open_room"));
+                return;
+            }
 
-        on_scroll_chat_list_wrapper(ScrollToPosition::Right);
+            room.set(evt.room.clone());
+            room_tabs.with_mut(|tabs| tabs.insert(evt.room, vec![]));
+            messages.reset();
+            preview.default();
+
+            on_scroll_chat_list_wrapper(ScrollToPosition::Right);
+        });
+    };
+
+    let on_join_space_room = move |evt: FormRoomEvent| {
+        spawn(async move {
+            let Ok(room_id) = RoomId::parse(&evt.room.id) else {
+                notification.handle_error(&translate!(i18, "chat.list.errors.space_join"));
+                return;
+            };
+
+            let Ok(_) = join_room(&client.get(), &room_id).await else {
+                notification.handle_error(&translate!(i18, "chat.list.errors.space_join"));
+                return;
+            };
+
+            let joined = RoomItem {
+                avatar_uri: evt.room.avatar_uri.clone(),
+                id: evt.room.id.clone(),
+                name: evt.room.name.clone(),
+                is_public: true,
+                is_direct: false,
+            };
+
+            rooms.with_mut(|r| r.push(joined.clone()));
+            all_rooms.with_mut(|r| r.push(joined));
+
+            if let Some(tree) = space_tree() {
+                task_space_tree.send(tree.space.id);
+            }
+
+            on_click_room(evt);
+        });
     };
 
     let on_click_helper = move |_| {
@@ -212,7 +338,8 @@ pub fn ChatList() -> Element {
                                 on_click: move |_| {
                                     rooms_list.set_joined(rooms().clone());
                                     rooms_filtered.set(rooms().clone());
-                                    selected_space.set(translate!(i18, "chat.list.home"));
+                                    space_tree.set(None);
+                                    selected_space.set(None);
                                     title_header.write().title = translate!(i18, "chat.list.home");
                                     if !rooms().iter().any(|r| { room.get().id.eq(&r.id) }) {
                                         room.default()
@@ -227,7 +354,9 @@ pub fn ChatList() -> Element {
                                     on_click: move |_| {
                                         rooms_list.set_joined(value.clone());
                                         rooms_filtered.set(value.clone());
-                                        selected_space.set(space.name.clone());
+                                        space_tree.set(None);
+                                        task_space_tree.send(space.id.clone());
+                                        selected_space.set(Some(space.id.clone()));
                                         title_header.write().title = space.name.clone();
                                         if !value.iter().any(|r| { room.get().id.eq(&r.id) }) {
                                             room.default()
@@ -283,9 +412,18 @@ pub fn ChatList() -> Element {
                            div {
                                 class: "chat-list__item",
                                 h2 { class: "header__title header__title--sticky", {translate!(i18, "chat.list.invitate")} }
+                                div { class: "chat-list__space-manage",
+                                    Button {
+                                        text: translate!(i18, "chat.list.decline_all"),
+                                        variant: Variant::Tertiary,
+                                        status: None,
+                                        on_click: on_decline_all_invitations
+                                    }
+                                }
                                 RoomsList {
-                                    rooms: rooms_list.get_invited(),
+                                    rooms: rooms_list.get_invited().into_iter().map(|invited| invited.room).collect::<Vec<_>>(),
                                     is_loading: is_loading(),
+                                    previews: invitation_descriptions(&rooms_list.get_invited(), &translate!(i18, "chat.preview.invited.by")),
                                     on_submit: on_click_invitation
                                 }
                            }
@@ -294,7 +432,44 @@ pub fn ChatList() -> Element {
                         div {
                             class: "chat-list__item",
                             h2 { class: "header__title header__title--sticky", {translate!(i18, "chat.list.rooms")} }
-                            RoomsList { rooms: rooms_filtered(), is_loading: is_loading(), on_submit: on_click_room }
+                            if let (Some(hierarchy), true) = (space_tree(), pattern().is_empty()) {
+                                div { class: "chat-list__space-manage",
+                                    Button {
+                                        text: translate!(i18, "chat.list.space.manage"),
+                                        variant: Variant::Tertiary,
+                                        status: None,
+                                        on_click: {
+                                            let id = hierarchy.space.id.clone();
+                                            move |_| {
+                                                navigator.push(Route::SpaceManage { id: id.clone() });
+                                            }
+                                        }
+                                    }
+                                }
+                                SpaceTree {
+                                    hierarchy,
+                                    on_submit: on_click_room,
+                                    on_join: on_join_space_room
+                                }
+                            } else {
+                                RoomsList {
+                                    rooms: rooms_filtered(),
+                                    is_loading: is_loading(),
+                                    previews: sliding.get().previews,
+                                    on_submit: on_click_room
+                                }
+                            }
+
+                            if pattern().is_empty() && sliding.get().status.eq(&SlidingSyncStatus::Running) && u64::from(sliding.get().range_end + 1) < sliding.get().count {
+                                div { class: "chat-list__load-more",
+                                    Button {
+                                        text: translate!(i18, "chat.list.load_more"),
+                                        variant: Variant::Secondary,
+                                        status: None,
+                                        on_click: move |_| sliding.load_more()
+                                    }
+                                }
+                            }
                         }
                     }
                 }
@@ -331,3 +506,22 @@ pub fn ChatList() -> Element {
         }
     }
 }
+
+// Who sent each invitation and why, shown in place of the last message
+fn invitation_descriptions(
+    invitations: &[InvitedRoom],
+    invited_by: &str,
+) -> HashMap<String, String> {
+    invitations
+        .iter()
+        .filter_map(|invited| {
+            let inviter = invited.inviter_name.as_ref()?;
+            let description = match &invited.reason {
+                Some(reason) => format!("{invited_by} {inviter}: {reason}"),
+                None => format!("{invited_by} {inviter}"),
+            };
+
+            Some((invited.room.id.clone(), description))
+        })
+        .collect()
+}
@@ -20,7 +20,7 @@ use crate::{
         use_room::use_room,
         use_session::use_session,
     },
-    pages::chat::room::new::CreationStatus,
+    pages::{chat::room::new::CreationStatus, route::Route},
     services::matrix::matrix::{create_room, find_user_by_id},
     utils::{
         i18n_get_key_value::i18n_get_key_value,
@@ -182,6 +182,8 @@ pub fn RoomGroup() -> Element {
                     data: content.clone(),
                     content_type,
                     size,
+                    caption: String::new(),
+                    compressed: None,
                 }));
 
                 Ok::<(), AttachError>(())
@@ -190,6 +192,7 @@ pub fn RoomGroup() -> Element {
                 let message_error = match e {
                     AttachError::NotFound => translate!(i18, "chat.input_message.not_found"),
                     AttachError::UncoverType => translate!(i18, "chat.input_message.file_type"),
+                    AttachError::TooLarge(_) => translate!(i18, "chat.input_message.too_large"),
                     AttachError::UnknownContent => translate!(i18, "chat.input_message.not_found"),
                 };
 
@@ -323,7 +326,9 @@ pub fn RoomGroup() -> Element {
                     Guest {
                         description: translate!(i18, "chat.guest.signup.description"),
                         cta: translate!(i18, "chat.guest.signup.cta"),
-                        on_click: move |_| {}
+                        on_click: move |_| {
+                            navigation.push(Route::Upgrade {});
+                        }
                     }
                 } else {
                     div { class: "group__cta__wrapper row",
@@ -1,3 +1,5 @@
 pub mod group;
 pub mod new;
 pub mod room;
+pub mod space;
+pub mod space_manage;
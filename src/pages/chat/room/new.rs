@@ -9,11 +9,14 @@ use crate::{
         molecules::{rooms::CurrentRoom, Guest},
     },
     hooks::{
-        use_auth::use_auth, use_client::use_client, use_init_app::BeforeSession,
+        use_client::use_client, use_ignored_users::use_ignored_users,
         use_notification::use_notification, use_room::use_room, use_session::use_session,
     },
-    pages::chat::room::group::{CreateRoomError, Profile},
-    services::matrix::matrix::{create_room, find_user_by_id},
+    pages::{
+        chat::room::group::{CreateRoomError, Profile},
+        route::Route,
+    },
+    services::matrix::matrix::{create_room, find_direct_room, find_user_by_id, IgnoreError},
     utils::sync_room::sync_created_room,
 };
 use futures_util::{StreamExt, TryFutureExt};
@@ -33,9 +36,7 @@ pub fn RoomNew() -> Element {
     let mut notification = use_notification();
     let mut room = use_room();
     let session = use_session();
-    let mut auth = use_auth();
-
-    let mut before_session = consume_context::<Signal<BeforeSession>>();
+    let mut ignored = use_ignored_users();
 
     let mut user_id = use_signal::<String>(|| String::from(""));
     let mut user = use_signal::<Option<Profile>>(|| None);
@@ -53,6 +54,27 @@ pub fn RoomNew() -> Element {
         }
     });
 
+    let on_handle_ignore = move |_| {
+        spawn(async move {
+            let id = user_id().trim().to_string();
+
+            let result = if ignored.is_ignored(&id) {
+                ignored.unignore(&id).await
+            } else {
+                ignored.ignore(&id).await
+            };
+
+            if let Err(e) = result {
+                let message = match e {
+                    IgnoreError::InvalidUserId => translate!(i18, "chat.common.error.user_id"),
+                    IgnoreError::RequestFailed => translate!(i18, "chat.ignore.errors.update"),
+                };
+
+                notification.handle_error(&message);
+            }
+        });
+    };
+
     let on_handle_create = move |_| {
         if session.is_guest() {
             return;
@@ -60,6 +82,25 @@ pub fn RoomNew() -> Element {
 
         spawn({
             async move {
+                let profile = user().clone().ok_or(CreateRoomError::InvalidUserId)?;
+
+                // Talking again to someone opens the DM we already have
+                let existing = find_direct_room(&client.get(), &user_id())
+                    .await
+                    .map_err(|_| CreateRoomError::InvalidUserId)?;
+
+                if let Some(direct) = existing {
+                    room.set(CurrentRoom {
+                        id: direct.room_id().to_string(),
+                        name: profile.displayname,
+                        avatar_uri: profile.avatar_uri,
+                    });
+
+                    navigation.go_back();
+
+                    return Ok(());
+                }
+
                 status.set(CreationStatus::Creating);
                 let u = UserId::parse(&user_id()).map_err(|_| CreateRoomError::InvalidUserId)?;
 
@@ -69,8 +110,6 @@ pub fn RoomNew() -> Element {
 
                 let room_id = room_meta.room_id.to_string();
 
-                let profile = user().clone().ok_or(CreateRoomError::InvalidUserId)?;
-
                 status.set(CreationStatus::Ok);
 
                 sync_created_room(&room_meta.room_id, &client.get()).await;
@@ -137,6 +176,16 @@ pub fn RoomNew() -> Element {
                             description: r#"{translate!(i18, "dm.description")} {user.displayname}"#,
                             on_click: on_handle_create
                         }
+                        Button {
+                            text: if ignored.is_ignored(user_id().trim()) {
+                                translate!(i18, "profile.ignored.cta_unignore")
+                            } else {
+                                translate!(i18, "chat.menu.ignore")
+                            },
+                            variant: Variant::Tertiary,
+                            on_click: on_handle_ignore,
+                            status: None
+                        }
                     }
                 )}
             }
@@ -145,8 +194,7 @@ pub fn RoomNew() -> Element {
                     description: translate!(i18, "chat.guest.signup.description"),
                     cta: translate!(i18, "chat.guest.signup.cta"),
                     on_click: move |_| {
-                        auth.set_logged_in(false);
-                        *before_session.write() = BeforeSession::Signup;
+                        navigation.push(Route::Upgrade {});
                     }
                 }
             }
@@ -0,0 +1,258 @@
+use std::ops::Deref;
+
+use dioxus::prelude::*;
+use dioxus_router::prelude::use_navigator;
+use dioxus_std::{i18n::use_i18, translate};
+use futures_util::TryFutureExt;
+
+use crate::{
+    components::{
+        atoms::{
+            attach::AttachType, button::Variant, Attach, Avatar, Button, Header, MessageInput,
+        },
+        molecules::Guest,
+    },
+    hooks::{
+        use_attach::{use_attach, AttachError, AttachFile},
+        use_client::use_client,
+        use_notification::use_notification,
+        use_session::use_session,
+    },
+    pages::{
+        chat::room::{group::CreateRoomError, new::CreationStatus},
+        route::Route,
+    },
+    services::matrix::matrix::create_space,
+    utils::sync_room::sync_created_room,
+};
+
+pub fn SpaceNew() -> Element {
+    use_context_provider::<Signal<Option<AttachFile>>>(|| Signal::new(None));
+
+    let i18 = use_i18();
+
+    let navigation = use_navigator();
+    let client = use_client();
+    let mut attach = use_attach();
+    let mut notification = use_notification();
+    let session = use_session();
+
+    let mut space_name = use_signal::<String>(|| String::from(""));
+    let mut space_topic = use_signal::<String>(|| String::from(""));
+    let mut is_public = use_signal::<bool>(|| false);
+    let mut status = use_signal::<CreationStatus>(|| CreationStatus::Start);
+
+    let on_handle_create = move |_| {
+        if session.is_guest() {
+            return;
+        }
+
+        spawn({
+            async move {
+                status.set(CreationStatus::Creating);
+
+                let avatar = attach.get().map(|file| file.data);
+                let topic = space_topic();
+                let topic = if topic.is_empty() {
+                    None
+                } else {
+                    Some(topic.as_str())
+                };
+
+                let space_meta =
+                    create_space(&client.get(), &space_name(), topic, is_public(), avatar)
+                        .await
+                        .map_err(|_| CreateRoomError::ServerError)?;
+
+                status.set(CreationStatus::Ok);
+
+                sync_created_room(&space_meta.room_id, &client.get()).await;
+
+                navigation.replace(Route::SpaceManage {
+                    id: space_meta.room_id.to_string(),
+                });
+
+                Ok::<(), CreateRoomError>(())
+            }
+            .unwrap_or_else(move |e: CreateRoomError| {
+                status.set(CreationStatus::Error(e));
+                notification.handle_error(&translate!(i18, "space.error.create"));
+            })
+        });
+    };
+
+    let on_handle_attach = move |event: Event<FormData>| {
+        spawn({
+            async move {
+                let files = &event.files().ok_or(AttachError::NotFound)?;
+                let fs = files.files();
+
+                let existing_file = fs.get(0).ok_or(AttachError::NotFound)?;
+                let content = files
+                    .read_file(existing_file)
+                    .await
+                    .ok_or(AttachError::NotFound)?;
+                let infered_type = infer::get(content.deref()).ok_or(AttachError::UncoverType)?;
+
+                let content_type: mime::Mime = infered_type
+                    .mime_type()
+                    .parse()
+                    .map_err(|_| AttachError::UnknownContent)?;
+
+                if content_type.type_() != mime::IMAGE {
+                    return Err(AttachError::UncoverType);
+                }
+
+                let blob = gloo::file::Blob::new(content.deref());
+                let size = blob.size().clone();
+                let object_url = gloo::file::ObjectUrl::from(blob);
+
+                attach.set(Some(AttachFile {
+                    name: existing_file.to_string(),
+                    preview_url: object_url,
+                    data: content.clone(),
+                    content_type,
+                    size,
+                    caption: String::new(),
+                    compressed: None,
+                }));
+
+                Ok::<(), AttachError>(())
+            }
+            .unwrap_or_else(move |e: AttachError| {
+                let message_error = match e {
+                    AttachError::NotFound => translate!(i18, "chat.input_message.not_found"),
+                    AttachError::UncoverType => translate!(i18, "chat.input_message.file_type"),
+                    AttachError::TooLarge(_) => translate!(i18, "chat.input_message.too_large"),
+                    AttachError::UnknownContent => translate!(i18, "chat.input_message.not_found"),
+                };
+
+                notification.handle_error(&message_error);
+            })
+        });
+    };
+
+    let element = if let Ok(file) = attach.get_file() {
+        rsx!(img {
+            class: "group__attach",
+            src: "{file.deref()}"
+        })
+    } else {
+        rsx!(Avatar {
+            name: if !space_name().is_empty() {
+                String::from(space_name())
+            } else {
+                String::from("X")
+            },
+            size: 80,
+            uri: None
+        })
+    };
+
+    rsx! {
+        Header {
+            text: translate!(i18, "space.new.title"),
+            on_event: move |_| { navigation.go_back() }
+        }
+
+        Attach { atype: AttachType::Avatar(element), on_click: on_handle_attach }
+
+        MessageInput {
+            message: "{space_name()}",
+            placeholder: translate!(i18, "space.new.name.placeholder"),
+            label: translate!(i18, "space.new.name.label"),
+            error: None,
+            on_input: move |event: Event<FormData>| {
+                space_name.set(event.value());
+            },
+            on_keypress: move |_| {},
+            on_click: move |_| {}
+        }
+
+        MessageInput {
+            message: "{space_topic()}",
+            placeholder: translate!(i18, "space.new.topic.placeholder"),
+            label: translate!(i18, "space.new.topic.label"),
+            error: None,
+            on_input: move |event: Event<FormData>| {
+                space_topic.set(event.value());
+            },
+            on_keypress: move |_| {},
+            on_click: move |_| {}
+        }
+
+        p { class: "group__title", {translate!(i18, "space.new.visibility.title")} }
+        div { class: "space__visibility row",
+            Button {
+                text: translate!(i18, "space.new.visibility.private"),
+                variant: if is_public() { Variant::Secondary } else { Variant::Primary },
+                status: None,
+                on_click: move |_| { is_public.set(false) }
+            }
+            Button {
+                text: translate!(i18, "space.new.visibility.public"),
+                variant: if is_public() { Variant::Primary } else { Variant::Secondary },
+                status: None,
+                on_click: move |_| { is_public.set(true) }
+            }
+        }
+        p { class: "space__hint",
+            if is_public() {
+                {translate!(i18, "space.new.visibility.public_description")}
+            } else {
+                {translate!(i18, "space.new.visibility.private_description")}
+            }
+        }
+
+        match *status.read() {
+            CreationStatus::Creating => {
+                rsx! {
+                    div {
+                        class: "room-new__status-container",
+                        p {
+                            class: "room-new__status__description",
+                            {translate!(i18, "space.status.creating")}
+                        }
+                    }
+                }
+            },
+            CreationStatus::Ok => {
+                rsx! {
+                    div {
+                        class: "room-new__status-container",
+                        p {
+                            class: "room-new__status__description",
+                            {translate!(i18, "space.status.created")}
+                        }
+                    }
+                }
+            },
+            _ => None
+        }
+
+        if session.is_guest() {
+            Guest {
+                description: translate!(i18, "chat.guest.signup.description"),
+                cta: translate!(i18, "chat.guest.signup.cta"),
+                on_click: move |_| {
+                    navigation.push(Route::Upgrade {});
+                }
+            }
+        } else {
+            div { class: "group__cta__wrapper row",
+                Button {
+                    text: translate!(i18, "space.new.cta.back"),
+                    status: None,
+                    variant: Variant::Secondary,
+                    on_click: move |_| { navigation.go_back() }
+                }
+                Button {
+                    text: translate!(i18, "space.new.cta.create"),
+                    status: None,
+                    disabled: space_name().is_empty() || matches!(*status.read(), CreationStatus::Creating),
+                    on_click: on_handle_create
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use dioxus::{html::input_data::keyboard_types, prelude::*};
+use dioxus_router::prelude::use_navigator;
+use dioxus_std::{i18n::use_i18, translate};
+use futures_util::StreamExt;
+use matrix_sdk::Client;
+
+use crate::{
+    components::atoms::{button::Variant, Button, Close, Header, Icon, MessageInput, RoomView},
+    hooks::{
+        use_client::use_client,
+        use_notification::{
+            use_notification, NotificationHandle, NotificationItem, NotificationType,
+        },
+    },
+    services::matrix::matrix::{
+        add_space_child, create_room_in_space, invite_to_space, remove_space_child,
+        space_hierarchy, SpaceChild, SpaceError, SpaceHierarchy,
+    },
+    utils::{
+        matrix::{mxc_to_thumbnail_uri, ImageMethod, ImageSize},
+        sync_room::sync_created_room,
+    },
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JoinedRoom {
+    id: String,
+    name: String,
+    avatar_uri: Option<String>,
+}
+
+pub enum SpaceAction {
+    Load,
+    Add(String),
+    Remove(String),
+    Create(String),
+    Invite(String),
+}
+
+#[derive(PartialEq, Props, Clone)]
+pub struct SpaceManageProps {
+    id: String,
+}
+
+pub fn SpaceManage(props: SpaceManageProps) -> Element {
+    let i18 = use_i18();
+
+    let navigation = use_navigator();
+    let client = use_client();
+    let mut notification = use_notification();
+
+    let mut hierarchy = use_signal::<Option<SpaceHierarchy>>(|| None);
+    let mut joined_rooms = use_signal::<Vec<JoinedRoom>>(|| vec![]);
+    let mut room_name = use_signal::<String>(|| String::from(""));
+    let mut user_id = use_signal::<String>(|| String::from(""));
+    let mut is_busy = use_signal::<bool>(|| false);
+
+    let space_id = props.id.clone();
+
+    let task_space = use_coroutine(|mut rx: UnboundedReceiver<SpaceAction>| async move {
+        while let Some(action) = rx.next().await {
+            is_busy.set(true);
+
+            let result = match action {
+                SpaceAction::Load => Ok(()),
+                SpaceAction::Add(child_id) => {
+                    add_space_child(&client.get(), &space_id, &child_id).await
+                }
+                SpaceAction::Remove(child_id) => {
+                    remove_space_child(&client.get(), &space_id, &child_id).await
+                }
+                SpaceAction::Create(name) => {
+                    match create_room_in_space(&client.get(), &space_id, &name, None).await {
+                        Ok(response) => {
+                            room_name.set(String::new());
+                            sync_created_room(&response.room_id, &client.get()).await;
+                            Ok(())
+                        }
+                        Err(_) => Err(SpaceError::RequestFailed),
+                    }
+                }
+                SpaceAction::Invite(user) => {
+                    let result = invite_to_space(&client.get(), &space_id, &user).await;
+
+                    if result.is_ok() {
+                        user_id.set(String::new());
+                        notification.handle_notification(NotificationItem {
+                            title: translate!(i18, "space.manage.invite.title"),
+                            body: translate!(i18, "space.manage.invite.sent"),
+                            show: true,
+                            handle: NotificationHandle {
+                                value: NotificationType::None,
+                            },
+                        });
+                    }
+
+                    result
+                }
+            };
+
+            if let Err(e) = result {
+                let message = match e {
+                    SpaceError::InvalidRoomId => translate!(i18, "chat.common.error.room_id"),
+                    SpaceError::InvalidUserId => translate!(i18, "chat.common.error.user_id"),
+                    SpaceError::SpaceNotFound => translate!(i18, "space.error.not_found"),
+                    SpaceError::RequestFailed => translate!(i18, "space.error.request"),
+                };
+
+                notification.handle_error(&message);
+            }
+
+            match space_hierarchy(&client.get(), &space_id).await {
+                Ok(h) => {
+                    let children = h
+                        .rooms
+                        .iter()
+                        .map(|child| child.room.id.clone())
+                        .collect::<Vec<_>>();
+
+                    joined_rooms.set(joined_rooms_outside(&client.get(), &space_id, &children));
+                    hierarchy.set(Some(h));
+                }
+                Err(_) => notification.handle_error(&translate!(i18, "space.error.not_found")),
+            }
+
+            is_busy.set(false);
+        }
+    });
+
+    use_effect(move || task_space.send(SpaceAction::Load));
+
+    let title = hierarchy
+        .read()
+        .as_ref()
+        .map(|h| h.space.name.clone())
+        .unwrap_or_else(|| translate!(i18, "space.manage.title"));
+
+    rsx! {
+        Header {
+            text: "{title}",
+            on_event: move |_| { navigation.go_back() }
+        }
+
+        section { class: "space-manage",
+            p { class: "group__title", {translate!(i18, "space.manage.children.title")} }
+            if let Some(h) = hierarchy() {
+                if h.rooms.is_empty() {
+                    p { class: "space__hint", {translate!(i18, "space.manage.children.empty")} }
+                }
+                for SpaceChild { room, .. } in h.rooms.into_iter() {
+                    div {
+                        key: "{room.id}",
+                        class: "group__users",
+                        RoomView {
+                            displayname: "{room.name}",
+                            avatar_uri: room.avatar_uri.clone(),
+                            description: "",
+                            on_click: move |_| {}
+                        }
+                        button {
+                            class: "group__cta--close",
+                            disabled: is_busy(),
+                            onclick: {
+                                let id = room.id.clone();
+                                move |_| { task_space.send(SpaceAction::Remove(id.clone())) }
+                            },
+                            Icon {
+                                stroke: "var(--icon-subdued)",
+                                icon: Close
+                            }
+                        }
+                    }
+                }
+            }
+
+            if !joined_rooms.read().is_empty() {
+                p { class: "group__title", {translate!(i18, "space.manage.add.title")} }
+                for joined in joined_rooms() {
+                    RoomView {
+                        key: "{joined.id}",
+                        displayname: "{joined.name}",
+                        avatar_uri: joined.avatar_uri.clone(),
+                        description: translate!(i18, "space.manage.add.description"),
+                        on_click: move |_| {
+                            if !is_busy() {
+                                task_space.send(SpaceAction::Add(joined.id.clone()))
+                            }
+                        }
+                    }
+                }
+            }
+
+            p { class: "group__title", {translate!(i18, "space.manage.create.title")} }
+            MessageInput {
+                message: "{room_name()}",
+                placeholder: translate!(i18, "space.manage.create.placeholder"),
+                error: None,
+                on_input: move |event: Event<FormData>| {
+                    room_name.set(event.value());
+                },
+                on_keypress: move |_| {},
+                on_click: move |_| {}
+            }
+            div { class: "group__cta__wrapper",
+                Button {
+                    text: translate!(i18, "space.manage.create.cta"),
+                    variant: Variant::Secondary,
+                    status: None,
+                    disabled: room_name().is_empty() || is_busy(),
+                    on_click: move |_| { task_space.send(SpaceAction::Create(room_name())) }
+                }
+            }
+
+            p { class: "group__title", {translate!(i18, "space.manage.invite.title")} }
+            MessageInput {
+                message: "{user_id()}",
+                placeholder: translate!(i18, "space.manage.invite.placeholder"),
+                error: None,
+                on_input: move |event: Event<FormData>| {
+                    user_id.set(event.value());
+                },
+                on_keypress: move |event: KeyboardEvent| {
+                    if event.code() == keyboard_types::Code::Enter && !user_id().is_empty() && !is_busy() {
+                        task_space.send(SpaceAction::Invite(user_id()))
+                    }
+                },
+                on_click: move |_| {}
+            }
+            div { class: "group__cta__wrapper",
+                Button {
+                    text: translate!(i18, "space.manage.invite.cta"),
+                    status: None,
+                    disabled: user_id().is_empty() || is_busy(),
+                    on_click: move |_| { task_space.send(SpaceAction::Invite(user_id())) }
+                }
+            }
+        }
+    }
+}
+
+// Named rooms we are in that are not linked to the space yet
+fn joined_rooms_outside(client: &Client, space_id: &str, children: &[String]) -> Vec<JoinedRoom> {
+    client
+        .joined_rooms()
+        .into_iter()
+        .filter(|room| !room.is_space() && !room.is_direct())
+        .filter(|room| {
+            let id = room.room_id().to_string();
+            !id.eq(space_id) && !children.contains(&id)
+        })
+        .filter_map(|room| {
+            room.name().map(|name| JoinedRoom {
+                id: room.room_id().to_string(),
+                name,
+                avatar_uri: room.avatar_url().and_then(|uri| {
+                    mxc_to_thumbnail_uri(&uri, ImageSize::default(), ImageMethod::CROP)
+                }),
+            })
+        })
+        .collect()
+}
@@ -4,14 +4,24 @@ use dioxus_std::{i18n::use_i18, translate};
 use std::ops::Deref;
 
 use crate::{
-    components::atoms::{attach::AttachType, Attach, Avatar, Button, MessageInput, Spinner},
+    components::{
+        atoms::{
+            attach::AttachType, button::Variant, Attach, Avatar, Button, MessageInput, Spinner,
+        },
+        molecules::SettingToggle,
+    },
     hooks::{
         use_attach::{use_attach, AttachError, AttachFile},
         use_client::use_client,
+        use_ignored_users::use_ignored_users,
         use_notification::use_notification,
     },
     pages::route::Route,
-    utils::matrix::{mxc_to_thumbnail_uri, ImageMethod, ImageSize},
+    services::matrix::matrix::IgnoreError,
+    utils::{
+        matrix::{mxc_to_thumbnail_uri, ImageMethod, ImageSize},
+        settings::{ENCRYPTED_URL_PREVIEWS, MAP_TILES_AUTOLOAD, SLIDING_SYNC},
+    },
 };
 
 use futures_util::TryFutureExt;
@@ -54,6 +64,9 @@ pub fn Profile() -> Element {
     let mut attach = use_attach();
     let mut notification = use_notification();
 
+    let mut ignored = use_ignored_users();
+    let mut user_to_ignore = use_signal(|| String::new());
+
     let mut original_profile = use_signal::<Profile>(|| Profile {
         displayname: String::from(""),
         avatar: None,
@@ -134,6 +147,28 @@ pub fn Profile() -> Element {
         })
     });
 
+    use_coroutine(|_: UnboundedReceiver<()>| async move { ignored.load().await });
+
+    let mut on_handle_ignore_error = move |e: IgnoreError| {
+        let message = match e {
+            IgnoreError::InvalidUserId => translate!(i18, "chat.common.error.user_id"),
+            IgnoreError::RequestFailed => translate!(i18, "chat.ignore.errors.update"),
+        };
+
+        notification.handle_error(&message);
+    };
+
+    let on_handle_ignore = move |_| {
+        spawn(async move {
+            let user_id = user_to_ignore().trim().to_string();
+
+            match ignored.ignore(&user_id).await {
+                Ok(_) => user_to_ignore.set(String::new()),
+                Err(e) => on_handle_ignore_error(e),
+            }
+        });
+    };
+
     let on_handle_attach = move |event: Event<FormData>| {
         spawn({
             async move {
@@ -168,6 +203,8 @@ pub fn Profile() -> Element {
                     data: content.clone(),
                     content_type,
                     size,
+                    caption: String::new(),
+                    compressed: None,
                 }));
 
                 Ok::<(), AttachError>(())
@@ -176,6 +213,7 @@ pub fn Profile() -> Element {
                 let message_error = match e {
                     AttachError::NotFound => translate!(i18, "chat.input_message.not_found"),
                     AttachError::UncoverType => translate!(i18, "chat.input_message.file_type"),
+                    AttachError::TooLarge(_) => translate!(i18, "chat.input_message.too_large"),
                     AttachError::UnknownContent => {
                         translate!(i18, "chat.input_message.unknown_content")
                     }
@@ -323,6 +361,68 @@ pub fn Profile() -> Element {
                     }
                 }
             
+                SettingToggle { setting: ENCRYPTED_URL_PREVIEWS, i18n_prefix: "profile.url_previews" }
+
+                SettingToggle { setting: MAP_TILES_AUTOLOAD, i18n_prefix: "profile.map_tiles" }
+
+                SettingToggle { setting: SLIDING_SYNC, i18n_prefix: "profile.sync" }
+
+                section {
+                    class: "profile__section",
+                    h2 {
+                        class: "profile__title",
+                        {translate!(i18, "profile.ignored.title")}
+                    }
+
+                    p {
+                        class: "profile__content",
+                        {translate!(i18, "profile.ignored.description")}
+                    }
+                    for user_id in ignored.get() {
+                        div {
+                            key: "{user_id}",
+                            class: "profile__ignored",
+                            span { class: "profile__ignored__id", "{user_id}" }
+                            Button {
+                                text: translate!(i18, "profile.ignored.cta_unignore"),
+                                variant: Variant::Tertiary,
+                                status: None,
+                                on_click: move |_| {
+                                    let user_id = user_id.clone();
+                                    spawn(async move {
+                                        if let Err(e) = ignored.unignore(&user_id).await {
+                                            on_handle_ignore_error(e);
+                                        }
+                                    });
+                                }
+                            }
+                        }
+                    }
+                    div {
+                        class: "profile__input",
+                        MessageInput {
+                            message: "{user_to_ignore}",
+                            placeholder: translate!(i18, "profile.ignored.placeholder"),
+                            error: None,
+                            on_input: move |event: Event<FormData>| {
+                                user_to_ignore.set(event.value());
+                            },
+                            on_keypress: move |_| {},
+                            on_click: move |_| {},
+                        }
+                    }
+                    div {
+                        class: "profile__cta",
+                        Button {
+                            text: translate!(i18, "profile.ignored.cta_ignore"),
+                            variant: Variant::Secondary,
+                            status: None,
+                            disabled: user_to_ignore().trim().is_empty(),
+                            on_click: on_handle_ignore
+                        }
+                    }
+                }
+
                 section {
                     class: "profile__section",
                     h2 {
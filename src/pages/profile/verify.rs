@@ -12,7 +12,6 @@ use futures_util::StreamExt;
 
 use matrix_sdk::{
     self,
-    config::SyncSettings,
     ruma::events::{
         key::verification::{
             done::{OriginalSyncKeyVerificationDoneEvent, ToDeviceKeyVerificationDoneEvent},
@@ -30,7 +29,6 @@ pub enum VerificationError {
     SasAcceptFailed,
     SasConfirmFailed,
     SasCancelFailed,
-    SyncFailed,
 }
 
 #[component]
@@ -75,7 +73,6 @@ pub fn Verify(id: String) -> Element {
                     VerificationError::SasCancelFailed => {
                         translate!(i18, "verify.errors.sas_cancel")
                     }
-                    VerificationError::SyncFailed => translate!(i18, "verify.errors.sas_sync"),
                 };
                 notification.handle_error(&message);
             }
@@ -204,10 +201,6 @@ pub fn Verify(id: String) -> Element {
                 }
             },
         );
-
-        if let Err(_) = client.sync(SyncSettings::new()).await {
-            task_handle_error.send(VerificationError::SyncFailed)
-        };
     });
 
     let on_handle_confirm = move |_| {
@@ -1,11 +1,15 @@
 use dioxus::prelude::*;
 use dioxus_router::prelude::*;
 
-use super::{page_not_found::PageNotFound, profile::profile::Profile, profile::verify::Verify};
+use super::{
+    page_not_found::PageNotFound, profile::profile::Profile, profile::verify::Verify,
+    upgrade::Upgrade,
+};
 
 use crate::{
     pages::chat::chat::Chat, pages::chat::chat_list::ChatList, pages::chat::room::group::RoomGroup,
-    pages::chat::room::new::RoomNew,
+    pages::chat::room::new::RoomNew, pages::chat::room::space::SpaceNew,
+    pages::chat::room::space_manage::SpaceManage,
 };
 
 use crate::components::organisms::IndexMenu;
@@ -19,6 +23,8 @@ pub enum Route {
         Profile {},
         #[route("/verify/:id")]
         Verify {id: String},
+        #[route("/upgrade")]
+        Upgrade {},
         #[route("/")]
         #[layout(Chat)] 
             #[route("/list")]
@@ -28,6 +34,10 @@ pub enum Route {
         RoomNew {},
         #[route("/group")]
         RoomGroup {},
+        #[route("/space")]
+        SpaceNew {},
+        #[route("/space/:id")]
+        SpaceManage {id: String},
     #[end_layout]
     #[route("/:..route")]
     PageNotFound { route: Vec<String> },
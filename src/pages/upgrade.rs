@@ -0,0 +1,186 @@
+use dioxus::prelude::*;
+use dioxus_router::prelude::use_navigator;
+use dioxus_std::{i18n::use_i18, translate};
+use futures_util::TryFutureExt;
+
+use crate::{
+    components::atoms::{input::InputType, Button, Header, MessageInput},
+    hooks::{use_client::use_client, use_notification::use_notification, use_session::use_session},
+    pages::route::Route,
+    services::matrix::matrix::{restore_session, upgrade_guest, UiaStage, UpgradeGuestError},
+    MatrixClientState,
+};
+
+pub enum UpgradeError {
+    PasswordMismatch,
+    Upgrade(UpgradeGuestError),
+    PersistFailed,
+    RestoreFailed,
+    SyncFailed,
+}
+
+pub fn Upgrade() -> Element {
+    let i18 = use_i18();
+    let navigation = use_navigator();
+    let mut client = use_client();
+    let mut session = use_session();
+    let mut notification = use_notification();
+
+    let mut password = use_signal(|| String::from(""));
+    let mut password_confirm = use_signal(|| String::from(""));
+    let mut error = use_signal::<Option<String>>(|| None);
+    let mut status = use_signal::<Option<String>>(|| None);
+    // Stage of the server's interactive auth done in the browser
+    let mut uia = use_signal::<Option<UiaStage>>(|| None);
+
+    let on_handle_upgrade = move |_| {
+        spawn({
+            async move {
+                if !password().eq(&password_confirm()) {
+                    return Err(UpgradeError::PasswordMismatch);
+                }
+
+                error.set(None);
+                status.set(Some(translate!(i18, "upgrade.status.upgrading")));
+
+                let uia_session = uia().map(|stage| stage.session);
+                let user_session = upgrade_guest(&client.get(), &password(), uia_session)
+                    .await
+                    .map_err(UpgradeError::Upgrade)?;
+
+                uia.set(None);
+
+                let serialized_session = session
+                    .persist_user_session(user_session)
+                    .map_err(|_| UpgradeError::PersistFailed)?;
+
+                // Same user id means the same indexeddb store, so joined
+                // rooms are kept while the client picks up the new token
+                let (c, sync_token) = restore_session(&serialized_session)
+                    .await
+                    .map_err(|_| UpgradeError::RestoreFailed)?;
+
+                client.set(MatrixClientState {
+                    client: Some(c.clone()),
+                });
+
+                session
+                    .sync(c.clone(), sync_token)
+                    .await
+                    .map_err(|_| UpgradeError::SyncFailed)?;
+
+                session
+                    .refresh_guest(&c)
+                    .await
+                    .map_err(|_| UpgradeError::SyncFailed)?;
+
+                status.set(None);
+                navigation.push(Route::ChatList {});
+
+                Ok::<(), UpgradeError>(())
+            }
+            .unwrap_or_else(move |e: UpgradeError| {
+                status.set(None);
+
+                match e {
+                    UpgradeError::PasswordMismatch => {
+                        error.set(Some(translate!(i18, "upgrade.errors.password_mismatch")))
+                    }
+                    UpgradeError::Upgrade(UpgradeGuestError::Rejected) => {
+                        uia.set(None);
+                        error.set(Some(translate!(i18, "upgrade.errors.rejected")))
+                    }
+                    UpgradeError::Upgrade(UpgradeGuestError::AuthRequired(stage)) => {
+                        uia.set(Some(stage))
+                    }
+                    UpgradeError::Upgrade(_) => {
+                        notification.handle_error(&translate!(i18, "upgrade.errors.failed"))
+                    }
+                    UpgradeError::PersistFailed => {
+                        notification.handle_error(&translate!(i18, "chat.common.error.persist"))
+                    }
+                    UpgradeError::RestoreFailed => {
+                        notification.handle_error(&translate!(i18, "main.errors.restore"))
+                    }
+                    UpgradeError::SyncFailed => {
+                        notification.handle_error(&translate!(i18, "chat.common.error.sync"))
+                    }
+                }
+            })
+        });
+    };
+
+    let user_id = session.get().map(|s| s.user_id).unwrap_or_default();
+
+    rsx! {
+        Header {
+            text: translate!(i18, "upgrade.title"),
+            on_event: move |_| {
+                navigation.go_back()
+            }
+        }
+
+        section { class: "upgrade",
+            p { class: "upgrade__description",
+                {translate!(i18, "upgrade.description")}
+            }
+            p { class: "upgrade__user", "{user_id}" }
+
+            MessageInput {
+                itype: InputType::Password,
+                message: "{password()}",
+                placeholder: translate!(i18, "upgrade.password.placeholder"),
+                label: translate!(i18, "upgrade.password.label"),
+                error: None,
+                on_input: move |event: FormEvent| {
+                    password.set(event.value().clone())
+                },
+                on_keypress: move |_| {},
+                on_click: move |_| {}
+            }
+            MessageInput {
+                itype: InputType::Password,
+                message: "{password_confirm()}",
+                placeholder: translate!(i18, "upgrade.password.placeholder"),
+                label: translate!(i18, "upgrade.password.confirm"),
+                error: error(),
+                on_input: move |event: FormEvent| {
+                    password_confirm.set(event.value().clone())
+                },
+                on_keypress: move |_| {},
+                on_click: move |_| {}
+            }
+
+            if let Some(stage) = uia() {
+                div { class: "upgrade__auth",
+                    p { class: "upgrade__auth__stage",
+                        {match stage.stage.as_str() {
+                            "m.login.email.identity" => translate!(i18, "upgrade.auth.email"),
+                            "m.login.msisdn" => translate!(i18, "upgrade.auth.msisdn"),
+                            "m.login.terms" => translate!(i18, "upgrade.auth.terms"),
+                            "m.login.recaptcha" => translate!(i18, "upgrade.auth.recaptcha"),
+                            other => format!("{} ({other})", translate!(i18, "upgrade.auth.other")),
+                        }}
+                    }
+                    a {
+                        class: "upgrade__auth__link",
+                        href: "{stage.fallback_url}",
+                        target: "_blank",
+                        rel: "noopener noreferrer",
+                        {translate!(i18, "upgrade.auth.cta")}
+                    }
+                    p { class: "upgrade__auth__hint", {translate!(i18, "upgrade.auth.hint")} }
+                }
+            }
+
+            div { class: "upgrade__cta",
+                Button {
+                    text: translate!(i18, "upgrade.cta"),
+                    status: status(),
+                    disabled: password().is_empty() || !session.is_guest(),
+                    on_click: on_handle_upgrade
+                }
+            }
+        }
+    }
+}
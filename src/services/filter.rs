@@ -0,0 +1,187 @@
+use matrix_sdk::{
+    ruma::{
+        api::client::filter::{Filter, FilterDefinition, LazyLoadOptions, RoomEventFilter},
+        events::RoomEventType,
+        uint, OwnedRoomId,
+    },
+    Client,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::services::matrix::matrix::{
+    BEACON_INFO_TYPES, BEACON_TYPES, POLL_END_TYPES, POLL_RESPONSE_TYPES, POLL_START_TYPES,
+    ROOM_EMOTES_TYPE,
+};
+
+// Bump the version whenever the definition changes, so a new filter
+// gets uploaded instead of reusing the cached id of the old one
+pub const SYNC_FILTER_NAME: &str = "fido-sync-v5";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyncFilter {
+    pub name: String,
+    pub id: String,
+}
+
+pub enum FilterError {
+    UploadFailed,
+}
+
+/// Event types the timeline knows how to render, everything else is dropped
+/// from the sync response by the server.
+pub fn rendered_event_types() -> Vec<String> {
+    let mut types = vec![
+        RoomEventType::RoomMessage.to_string(),
+        RoomEventType::RoomEncrypted.to_string(),
+        RoomEventType::Sticker.to_string(),
+    ];
+
+    types.extend(
+        POLL_START_TYPES
+            .iter()
+            .chain(POLL_RESPONSE_TYPES.iter())
+            .chain(POLL_END_TYPES.iter())
+            .chain(BEACON_INFO_TYPES.iter())
+            .chain(BEACON_TYPES.iter())
+            .map(|event_type| event_type.to_string()),
+    );
+
+    types
+}
+
+/// State changes arrive inside the timeline too, the store only picks up
+/// renames, joins or new power levels if these go through the filter.
+pub fn state_event_types() -> Vec<String> {
+    let mut types = [
+        RoomEventType::RoomCreate,
+        RoomEventType::RoomName,
+        RoomEventType::RoomTopic,
+        RoomEventType::RoomAvatar,
+        RoomEventType::RoomCanonicalAlias,
+        RoomEventType::RoomMember,
+        RoomEventType::RoomPowerLevels,
+        RoomEventType::RoomJoinRules,
+        RoomEventType::RoomEncryption,
+        RoomEventType::RoomTombstone,
+        RoomEventType::SpaceChild,
+        RoomEventType::SpaceParent,
+    ]
+    .iter()
+    .map(|event_type| event_type.to_string())
+    .collect::<Vec<_>>();
+
+    types.push(ROOM_EMOTES_TYPE.to_string());
+
+    types
+}
+
+/// Everything the timeline filter lets through.
+pub fn timeline_event_types() -> Vec<String> {
+    let mut types = rendered_event_types();
+    types.extend(state_event_types());
+
+    types
+}
+
+pub fn sync_filter_definition(types: &[String]) -> FilterDefinition<'_> {
+    let lazy_load_options = LazyLoadOptions::Enabled {
+        include_redundant_members: false,
+    };
+
+    let mut timeline = RoomEventFilter::empty();
+    timeline.limit = Some(uint!(20));
+    timeline.types = Some(types);
+    timeline.lazy_load_options = lazy_load_options.clone();
+
+    let mut state = RoomEventFilter::empty();
+    state.lazy_load_options = lazy_load_options;
+
+    let mut filter = FilterDefinition::empty();
+    filter.room.include_leave = false;
+    filter.room.timeline = timeline;
+    filter.room.state = state;
+    filter.presence = Filter::ignore_all();
+
+    filter
+}
+
+/// The same filter limited to `rooms`. With sliding sync in charge of the room
+/// list, classic sync only follows the rooms opened from it.
+pub fn limited_sync_filter_definition<'a>(
+    types: &'a [String],
+    rooms: &'a [OwnedRoomId],
+) -> FilterDefinition<'a> {
+    let mut filter = sync_filter_definition(types);
+    filter.room.rooms = Some(rooms);
+
+    filter
+}
+
+pub async fn upload_sync_filter(client: &Client) -> Result<SyncFilter, FilterError> {
+    let types = timeline_event_types();
+
+    let id = client
+        .get_or_upload_filter(SYNC_FILTER_NAME, sync_filter_definition(&types))
+        .await
+        .map_err(|_| FilterError::UploadFailed)?;
+
+    Ok(SyncFilter {
+        name: SYNC_FILTER_NAME.to_string(),
+        id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeline_types_keep_state_events() {
+        let types = timeline_event_types();
+
+        for event_type in [
+            "m.room.message",
+            "m.room.encrypted",
+            "m.sticker",
+            "m.room.member",
+            "m.room.name",
+            "m.room.power_levels",
+            "m.space.child",
+            "im.ponies.room_emotes",
+        ] {
+            assert!(
+                types.iter().any(|t| t.eq(event_type)),
+                "{event_type} is filtered out"
+            );
+        }
+    }
+
+    #[test]
+    fn rendered_types_include_polls_and_beacons() {
+        let types = rendered_event_types();
+
+        for event_type in POLL_START_TYPES
+            .iter()
+            .chain(POLL_END_TYPES.iter())
+            .chain(BEACON_INFO_TYPES.iter())
+        {
+            assert!(types.iter().any(|t| t.eq(event_type)));
+        }
+    }
+
+    #[test]
+    fn serialized_definition() {
+        let types = timeline_event_types();
+        let value = serde_json::to_value(sync_filter_definition(&types)).unwrap();
+
+        assert_eq!(value["room"]["timeline"]["limit"], 20);
+        assert_eq!(value["room"]["timeline"]["lazy_load_members"], true);
+        assert_eq!(value["room"]["state"]["lazy_load_members"], true);
+        assert_eq!(value["presence"]["not_types"], serde_json::json!(["*"]));
+        assert_eq!(
+            value["room"]["timeline"]["types"],
+            serde_json::to_value(&types).unwrap()
+        );
+        assert!(value["room"]["state"].get("types").is_none());
+    }
+}
@@ -1,24 +1,24 @@
 pub mod matrix {
     use std::{
-        collections::HashMap,
+        collections::{BTreeMap, HashMap},
         ops::Deref,
         time::{Duration, UNIX_EPOCH},
     };
 
     use chrono::{DateTime, Local, Utc};
+    use futures::future::join_all;
     use log::info;
 
     use matrix_sdk::{
-        attachment::AttachmentConfig,
         config::RequestConfig,
-        deserialized_responses::{SyncTimelineEvent, TimelineSlice},
+        deserialized_responses::{MemberEvent, SyncTimelineEvent, TimelineSlice},
         media::{MediaFormat, MediaRequest, MediaThumbnailSize},
-        room::{Invited, MessagesOptions, Room},
+        room::{Invited, Joined, MessagesOptions, Room},
         ruma::{
             api::{
                 self,
                 client::{
-                    filter::{LazyLoadOptions, RoomEventFilter},
+                    filter::{LazyLoadOptions, RoomEventFilter, UrlFilter},
                     media::get_content_thumbnail::v3::Method,
                     room::{create_room::v3::RoomPreset, Visibility},
                     uiaa,
@@ -29,15 +29,18 @@ pub mod matrix {
                 room::{
                     avatar::RoomAvatarEventContent,
                     message::{
-                        FileMessageEventContent, ImageMessageEventContent, InReplyTo,
-                        MessageFormat, MessageType, OriginalSyncRoomMessageEvent, Relation,
-                        RoomMessageEventContent, VideoMessageEventContent,
+                        sanitize_html, AudioInfo, AudioMessageEventContent, FileInfo,
+                        FileMessageEventContent, FormattedBody, HtmlSanitizerMode,
+                        ImageMessageEventContent, InReplyTo, MessageFormat, MessageType,
+                        OriginalSyncRoomMessageEvent, Relation, RemoveReplyFallback,
+                        RoomMessageEventContent, VideoInfo, VideoMessageEventContent,
                     },
-                    MediaSource,
+                    ImageInfo, MediaSource, ThumbnailInfo,
                 },
+                sticker::StickerEventContent,
                 AnyInitialStateEvent, AnyMessageLikeEvent, AnySyncMessageLikeEvent,
                 AnySyncTimelineEvent, AnyTimelineEvent, EmptyStateKey, InitialStateEvent,
-                MessageLikeEvent, SyncMessageLikeEvent,
+                MessageLikeEvent, OriginalSyncMessageLikeEvent, SyncMessageLikeEvent,
             },
             serde::Raw,
             MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedUserId, RoomId, TransactionId, UInt,
@@ -47,19 +50,38 @@ pub mod matrix {
     use mime::Mime;
     use ruma::{
         api::client::{
-            account::register::RegistrationKind, directory::get_public_rooms,
-            message::send_message_event::v3::Response,
+            account::register::RegistrationKind,
+            directory::get_public_rooms_filtered,
+            knock::knock_room,
+            media::{get_media_config, get_media_preview},
+            membership::leave_room,
+            message::{get_message_events, send_message_event::v3::Response},
+            room::report_content,
+            space::{get_hierarchy, SpaceHierarchyRoomsChunk},
         },
-        events::room::message::Thread,
-        OwnedMxcUri, OwnedRoomId, UserId,
+        directory::Filter,
+        events::{
+            direct::DirectEventContent, ignored_user_list::IgnoredUserListEventContent,
+            room::message::Thread, GlobalAccountDataEventType, StateEventType,
+        },
+        room::RoomType,
+        space::SpaceRoomJoinRule,
+        EventId, Int, MxcUri, OwnedMxcUri, OwnedRoomId, OwnedServerName, RoomOrAliasId,
+        RoomVersionId, ServerName, UserId,
     };
     use url::Url;
+    use uuid::Uuid;
 
     use crate::{
-        components::atoms::room::RoomItem,
+        components::atoms::room::{InvitedRoom, RoomItem},
         hooks::{use_send_message::SendMessageError, use_session::UserSession},
         pages::chat::room::group::Profile,
-        utils::matrix::{mxc_to_download_uri, mxc_to_thumbnail_uri, ImageMethod, ImageSize},
+        services::filter::SyncFilter,
+        utils::{
+            matrix::{mxc_to_download_uri, mxc_to_thumbnail_uri, ImageMethod, ImageSize},
+            media::read_media_metadata,
+            upload::{post_with_progress, Upload, UploadError},
+        },
     };
 
     use matrix_sdk::Session;
@@ -71,10 +93,31 @@ pub mod matrix {
         pub body: String,
         pub(crate) data: Vec<u8>,
         pub content_type: Mime,
+        pub info: Option<AttachmentInfo>,
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+    pub struct AttachmentInfo {
+        pub width: Option<u32>,
+        pub height: Option<u32>,
+        /// Milliseconds
+        pub duration: Option<u64>,
+        pub blurhash: Option<String>,
+        pub thumbnail: Option<AttachmentThumbnail>,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    pub struct AttachmentThumbnail {
+        pub uri: String,
+        pub content_type: String,
+        pub size: u64,
+        pub width: u32,
+        pub height: u32,
     }
 
     pub struct AttachmentStream {
         pub attachment: Attachment,
+        pub caption: Option<String>,
         pub send_to_thread: bool,
     }
 
@@ -114,11 +157,11 @@ pub mod matrix {
         reply_to: Option<OwnedEventId>,
         thread_to: Option<OwnedEventId>,
         latest_event: Option<OwnedEventId>,
+        tx_id: &TransactionId,
     ) -> Result<Response, SendMessageError> {
         let room = client
             .get_joined_room(&room_id)
             .ok_or(SendMessageError::RoomNotFound)?;
-        let tx_id = TransactionId::new();
 
         let mut event_content = RoomMessageEventContent::new(msg);
 
@@ -138,7 +181,7 @@ pub mod matrix {
             None
         };
 
-        room.send(event_content, Some(&tx_id))
+        room.send(event_content, Some(tx_id))
             .await
             .map_err(|_| SendMessageError::DispatchMessage)
     }
@@ -153,6 +196,153 @@ pub mod matrix {
             .await
     }
 
+    /// Reads dimensions and a blurhash of images and videos and uploads a
+    /// thumbnail for them. Other files, or media the browser can't decode,
+    /// are sent without info
+    pub async fn attachment_info(client: &Client, attach: &Attachment) -> Option<AttachmentInfo> {
+        let metadata = read_media_metadata(&attach.data, &attach.content_type)
+            .await
+            .ok()?;
+
+        let thumbnail = match metadata.thumbnail {
+            Some(thumbnail) => client
+                .media()
+                .upload(&thumbnail.content_type, &thumbnail.data)
+                .await
+                .ok()
+                .map(|response| AttachmentThumbnail {
+                    uri: response.content_uri.to_string(),
+                    content_type: thumbnail.content_type.to_string(),
+                    size: thumbnail.data.len() as u64,
+                    width: thumbnail.width,
+                    height: thumbnail.height,
+                }),
+            None => None,
+        };
+
+        Some(AttachmentInfo {
+            width: metadata.width,
+            height: metadata.height,
+            duration: metadata.duration,
+            blurhash: metadata.blurhash,
+            thumbnail,
+        })
+    }
+
+    /// Maximum upload size, `m.upload.size`, if the homeserver sets one
+    pub async fn media_upload_limit(client: &Client) -> Option<u64> {
+        let request = get_media_config::v3::Request::new();
+        let response = client.send(request, None).await.ok()?;
+
+        response.upload_size.map(u64::from)
+    }
+
+    #[derive(PartialEq, Debug, Clone)]
+    pub struct UrlPreview {
+        pub url: String,
+        pub title: Option<String>,
+        pub description: Option<String>,
+        pub site_name: Option<String>,
+        pub image: Option<String>,
+    }
+
+    #[derive(PartialEq, Debug, Clone)]
+    pub enum UrlPreviewError {
+        RequestFailed,
+        NotFound,
+    }
+
+    /// OpenGraph data of a link, as scraped by the homeserver
+    pub async fn url_preview(client: &Client, url: &str) -> Result<UrlPreview, UrlPreviewError> {
+        let request = get_media_preview::v3::Request::new(url, MilliSecondsSinceUnixEpoch::now());
+        let response = client
+            .send(request, None)
+            .await
+            .map_err(|_| UrlPreviewError::RequestFailed)?;
+
+        let data = response
+            .data
+            .and_then(|data| serde_json::from_str::<serde_json::Value>(data.get()).ok())
+            .ok_or(UrlPreviewError::NotFound)?;
+
+        let field = |key: &str| {
+            data[key]
+                .as_str()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let preview = UrlPreview {
+            url: url.to_string(),
+            title: field("og:title"),
+            description: field("og:description"),
+            site_name: field("og:site_name"),
+            image: field("og:image").and_then(|uri| {
+                mxc_to_thumbnail_uri(
+                    &OwnedMxcUri::from(uri),
+                    ImageSize::new(320, 240),
+                    ImageMethod::SCALE,
+                )
+            }),
+        };
+
+        match (&preview.title, &preview.description) {
+            (None, None) => Err(UrlPreviewError::NotFound),
+            _ => Ok(preview),
+        }
+    }
+
+    /// Starts uploading an attachment, returning a handle to cancel it along
+    /// with the future resolving to its mxc uri
+    pub async fn upload_attachment_with_progress(
+        client: &Client,
+        attach: &Attachment,
+        on_progress: impl Fn(f64) + 'static,
+    ) -> Result<
+        (
+            Upload,
+            impl std::future::Future<Output = Result<OwnedMxcUri, UploadError>>,
+        ),
+        UploadError,
+    > {
+        let session = client.session().ok_or(UploadError::RequestFailed)?;
+
+        let request_url = client
+            .homeserver()
+            .await
+            .join("_matrix/media/v3/upload")
+            .map_err(|_| UploadError::RequestFailed)?;
+
+        let authorization = format!("Bearer {}", session.access_token);
+        let content_type = attach.content_type.to_string();
+
+        let (upload, response) = post_with_progress(
+            request_url.as_str(),
+            &[
+                ("Authorization", &authorization),
+                ("Content-Type", &content_type),
+            ],
+            &attach.data,
+            on_progress,
+        )?;
+
+        let uri = async move {
+            let body = response.await?;
+
+            serde_json::from_str::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|value| {
+                    value
+                        .get("content_uri")
+                        .and_then(|uri| uri.as_str())
+                        .map(|uri| OwnedMxcUri::from(uri))
+                })
+                .ok_or(UploadError::RequestFailed)
+        };
+
+        Ok((upload, uri))
+    }
+
     pub async fn send_attachment(
         client: &Client,
         room_id: &RoomId,
@@ -161,53 +351,111 @@ pub mod matrix {
         reply_to: Option<OwnedEventId>,
         thread_to: Option<OwnedEventId>,
         latest_event: Option<OwnedEventId>,
+        tx_id: &TransactionId,
     ) -> Result<Response, SendMessageError> {
-        let room = client
-            .get_joined_room(&room_id)
-            .ok_or(SendMessageError::RoomNotFound)?;
+        let info = attach.info.clone().unwrap_or_default();
+        let size = UInt::new(attach.data.len() as u64);
+        let mimetype = Some(attach.content_type.to_string());
+
+        let thumbnail_source = info
+            .thumbnail
+            .as_ref()
+            .map(|t| MediaSource::Plain(OwnedMxcUri::from(t.uri.as_str())));
+        let thumbnail_info = info.thumbnail.as_ref().map(|t| {
+            Box::new(assign!(ThumbnailInfo::new(), {
+                width: Some(UInt::from(t.width)),
+                height: Some(UInt::from(t.height)),
+                mimetype: Some(t.content_type.clone()),
+                size: UInt::new(t.size),
+            }))
+        });
 
         let message_type = match attach.content_type.type_() {
             mime::IMAGE => {
-                let event_content =
-                    ImageMessageEventContent::plain(attach.body.clone(), uri.clone(), None);
+                let image_info = assign!(ImageInfo::new(), {
+                    width: info.width.map(UInt::from),
+                    height: info.height.map(UInt::from),
+                    mimetype,
+                    size,
+                    thumbnail_source,
+                    thumbnail_info,
+                    blurhash: info.blurhash,
+                });
+
+                let event_content = ImageMessageEventContent::plain(
+                    attach.body.clone(),
+                    uri.clone(),
+                    Some(Box::new(image_info)),
+                );
 
                 MessageType::Image(event_content)
             }
             mime::VIDEO => {
-                let event_content =
-                    VideoMessageEventContent::plain(attach.body.clone(), uri.clone(), None);
+                let video_info = assign!(VideoInfo::new(), {
+                    width: info.width.map(UInt::from),
+                    height: info.height.map(UInt::from),
+                    duration: info.duration.map(Duration::from_millis),
+                    mimetype,
+                    size,
+                    thumbnail_source,
+                    thumbnail_info,
+                    blurhash: info.blurhash,
+                });
+
+                let event_content = VideoMessageEventContent::plain(
+                    attach.body.clone(),
+                    uri.clone(),
+                    Some(Box::new(video_info)),
+                );
 
                 MessageType::Video(event_content)
             }
+            mime::AUDIO => {
+                let audio_info = assign!(AudioInfo::new(), {
+                    duration: info.duration.map(Duration::from_millis),
+                    mimetype,
+                    size,
+                });
+
+                let event_content = AudioMessageEventContent::plain(
+                    attach.body.clone(),
+                    uri.clone(),
+                    Some(Box::new(audio_info)),
+                );
+
+                MessageType::Audio(event_content)
+            }
             mime::APPLICATION => {
-                let event_content =
-                    FileMessageEventContent::plain(attach.body.clone(), uri.clone(), None);
+                let file_info = assign!(FileInfo::new(), {
+                    mimetype,
+                    size,
+                    thumbnail_source,
+                    thumbnail_info,
+                });
+
+                let event_content = FileMessageEventContent::plain(
+                    attach.body.clone(),
+                    uri.clone(),
+                    Some(Box::new(file_info)),
+                );
 
                 MessageType::File(event_content)
             }
             _ => return Err(SendMessageError::InvalidFile),
         };
 
-        if reply_to.is_some() || latest_event.is_some() {
-            send_message(
-                client,
-                room_id,
-                message_type,
-                reply_to,
-                thread_to,
-                latest_event,
-            )
-            .await
-        } else {
-            room.send_attachment(
-                &attach.body,
-                &attach.content_type,
-                &attach.data,
-                AttachmentConfig::new(),
-            )
-            .await
-            .map_err(|_| SendMessageError::DispatchMessage)
-        }
+        // The media is already uploaded, sending the event with the caller's
+        // transaction id keeps retries from duplicating the message
+        send_message(
+            client,
+            room_id,
+            message_type,
+            reply_to,
+            thread_to,
+            latest_event,
+            tx_id,
+        )
+        .await
     }
 
     pub fn listen_messages(client: &Client) {
@@ -216,15 +464,26 @@ pub mod matrix {
         });
     }
 
+    #[derive(Clone, Debug, Default, PartialEq)]
     pub struct Conversations {
         pub rooms: Vec<RoomItem>,
         pub spaces: HashMap<RoomItem, Vec<RoomItem>>,
     }
 
-    pub async fn invited_rooms(client: &Client) -> Result<Vec<RoomItem>, String> {
+    pub async fn invited_rooms(client: &Client) -> Result<Vec<InvitedRoom>, String> {
         let mut rooms = Vec::new();
 
+        let ignored = ignored_users(client).await;
+
         for room in client.invited_rooms() {
+            if is_invited_by(&room, &ignored).await {
+                if let Err(e) = room.reject_invitation().await {
+                    info!("failed to reject the invitation of an ignored user {e:?}");
+                }
+
+                continue;
+            }
+
             let Ok(item) = format_invited_room(&client, room).await else {
                 continue;
             };
@@ -235,7 +494,19 @@ pub mod matrix {
         Ok(rooms)
     }
 
-    pub async fn format_invited_room(client: &Client, room: Invited) -> Result<RoomItem, String> {
+    /// Whether the invitation was sent by one of the given users
+    pub async fn is_invited_by(room: &Invited, users: &[String]) -> bool {
+        room.invite_details()
+            .await
+            .ok()
+            .and_then(|invite| invite.inviter)
+            .is_some_and(|inviter| users.contains(&inviter.user_id().to_string()))
+    }
+
+    pub async fn format_invited_room(
+        client: &Client,
+        room: Invited,
+    ) -> Result<InvitedRoom, String> {
         let avatar_uri: Option<String> = room
             .avatar_url()
             .and_then(|uri| mxc_to_thumbnail_uri(&uri, ImageSize::default(), ImageMethod::CROP));
@@ -249,78 +520,151 @@ pub mod matrix {
             return Err(String::from("User not found"));
         };
 
-        let room = RoomItem {
-            avatar_uri: avatar_uri,
-            id: room.room_id().to_string(),
-            name: room.name().unwrap_or(room_creator.displayname),
-            is_public: true,
-            is_direct: false,
+        let invite = room.invite_details().await.ok();
+
+        // The reason lives in our own membership of the stripped state
+        let reason = invite
+            .as_ref()
+            .and_then(|invite| match invite.invitee.event().as_ref() {
+                MemberEvent::Stripped(event) => event.content.reason.clone(),
+                MemberEvent::Sync(event) => event
+                    .as_original()
+                    .and_then(|event| event.content.reason.clone()),
+            });
+
+        let inviter = invite.and_then(|invite| invite.inviter);
+
+        let room = InvitedRoom {
+            room: RoomItem {
+                avatar_uri: avatar_uri,
+                id: room.room_id().to_string(),
+                name: room.name().unwrap_or(room_creator.displayname),
+                is_public: true,
+                is_direct: false,
+            },
+            inviter_id: inviter.as_ref().map(|m| m.user_id().to_string()),
+            inviter_name: inviter.as_ref().map(|m| m.name().to_string()),
+            inviter_avatar: inviter.as_ref().and_then(|m| {
+                m.avatar_url().and_then(|uri| {
+                    mxc_to_thumbnail_uri(uri, ImageSize::default(), ImageMethod::CROP)
+                })
+            }),
+            reason,
         };
 
         Ok(room)
     }
 
-    pub async fn public_rooms_and_spaces(
+    #[derive(Debug)]
+    pub enum InvitationError {
+        InvalidRoomId,
+        RequestFailed,
+    }
+
+    /// Declines an invitation, the reason reaches the inviter through our
+    /// leave event
+    pub async fn reject_invitation(
+        client: &Client,
+        room_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), InvitationError> {
+        let room_id = RoomId::parse(room_id).map_err(|_| InvitationError::InvalidRoomId)?;
+
+        let request = assign!(leave_room::v3::Request::new(&room_id), { reason });
+
+        client
+            .send(request, None)
+            .await
+            .map_err(|_| InvitationError::RequestFailed)?;
+
+        Ok(())
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct PublicRoomEntry {
+        pub room: RoomItem,
+        pub alias: Option<String>,
+        pub topic: Option<String>,
+        pub members: u64,
+        pub world_readable: bool,
+        pub guest_can_join: bool,
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct PublicRoomsPage {
+        pub rooms: Vec<PublicRoomEntry>,
+        /// Token for the next page, None once the directory is exhausted
+        pub next_batch: Option<String>,
+        pub total: Option<u64>,
+    }
+
+    #[derive(Debug)]
+    pub enum PublicRoomsError {
+        InvalidServer,
+        RequestFailed,
+    }
+
+    pub async fn public_rooms_directory(
         client: &Client,
-        limit: Option<u32>,
+        limit: u32,
         since: Option<&str>,
         server: Option<&str>,
-    ) -> Result<Conversations, String> {
-        let mut rooms = Vec::new();
-        let mut spaces: HashMap<RoomItem, Vec<RoomItem>> = HashMap::new();
-
-        let limit = limit.map(UInt::from);
-        let server = server.map(|s| s.try_into().ok()).flatten();
+        search: Option<&str>,
+    ) -> Result<PublicRoomsPage, PublicRoomsError> {
+        let server = server
+            .map(|s| <&ServerName>::try_from(s).map_err(|_| PublicRoomsError::InvalidServer))
+            .transpose()?;
+
+        let filter = assign!(Filter::new(), {
+            generic_search_term: search,
+        });
 
-        let request = assign!(get_public_rooms::v3::Request::new(), {
-            limit,
+        let request = assign!(get_public_rooms_filtered::v3::Request::new(), {
+            limit: Some(UInt::from(limit)),
             since,
             server,
+            filter,
         });
 
         let response = client
             .send(request, Some(RequestConfig::default().force_auth()))
             .await
-            .map_err(|_| String::from("ServerError"))?;
-
-        for room in response.chunk {
-            let avatar_uri: Option<String> = room.avatar_url.and_then(|uri| {
-                mxc_to_thumbnail_uri(&uri, ImageSize::default(), ImageMethod::CROP)
-            });
-
-            let room = RoomItem {
-                avatar_uri: avatar_uri,
-                id: room.room_id.to_string(),
-                name: room.name.unwrap_or(String::from("Unnamed")),
-                is_public: true,
-                is_direct: false,
-            };
-
-            rooms.push(room);
-        }
-
-        let mut to_list_rooms = vec![];
+            .map_err(|_| PublicRoomsError::RequestFailed)?;
 
-        for (key, value) in spaces.iter_mut() {
-            rooms.iter().for_each(|room| {
-                let room_homeserver = room.id.split(":").collect::<Vec<&str>>()[1];
-                let space_homeserver = key.id.split(":").collect::<Vec<&str>>()[1];
+        let rooms = response
+            .chunk
+            .into_iter()
+            .map(|room| {
+                let avatar_uri: Option<String> = room.avatar_url.and_then(|uri| {
+                    mxc_to_thumbnail_uri(&uri, ImageSize::default(), ImageMethod::CROP)
+                });
 
-                if room_homeserver.eq(space_homeserver) && !room.is_direct && !room.id.eq(&key.id) {
-                    value.push(room.clone());
-                } else {
-                    to_list_rooms.push(room.clone());
+                let alias = room.canonical_alias.map(|alias| alias.to_string());
+
+                PublicRoomEntry {
+                    room: RoomItem {
+                        avatar_uri,
+                        id: room.room_id.to_string(),
+                        name: room
+                            .name
+                            .or(alias.clone())
+                            .unwrap_or(String::from("Unnamed")),
+                        is_public: true,
+                        is_direct: false,
+                    },
+                    alias,
+                    topic: room.topic,
+                    members: u64::from(room.num_joined_members),
+                    world_readable: room.world_readable,
+                    guest_can_join: room.guest_can_join,
                 }
-            });
-        }
-
-        if spaces.len() == 0 {
-            to_list_rooms = rooms;
-        }
+            })
+            .collect();
 
-        Ok(Conversations {
-            rooms: to_list_rooms,
-            spaces: spaces,
+        Ok(PublicRoomsPage {
+            rooms,
+            next_batch: response.next_batch,
+            total: response.total_room_count_estimate.map(u64::from),
         })
     }
 
@@ -329,7 +673,8 @@ pub mod matrix {
         session_data: UserSession,
     ) -> Conversations {
         let mut rooms = Vec::new();
-        let mut spaces = HashMap::new();
+        let mut spaces = vec![];
+        let mut parents = HashMap::new();
         let rooms_response = client.rooms();
 
         for room in rooms_response {
@@ -367,6 +712,13 @@ pub mod matrix {
                 })
             };
 
+            // Spaces list their children, rooms can name the space they belong to
+            let links = if is_space {
+                linked_rooms(&room, StateEventType::SpaceChild).await
+            } else {
+                linked_rooms(&room, StateEventType::SpaceParent).await
+            };
+
             if let Some(name) = room.name() {
                 let room = RoomItem {
                     avatar_uri: avatar_uri,
@@ -377,8 +729,9 @@ pub mod matrix {
                 };
 
                 if is_space {
-                    spaces.insert(room, vec![]);
+                    spaces.push((room, links));
                 } else {
+                    parents.insert(room.id.clone(), links);
                     rooms.push(room);
                 }
             } else {
@@ -404,899 +757,3423 @@ pub mod matrix {
             }
         }
 
-        let mut to_list_rooms = vec![];
-
-        for (key, value) in spaces.iter_mut() {
-            rooms.iter().for_each(|room| {
-                let room_homeserver = room.id.split(":").collect::<Vec<&str>>()[1];
-                let space_homeserver = key.id.split(":").collect::<Vec<&str>>()[1];
-
-                if room_homeserver.eq(space_homeserver) && !room.is_direct && !room.id.eq(&key.id) {
-                    value.push(room.clone());
-                } else {
-                    to_list_rooms.push(room.clone());
-                }
-            });
-        }
-
-        if spaces.len() == 0 {
-            to_list_rooms = rooms;
-        }
+        let spaces = group_by_space(&rooms, spaces, &parents);
 
-        Conversations {
-            rooms: to_list_rooms,
-            spaces: spaces,
-        }
+        Conversations { rooms, spaces }
     }
 
-    pub enum LeaveRoomError {
-        InvalidRoomId,
-        RoomNotFound,
-        Failed,
+    async fn linked_rooms(room: &Room, event_type: StateEventType) -> Vec<String> {
+        room.get_state_events(event_type)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|event| {
+                event
+                    .get_field::<serde_json::Value>("content")
+                    .ok()
+                    .flatten()
+                    .is_some_and(|content| has_via(&content))
+            })
+            .filter_map(|event| event.get_field::<String>("state_key").ok().flatten())
+            .collect()
     }
 
-    pub async fn leave_room(client: &Client, id: &str) -> Result<(), LeaveRoomError> {
-        let room_id = RoomId::parse(id).map_err(|_| LeaveRoomError::InvalidRoomId)?;
-        let room = client
-            .get_joined_room(&room_id)
-            .ok_or(LeaveRoomError::RoomNotFound)?;
+    // Space child and parent events without servers to join through are removed links
+    fn has_via(content: &serde_json::Value) -> bool {
+        content["via"].as_array().is_some_and(|via| !via.is_empty())
+    }
 
-        room.leave().await.map_err(|_| LeaveRoomError::Failed)
+    /// Groups the joined rooms under the spaces listing them as children or named
+    /// as their parent. Spaces nested in another joined space are not top level,
+    /// they are reached through the hierarchy of their parent.
+    fn group_by_space(
+        rooms: &[RoomItem],
+        spaces: Vec<(RoomItem, Vec<String>)>,
+        parents: &HashMap<String, Vec<String>>,
+    ) -> HashMap<RoomItem, Vec<RoomItem>> {
+        let nested = spaces
+            .iter()
+            .flat_map(|(space, children)| children.iter().filter(|c| !c.eq(&&space.id)))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        spaces
+            .into_iter()
+            .filter(|(space, _)| !nested.contains(&space.id))
+            .map(|(space, children)| {
+                let value = rooms
+                    .iter()
+                    .filter(|room| {
+                        children.contains(&room.id)
+                            || parents
+                                .get(&room.id)
+                                .is_some_and(|parents| parents.contains(&space.id))
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                (space, value)
+            })
+            .collect()
     }
 
-    #[derive(PartialEq, Debug, Clone)]
-    pub struct RoomMember {
-        pub id: String,
-        pub name: String,
-        pub avatar_uri: Option<String>,
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct SpaceChild {
+        pub room: RoomItem,
+        pub topic: Option<String>,
+        pub members: u64,
+        pub suggested: bool,
+        pub is_joined: bool,
     }
 
-    pub enum RoomMemberError {
-        NotFound,
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct SpaceHierarchy {
+        pub space: RoomItem,
+        pub rooms: Vec<SpaceChild>,
+        pub subspaces: Vec<SpaceHierarchy>,
     }
 
-    #[derive(Clone, Debug)]
-    pub enum FindUserError {
-        InvalidUserId,
-        UserNotFound,
-        InvalidUsername,
+    #[derive(Debug)]
+    pub enum SpaceHierarchyError {
+        InvalidRoomId,
+        RequestFailed,
+        SpaceNotFound,
     }
 
-    pub async fn find_user_by_id(id: &str, client: &Client) -> Result<Profile, FindUserError> {
-        let u = UserId::parse(&id).map_err(|_| FindUserError::InvalidUserId)?;
+    // Big spaces are paginated, stop after a few pages instead of walking all of them
+    const SPACE_HIERARCHY_MAX_PAGES: usize = 5;
 
-        let u = u.deref();
+    pub async fn space_hierarchy(
+        client: &Client,
+        space_id: &str,
+    ) -> Result<SpaceHierarchy, SpaceHierarchyError> {
+        let room_id = RoomId::parse(space_id).map_err(|_| SpaceHierarchyError::InvalidRoomId)?;
 
-        let request = matrix_sdk::ruma::api::client::profile::get_profile::v3::Request::new(u);
+        let mut chunks = HashMap::new();
+        let mut from: Option<String> = None;
 
-        let response = client
-            .send(request, None)
-            .await
-            .map_err(|_| FindUserError::UserNotFound)?;
+        for _ in 0..SPACE_HIERARCHY_MAX_PAGES {
+            let request = assign!(get_hierarchy::v1::Request::new(&room_id), {
+                from: from.as_deref(),
+            });
 
-        let displayname = response.displayname.ok_or(FindUserError::InvalidUsername)?;
+            let response = client
+                .send(request, None)
+                .await
+                .map_err(|_| SpaceHierarchyError::RequestFailed)?;
 
-        let avatar_uri = response
-            .avatar_url
-            .map(|uri| {
-                mxc_to_thumbnail_uri(
-                    &uri,
-                    ImageSize {
-                        width: 48,
-                        height: 48,
-                    },
-                    ImageMethod::CROP,
-                )
-            })
-            .flatten();
+            for chunk in response.rooms {
+                chunks.insert(chunk.room_id.to_string(), chunk);
+            }
 
-        let profile = Profile {
-            displayname,
-            avatar_uri,
-            id: id.to_string(),
-        };
+            from = response.next_batch;
 
-        Ok(profile)
+            if from.is_none() {
+                break;
+            }
+        }
+
+        build_space_hierarchy(client, space_id, &chunks, &mut vec![])
+            .ok_or(SpaceHierarchyError::SpaceNotFound)
     }
 
-    pub async fn room_member(
-        member_id: OwnedUserId,
-        room: &Room,
-    ) -> Result<RoomMember, RoomMemberError> {
-        let member = room
-            .get_member(&member_id)
-            .await
-            .map_err(|_| RoomMemberError::NotFound)?
-            .ok_or(RoomMemberError::NotFound)?;
+    fn build_space_hierarchy(
+        client: &Client,
+        space_id: &str,
+        chunks: &HashMap<String, SpaceHierarchyRoomsChunk>,
+        visited: &mut Vec<String>,
+    ) -> Option<SpaceHierarchy> {
+        let chunk = chunks.get(space_id)?;
+        visited.push(space_id.to_string());
+
+        let mut rooms = vec![];
+        let mut subspaces = vec![];
+
+        for child_state in chunk.children_state.iter() {
+            let Ok(Some(child_id)) = child_state.get_field::<String>("state_key") else {
+                continue;
+            };
 
-        let avatar_uri = member
-            .avatar_url()
-            .and_then(|uri| mxc_to_thumbnail_uri(&uri, ImageSize::default(), ImageMethod::SCALE));
+            // Spaces can link each other, each one is rendered once
+            if visited.contains(&child_id) {
+                continue;
+            }
 
-        let name = member.display_name().ok_or(RoomMemberError::NotFound)?;
+            // Children the server can't preview are left out of the response
+            let Some(child) = chunks.get(&child_id) else {
+                continue;
+            };
 
-        Ok(RoomMember {
-            id: member.user_id().to_string(),
-            name: name.to_string(),
-            avatar_uri,
+            if matches!(child.room_type, Some(RoomType::Space)) {
+                if let Some(subspace) = build_space_hierarchy(client, &child_id, chunks, visited) {
+                    subspaces.push(subspace);
+                }
+            } else {
+                let suggested = child_state
+                    .get_field::<serde_json::Value>("content")
+                    .ok()
+                    .flatten()
+                    .and_then(|content| content["suggested"].as_bool())
+                    .unwrap_or(false);
+
+                rooms.push(SpaceChild {
+                    room: hierarchy_room_item(child),
+                    topic: child.topic.clone(),
+                    members: u64::from(child.num_joined_members),
+                    suggested,
+                    is_joined: client.get_joined_room(&child.room_id).is_some(),
+                });
+            }
+        }
+
+        // Suggested rooms go first, keeping the order of the space otherwise
+        rooms.sort_by_key(|child| !child.suggested);
+
+        Some(SpaceHierarchy {
+            space: hierarchy_room_item(chunk),
+            rooms,
+            subspaces,
         })
     }
 
-    #[derive(Clone)]
-    pub struct AccountInfo {
-        pub name: String,
-        pub avatar_uri: Option<String>,
+    fn hierarchy_room_item(chunk: &SpaceHierarchyRoomsChunk) -> RoomItem {
+        let avatar_uri = chunk
+            .avatar_url
+            .as_ref()
+            .and_then(|uri| mxc_to_thumbnail_uri(uri, ImageSize::default(), ImageMethod::CROP));
+
+        let name = chunk
+            .name
+            .clone()
+            .or_else(|| {
+                chunk
+                    .canonical_alias
+                    .as_ref()
+                    .map(|alias| alias.to_string())
+            })
+            .unwrap_or_else(|| chunk.room_id.to_string());
+
+        RoomItem {
+            avatar_uri,
+            id: chunk.room_id.to_string(),
+            name,
+            is_public: matches!(chunk.join_rule, SpaceRoomJoinRule::Public),
+            is_direct: false,
+        }
     }
 
-    pub async fn account(client: &Client) -> AccountInfo {
-        let avatar = client.account().get_avatar_url().await;
-        let display_name = client.account().get_display_name().await;
-
-        let avatar_uri = avatar
-            .ok()
-            .flatten()
-            .map(|uri| mxc_to_thumbnail_uri(&uri, ImageSize::default(), ImageMethod::CROP))
-            .flatten();
-
-        let name = display_name.ok().flatten().unwrap_or(String::from(""));
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum RoomJoinRule {
+        Public,
+        Invite,
+        Knock,
+        Restricted,
+    }
 
-        AccountInfo { name, avatar_uri }
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct RoomSummary {
+        pub room: RoomItem,
+        pub topic: Option<String>,
+        pub members: u64,
+        pub join_rule: RoomJoinRule,
+        pub world_readable: bool,
     }
 
     #[derive(Debug)]
-    pub enum CreateRoomError {
+    pub enum PeekError {
+        InvalidRoomId,
+        RoomNotFound,
         RequestFailed,
-        InvalidMedia,
-        InvalidInfo,
     }
 
-    pub async fn create_room(
+    /// Summary of a room we are not in, as far as the server lets us see it
+    pub async fn room_summary(client: &Client, room_id: &str) -> Result<RoomSummary, PeekError> {
+        let room_id = RoomId::parse(room_id).map_err(|_| PeekError::InvalidRoomId)?;
+
+        // The hierarchy of a room without children is the room itself
+        let request = assign!(get_hierarchy::v1::Request::new(&room_id), {
+            limit: Some(UInt::from(1u32)),
+            max_depth: Some(UInt::from(0u32)),
+        });
+
+        let response = client
+            .send(request, None)
+            .await
+            .map_err(|_| PeekError::RequestFailed)?;
+
+        let chunk = response
+            .rooms
+            .into_iter()
+            .find(|chunk| chunk.room_id.eq(&room_id))
+            .ok_or(PeekError::RoomNotFound)?;
+
+        let join_rule = match chunk.join_rule {
+            SpaceRoomJoinRule::Public => RoomJoinRule::Public,
+            SpaceRoomJoinRule::Knock => RoomJoinRule::Knock,
+            SpaceRoomJoinRule::Restricted => RoomJoinRule::Restricted,
+            _ => RoomJoinRule::Invite,
+        };
+
+        Ok(RoomSummary {
+            room: hierarchy_room_item(&chunk),
+            topic: chunk.topic.clone(),
+            members: u64::from(chunk.num_joined_members),
+            join_rule,
+            world_readable: chunk.world_readable,
+        })
+    }
+
+    /// Reads the history of a world readable room without joining it, replies
+    /// and threads are shown as plain messages
+    pub async fn peek_messages(
         client: &Client,
-        is_dm: bool,
-        users: &[OwnedUserId],
-        name: Option<String>,
-        avatar: Option<Vec<u8>>,
-    ) -> Result<api::client::room::create_room::v3::Response, CreateRoomError> {
-        let mut request = api::client::room::create_room::v3::Request::new();
+        room_id: &str,
+        limit: u32,
+        from: Option<&str>,
+    ) -> Result<(Option<String>, Vec<TimelineRelation>), PeekError> {
+        let room_id = RoomId::parse(room_id).map_err(|_| PeekError::InvalidRoomId)?;
+        let logged_user_id = client
+            .user_id()
+            .map(|id| id.to_string())
+            .unwrap_or_default();
 
-        let mut init_state_ev_vec: Vec<Raw<AnyInitialStateEvent>> = vec![];
+        let filter = assign!(RoomEventFilter::default(), {
+            lazy_load_options: LazyLoadOptions::Enabled { include_redundant_members: false },
+        });
 
-        if let Some(data) = avatar {
-            let response = client
-                .media()
-                .upload(&mime::IMAGE_JPEG, &data)
-                .await
-                .map_err(|_| CreateRoomError::InvalidMedia)?;
+        let request = assign!(get_message_events::v3::Request::backward(&room_id), {
+            limit: UInt::from(limit),
+            filter,
+            from,
+        });
+
+        let response = client
+            .send(request, None)
+            .await
+            .map_err(|_| PeekError::RequestFailed)?;
+
+        // There is no local room to look members up, the lazy loaded member
+        // events of the response name the senders instead
+        let members = response
+            .state
+            .iter()
+            .filter(|event| {
+                event
+                    .get_field::<String>("type")
+                    .ok()
+                    .flatten()
+                    .is_some_and(|t| t.eq("m.room.member"))
+            })
+            .filter_map(|event| {
+                let id = event.get_field::<String>("state_key").ok().flatten()?;
+                let content = event
+                    .get_field::<serde_json::Value>("content")
+                    .ok()
+                    .flatten()?;
+
+                let member = RoomMember {
+                    id: id.clone(),
+                    name: content["displayname"]
+                        .as_str()
+                        .map(String::from)
+                        .unwrap_or(id.clone()),
+                    avatar_uri: content["avatar_url"].as_str().and_then(|uri| {
+                        mxc_to_thumbnail_uri(
+                            &OwnedMxcUri::from(uri),
+                            ImageSize::default(),
+                            ImageMethod::SCALE,
+                        )
+                    }),
+                };
 
-            let mut avatar_content = RoomAvatarEventContent::new();
-            avatar_content.url = Some(response.content_uri);
+                Some((id, member))
+            })
+            .collect::<HashMap<_, _>>();
 
-            let init_state_ev: InitialStateEvent<RoomAvatarEventContent> = InitialStateEvent {
-                content: avatar_content,
-                state_key: EmptyStateKey,
-            };
+        let mut messages = vec![];
 
-            let raw_init_state_ev =
-                Raw::new(&init_state_ev).map_err(|_| CreateRoomError::InvalidInfo)?;
+        for event in response.chunk.iter() {
+            let Ok(AnyTimelineEvent::MessageLike(AnyMessageLikeEvent::RoomMessage(
+                MessageLikeEvent::Original(original),
+            ))) = event.deserialize()
+            else {
+                continue;
+            };
 
-            let raw_any_init_state_ev: Raw<AnyInitialStateEvent> = raw_init_state_ev.cast();
-            init_state_ev_vec.push(raw_any_init_state_ev);
+            let sender = original.sender.to_string();
+            let member = match members.get(&sender) {
+                Some(member) => RoomMember {
+                    id: member.id.clone(),
+                    name: member.name.clone(),
+                    avatar_uri: member.avatar_uri.clone(),
+                },
+                None => RoomMember {
+                    id: sender.clone(),
+                    name: sender.clone(),
+                    avatar_uri: None,
+                },
+            };
 
-            request.initial_state = &init_state_ev_vec;
+            if let Some(message) = format_original_any_room_message_event(
+                &original.content.msgtype,
+                original.event_id,
+                &member,
+                &logged_user_id,
+                original.origin_server_ts,
+                client,
+            )
+            .await
+            {
+                messages.push(TimelineRelation::None(message));
+            }
         }
 
-        request.name = name.as_deref();
-        request.is_direct = is_dm;
+        // Events come newest first, the timeline keeps the oldest first
+        messages.reverse();
 
-        let visibility = Visibility::Private;
-        if is_dm {
-            request.invite = users;
-            request.visibility = visibility.clone();
-            request.preset = Some(RoomPreset::PrivateChat);
-        }
+        Ok((response.end, messages))
+    }
 
-        client.create_room(request).await.map_err(|e| {
-            log::error!("{:?}", e);
-            CreateRoomError::RequestFailed
-        })
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum ReportTarget {
+        Event { room_id: String, event_id: String },
+        Room { room_id: String },
+        User { user_id: String },
     }
 
-    #[derive(PartialEq, Debug, Clone)]
-    pub enum ImageType {
-        URL(String),
-        Media(Vec<u8>),
+    #[derive(Debug)]
+    pub enum ReportError {
+        InvalidRoomId,
+        InvalidEventId,
+        InvalidUserId,
+        SessionNotFound,
+        Unsupported,
+        RequestFailed,
     }
 
-    #[derive(PartialEq, Debug, Clone)]
-    pub struct FileContent {
-        pub size: Option<u64>,
-        pub body: String,
-        pub source: Option<ImageType>,
+    /// Reports content to the admins of our homeserver. The score goes from
+    /// -100, the most offensive, to 0 and is only sent for events.
+    pub async fn report_content(
+        client: &Client,
+        target: &ReportTarget,
+        reason: Option<&str>,
+        score: i32,
+    ) -> Result<(), ReportError> {
+        match target {
+            ReportTarget::Event { room_id, event_id } => {
+                let room_id = RoomId::parse(room_id).map_err(|_| ReportError::InvalidRoomId)?;
+                let event_id = EventId::parse(event_id).map_err(|_| ReportError::InvalidEventId)?;
+
+                let request = report_content::v3::Request::new(
+                    &room_id,
+                    &event_id,
+                    Some(Int::from(score)),
+                    reason,
+                );
+
+                client
+                    .send(request, None)
+                    .await
+                    .map_err(|_| ReportError::RequestFailed)?;
+
+                Ok(())
+            }
+            ReportTarget::Room { room_id } => {
+                RoomId::parse(room_id).map_err(|_| ReportError::InvalidRoomId)?;
+
+                report_raw(client, &["rooms", room_id, "report"], reason).await
+            }
+            ReportTarget::User { user_id } => {
+                UserId::parse(user_id).map_err(|_| ReportError::InvalidUserId)?;
+
+                report_raw(client, &["users", user_id, "report"], reason).await
+            }
+        }
     }
 
-    #[derive(PartialEq, Debug, Clone)]
-    pub enum TimelineMessageType {
-        Image(FileContent),
-        Text(String),
-        Html(String),
-        File(FileContent),
-        Video(FileContent),
+    // Reporting rooms and users is newer than our ruma, the endpoints are
+    // called by hand
+    async fn report_raw(
+        client: &Client,
+        segments: &[&str],
+        reason: Option<&str>,
+    ) -> Result<(), ReportError> {
+        let session = client.session().ok_or(ReportError::SessionNotFound)?;
+
+        let mut request_url = client
+            .homeserver()
+            .await
+            .join("_matrix/client/v3/")
+            .map_err(|_| ReportError::RequestFailed)?;
+
+        request_url
+            .path_segments_mut()
+            .map_err(|_| ReportError::RequestFailed)?
+            .pop_if_empty()
+            .extend(segments);
+
+        let body = serde_json::json!({ "reason": reason.unwrap_or_default() });
+
+        let res = reqwest::Client::new()
+            .post(request_url)
+            .header("Content-Type", "application/json")
+            .header("Authorization", format!("Bearer {}", session.access_token))
+            .body(body.to_string())
+            .send()
+            .await
+            .map_err(|_| ReportError::RequestFailed)?;
+
+        let status = res.status();
+
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let errcode = res
+            .text()
+            .await
+            .ok()
+            .and_then(|body| serde_json::from_str::<serde_json::Value>(&body).ok())
+            .and_then(|v| v["errcode"].as_str().map(String::from));
+
+        info!("report rejected {:?} {:?}", status, errcode);
+
+        match (status.as_u16(), errcode.as_deref()) {
+            (404, Some("M_UNRECOGNIZED")) | (405, _) => Err(ReportError::Unsupported),
+            _ => Err(ReportError::RequestFailed),
+        }
     }
 
-    #[derive(PartialEq, Debug, Clone)]
-    pub enum EventOrigin {
-        OTHER,
-        ME,
+    #[derive(Debug)]
+    pub enum IgnoreError {
+        InvalidUserId,
+        RequestFailed,
     }
 
-    #[derive(PartialEq, Debug, Clone)]
-    pub struct TimelineMessage {
-        pub event_id: String,
-        pub sender: RoomMember,
-        pub body: TimelineMessageType,
-        pub origin: EventOrigin,
-        pub time: String,
+    /// Users of the ignored list in the account data, as the last sync left it
+    pub async fn ignored_users(client: &Client) -> Vec<String> {
+        client
+            .account()
+            .account_data::<IgnoredUserListEventContent>()
+            .await
+            .ok()
+            .flatten()
+            .and_then(|raw| raw.deserialize().ok())
+            .map(|content| {
+                content
+                    .ignored_users
+                    .keys()
+                    .map(|user_id| user_id.to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
-    #[derive(PartialEq, Debug, Clone)]
-    pub struct TimelineMessageReply {
-        pub event: TimelineMessage,
-        pub reply: Option<TimelineMessage>,
+    /// Replaces the ignored list, the server stops sending us anything from
+    /// the users in it
+    pub async fn set_ignored_users(client: &Client, users: &[String]) -> Result<(), IgnoreError> {
+        let user_ids = users
+            .iter()
+            .map(|user_id| UserId::parse(user_id).map_err(|_| IgnoreError::InvalidUserId))
+            .collect::<Result<Vec<OwnedUserId>, IgnoreError>>()?;
+
+        client
+            .account()
+            .set_account_data(IgnoredUserListEventContent::users(user_ids))
+            .await
+            .map_err(|_| IgnoreError::RequestFailed)?;
+
+        Ok(())
     }
 
-    #[derive(PartialEq, Debug, Clone)]
-    pub struct TimelineMessageThread {
-        pub event_id: String,
-        pub thread: Vec<TimelineMessage>,
+    #[derive(Debug)]
+    pub enum KnockError {
+        InvalidRoomId,
+        InvalidUserId,
+        RoomNotFound,
+        RequestFailed,
     }
 
-    #[derive(PartialEq, Debug, Clone)]
-    pub struct TimelineThread {
-        pub event_id: String,
-        pub thread: Vec<TimelineMessage>,
-        pub latest_event: String,
-        pub count: usize,
+    /// Asks the members of a room with the knock join rule to let us in
+    pub async fn knock_room(
+        client: &Client,
+        room_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), KnockError> {
+        let room_id = RoomId::parse(room_id).map_err(|_| KnockError::InvalidRoomId)?;
+        let servers = via_servers(client, room_id.as_str())
+            .iter()
+            .filter_map(|server| ServerName::parse(server).ok())
+            .collect::<Vec<OwnedServerName>>();
+
+        let request = assign!(knock_room::v3::Request::new(<&RoomOrAliasId>::from(&*room_id)), {
+            reason,
+            server_name: &servers,
+        });
+
+        client
+            .send(request, None)
+            .await
+            .map_err(|_| KnockError::RequestFailed)?;
+
+        Ok(())
     }
 
-    #[derive(PartialEq, Debug, Clone)]
-    pub enum TimelineRelation {
-        None(TimelineMessage),
-        Reply(TimelineMessageReply),
-        CustomThread(TimelineThread),
-        Thread(TimelineMessageThread),
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct KnockRequest {
+        pub member: RoomMember,
+        pub reason: Option<String>,
     }
 
-    #[derive(PartialEq, Debug, Clone)]
-    pub enum TimelineError {
-        RoomNotFound,
-        InvalidLimit,
-        MessagesNotFound,
+    /// Knocks waiting for an answer, only listed to members allowed to both
+    /// invite and kick since those are the answers
+    pub async fn pending_knocks(
+        client: &Client,
+        room_id: &str,
+    ) -> Result<Vec<KnockRequest>, KnockError> {
+        let room_id = RoomId::parse(room_id).map_err(|_| KnockError::InvalidRoomId)?;
+        let room = client
+            .get_joined_room(&room_id)
+            .ok_or(KnockError::RoomNotFound)?;
+
+        if !can_answer_knocks(client, &room).await {
+            return Ok(vec![]);
+        }
+
+        let events = knock_members(client, &room_id).await?;
+
+        Ok(knock_requests(&events))
     }
 
-    pub async fn timeline(
+    // The membership filter of our ruma predates knocking, the endpoint is
+    // called by hand so the list comes from the server and not the store
+    async fn knock_members(
         client: &Client,
         room_id: &RoomId,
-        limit: u64,
-        from: Option<String>,
-        old_messages: Vec<TimelineRelation>,
-        session_data: UserSession,
-    ) -> Result<(Option<String>, Vec<TimelineRelation>), TimelineError> {
-        let mut messages: Vec<TimelineRelation> = old_messages;
+    ) -> Result<Vec<serde_json::Value>, KnockError> {
+        let session = client.session().ok_or(KnockError::RequestFailed)?;
 
-        let room = client
-            .get_room(&room_id)
-            .ok_or(TimelineError::RoomNotFound)?;
+        let mut request_url = client
+            .homeserver()
+            .await
+            .join("_matrix/client/v3/")
+            .map_err(|_| KnockError::RequestFailed)?;
+
+        request_url
+            .path_segments_mut()
+            .map_err(|_| KnockError::RequestFailed)?
+            .pop_if_empty()
+            .extend(&["rooms", room_id.as_str(), "members"]);
+        request_url
+            .query_pairs_mut()
+            .append_pair("membership", "knock");
+
+        let res = reqwest::Client::new()
+            .get(request_url)
+            .header("Authorization", format!("Bearer {}", session.access_token))
+            .send()
+            .await
+            .map_err(|_| KnockError::RequestFailed)?;
 
-        let filter = assign!(RoomEventFilter::default(), {
-            lazy_load_options: LazyLoadOptions::Enabled { include_redundant_members: false },
-        });
-        let options = assign!(MessagesOptions::backward(), {
-            limit: UInt::new(limit).ok_or(TimelineError::InvalidLimit)?,
-            filter,
-            from: from.as_deref()
-        });
+        if !res.status().is_success() {
+            info!("knocks rejected {:?}", res.status());
+            return Err(KnockError::RequestFailed);
+        }
 
-        let m = room
-            .messages(options)
+        let body = res
+            .json::<serde_json::Value>()
             .await
-            .map_err(|_| TimelineError::MessagesNotFound)?;
+            .map_err(|_| KnockError::RequestFailed)?;
 
-        let t = TimelineSlice::new(
-            m.chunk.into_iter().map(SyncTimelineEvent::from).collect(),
-            m.start,
-            m.end.clone(),
-            false,
-            false,
-        );
+        Ok(body["chunk"].as_array().cloned().unwrap_or_default())
+    }
 
-        for sync_timeline_event in t.events.iter() {
-            let deserialized = deserialize_any_timeline_event(
-                sync_timeline_event
-                    .event
-                    .deserialize()
-                    .expect("can't deserialize iter events: timeline"),
-                &room,
-                &session_data.user_id,
-                &client,
-            )
-            .await;
+    /// Knocks out of a list of member events, other memberships are skipped
+    pub fn knock_requests(events: &[serde_json::Value]) -> Vec<KnockRequest> {
+        events
+            .iter()
+            .filter_map(|event| {
+                let id = event["state_key"].as_str()?.to_string();
+                let content = &event["content"];
+
+                if !content["membership"]
+                    .as_str()
+                    .is_some_and(|m| m.eq("knock"))
+                {
+                    return None;
+                }
 
-            if let Some(timeline_relation) = deserialized {
-                match &timeline_relation {
-                    TimelineRelation::Thread(thread) => {
-                        // Position of an existing thread timeline
+                Some(KnockRequest {
+                    member: RoomMember {
+                        id: id.clone(),
+                        name: content["displayname"]
+                            .as_str()
+                            .map(String::from)
+                            .unwrap_or(id),
+                        avatar_uri: content["avatar_url"].as_str().and_then(|uri| {
+                            mxc_to_thumbnail_uri(
+                                &OwnedMxcUri::from(uri),
+                                ImageSize::default(),
+                                ImageMethod::SCALE,
+                            )
+                        }),
+                    },
+                    reason: content["reason"].as_str().map(String::from),
+                })
+            })
+            .collect()
+    }
 
-                        let position = messages.iter().position(|m| {
-                            if let TimelineRelation::CustomThread(thread) = m {
-                                thread.event_id.eq(&thread.event_id)
-                            } else {
-                                false
-                            }
-                        });
+    async fn can_answer_knocks(client: &Client, room: &Joined) -> bool {
+        let Some(user_id) = client.user_id() else {
+            return false;
+        };
 
-                        if let Some(p) = position {
-                            if let TimelineRelation::CustomThread(ref mut timeline_thread) =
-                                messages[p]
-                            {
-                                timeline_thread.thread.push(thread.thread[0].clone());
-                                timeline_thread.thread.rotate_right(1);
-                            };
-                        } else {
-                            let n = TimelineRelation::CustomThread(TimelineThread {
-                                event_id: thread.event_id.clone(),
-                                thread: thread.thread.clone(),
-                                latest_event: thread.thread[thread.thread.len() - 1]
-                                    .clone()
-                                    .event_id,
-                                count: thread.thread.len(),
-                            });
+        let Ok(Some(member)) = room.get_member_no_sync(user_id).await else {
+            return false;
+        };
 
-                            messages.push(n);
-                            messages.rotate_right(1);
-                        }
-                    }
-                    TimelineRelation::None(message) => {
-                        // Position of a head thread timeline
-                        let position = messages.iter().position(|m| {
-                            let TimelineRelation::CustomThread(thread) = m else {
-                                return false;
-                            };
+        let content = room
+            .get_state_event(StateEventType::RoomPowerLevels, "")
+            .await
+            .ok()
+            .flatten()
+            .and_then(|event| {
+                event
+                    .get_field::<serde_json::Value>("content")
+                    .ok()
+                    .flatten()
+            })
+            .unwrap_or_default();
 
-                            thread.event_id.eq(&message.event_id)
-                        });
+        // Defaults of the spec when the power levels leave them out
+        let invite = content["invite"].as_i64().unwrap_or(0);
+        let kick = content["kick"].as_i64().unwrap_or(50);
 
-                        let Some(p) = position else {
-                            messages.push(timeline_relation);
-                            messages.rotate_right(1);
-                            continue;
-                        };
+        member.power_level() >= invite && member.power_level() >= kick
+    }
 
-                        if let TimelineRelation::CustomThread(ref mut timeline_thread) = messages[p]
-                        {
-                            let formatted_thread = format_head_thread(
-                                sync_timeline_event
-                                    .event
-                                    .deserialize()
-                                    .expect("can't deserialize event custom thread: timeline"),
-                            );
+    /// Lets the knocking user in by inviting them
+    pub async fn approve_knock(
+        client: &Client,
+        room_id: &str,
+        user_id: &str,
+    ) -> Result<(), KnockError> {
+        let room_id = RoomId::parse(room_id).map_err(|_| KnockError::InvalidRoomId)?;
+        let user_id = UserId::parse(user_id).map_err(|_| KnockError::InvalidUserId)?;
+        let room = client
+            .get_joined_room(&room_id)
+            .ok_or(KnockError::RoomNotFound)?;
 
-                            if let Some((_, latest_event)) = formatted_thread {
-                                timeline_thread.latest_event = latest_event;
-                            }
-                            timeline_thread.thread.push(message.clone());
-                            timeline_thread.thread.rotate_right(1);
-                        };
-                    }
-                    _ => {
-                        messages.push(timeline_relation);
-                        messages.rotate_right(1);
-                    }
-                }
-            }
-        }
+        room.invite_user_by_id(&user_id)
+            .await
+            .map_err(|_| KnockError::RequestFailed)
+    }
 
-        Ok((m.end, messages))
+    /// Turns the knock down, kicking a knocking user retracts their knock
+    pub async fn deny_knock(
+        client: &Client,
+        room_id: &str,
+        user_id: &str,
+    ) -> Result<(), KnockError> {
+        let room_id = RoomId::parse(room_id).map_err(|_| KnockError::InvalidRoomId)?;
+        let user_id = UserId::parse(user_id).map_err(|_| KnockError::InvalidUserId)?;
+        let room = client
+            .get_joined_room(&room_id)
+            .ok_or(KnockError::RoomNotFound)?;
+
+        room.kick_user(&user_id, None)
+            .await
+            .map_err(|_| KnockError::RequestFailed)
     }
 
-    pub fn format_head_thread(ev: AnySyncTimelineEvent) -> Option<(usize, String)> {
-        let AnySyncTimelineEvent::MessageLike(AnySyncMessageLikeEvent::RoomMessage(
-            SyncMessageLikeEvent::Original(original),
-        )) = ev
-        else {
-            return None;
-        };
+    pub enum LeaveRoomError {
+        InvalidRoomId,
+        RoomNotFound,
+        Failed,
+    }
 
-        original
-            .unsigned
-            .relations
-            .map(|relations| {
-                relations.thread.map(|thread| {
-                    (
-                        2,
-                        thread
-                            .latest_event
-                            .deserialize()
-                            .expect("can't deserialize latest event: format_head_thread")
-                            .event_id()
-                            .to_string(),
-                    )
-                })
-            })
-            .flatten()
+    pub async fn leave_room(client: &Client, id: &str) -> Result<(), LeaveRoomError> {
+        let room_id = RoomId::parse(id).map_err(|_| LeaveRoomError::InvalidRoomId)?;
+        let room = client
+            .get_joined_room(&room_id)
+            .ok_or(LeaveRoomError::RoomNotFound)?;
+
+        room.leave().await.map_err(|_| LeaveRoomError::Failed)
     }
 
-    pub async fn deserialize_any_timeline_event(
-        event: AnySyncTimelineEvent,
-        room: &Room,
-        logged_user_id: &str,
-        client: &Client,
-    ) -> Option<TimelineRelation> {
-        log::info!("{:?}", event);
-        let AnySyncTimelineEvent::MessageLike(AnySyncMessageLikeEvent::RoomMessage(
-            SyncMessageLikeEvent::Original(original),
-        )) = event
-        else {
-            return None;
-        };
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct RoomMember {
+        pub id: String,
+        pub name: String,
+        pub avatar_uri: Option<String>,
+    }
 
-        let message_type = &original.content.msgtype;
-        let event_id = original.event_id;
+    pub enum RoomMemberError {
+        NotFound,
+    }
 
-        let Ok(member) = room_member(original.sender, &room).await else {
-            return None;
-        };
+    #[derive(Clone, Debug)]
+    pub enum FindUserError {
+        InvalidUserId,
+        UserNotFound,
+        InvalidUsername,
+    }
 
-        let relates = &original.content.relates_to;
-        let time = original.origin_server_ts;
+    pub async fn find_user_by_id(id: &str, client: &Client) -> Result<Profile, FindUserError> {
+        let u = UserId::parse(&id).map_err(|_| FindUserError::InvalidUserId)?;
 
-        let formatted_message = format_original_any_room_message_event(
-            &message_type,
-            event_id,
-            &member,
-            &logged_user_id,
-            time,
-            &client,
-        )
-        .await;
+        let u = u.deref();
 
-        let mut message_result = None;
+        let request = matrix_sdk::ruma::api::client::profile::get_profile::v3::Request::new(u);
 
-        let Some(relation) = relates else {
-            if let Some(message) = formatted_message {
-                message_result = Some(TimelineRelation::None(message));
-            }
+        let response = client
+            .send(request, None)
+            .await
+            .map_err(|_| FindUserError::UserNotFound)?;
 
-            return message_result;
+        let displayname = response.displayname.ok_or(FindUserError::InvalidUsername)?;
+
+        let avatar_uri = response
+            .avatar_url
+            .map(|uri| {
+                mxc_to_thumbnail_uri(
+                    &uri,
+                    ImageSize {
+                        width: 48,
+                        height: 48,
+                    },
+                    ImageMethod::CROP,
+                )
+            })
+            .flatten();
+
+        let profile = Profile {
+            displayname,
+            avatar_uri,
+            id: id.to_string(),
         };
 
-        match &relation {
-            Relation::_Custom => {
-                if let Some(message) = formatted_message {
-                    message_result = Some(TimelineRelation::None(message));
-                }
-            }
-            _ => {
-                if let Some(message) = formatted_message {
-                    message_result = format_relation_from_event(
-                        &message_type,
-                        relates,
-                        &room,
-                        message,
-                        &member,
-                        &logged_user_id,
-                        time,
-                        &client,
-                    )
-                    .await;
-                }
-            }
-        }
-        message_result
+        Ok(profile)
     }
 
-    pub async fn deserialize_timeline_event(
-        event: AnyTimelineEvent,
+    pub async fn room_member(
+        member_id: OwnedUserId,
         room: &Room,
-        logged_user_id: &str,
-        client: &Client,
-    ) -> Option<TimelineMessage> {
-        let AnyTimelineEvent::MessageLike(AnyMessageLikeEvent::RoomMessage(
-            MessageLikeEvent::Original(original),
-        )) = event
-        else {
-            return None;
-        };
+    ) -> Result<RoomMember, RoomMemberError> {
+        let member = room
+            .get_member(&member_id)
+            .await
+            .map_err(|_| RoomMemberError::NotFound)?
+            .ok_or(RoomMemberError::NotFound)?;
 
-        let Ok(member) = room_member(original.sender, &room).await else {
-            return None;
-        };
+        let avatar_uri = member
+            .avatar_url()
+            .and_then(|uri| mxc_to_thumbnail_uri(&uri, ImageSize::default(), ImageMethod::SCALE));
 
-        let message_type = &original.content.msgtype;
-        let event_id = original.event_id;
-        let time = original.origin_server_ts;
+        let name = member.display_name().ok_or(RoomMemberError::NotFound)?;
 
-        let message_result = format_original_any_room_message_event(
-            &message_type,
-            event_id,
-            &member,
-            &logged_user_id,
-            time,
-            &client,
-        )
-        .await;
+        Ok(RoomMember {
+            id: member.user_id().to_string(),
+            name: name.to_string(),
+            avatar_uri,
+        })
+    }
 
-        message_result
+    #[derive(Clone)]
+    pub struct AccountInfo {
+        pub name: String,
+        pub avatar_uri: Option<String>,
     }
 
-    pub async fn format_original_any_room_message_event(
-        n: &MessageType,
-        event: OwnedEventId,
-        member: &RoomMember,
-        logged_user_id: &str,
-        time: MilliSecondsSinceUnixEpoch,
-        client: &Client,
-    ) -> Option<TimelineMessage> {
-        let mut message_result = None;
+    pub async fn account(client: &Client) -> AccountInfo {
+        let avatar = client.account().get_avatar_url().await;
+        let display_name = client.account().get_display_name().await;
+
+        let avatar_uri = avatar
+            .ok()
+            .flatten()
+            .map(|uri| mxc_to_thumbnail_uri(&uri, ImageSize::default(), ImageMethod::CROP))
+            .flatten();
+
+        let name = display_name.ok().flatten().unwrap_or(String::from(""));
+
+        AccountInfo { name, avatar_uri }
+    }
+
+    #[derive(Debug)]
+    pub enum CreateRoomError {
+        RequestFailed,
+        InvalidMedia,
+        InvalidInfo,
+    }
+
+    pub async fn create_room(
+        client: &Client,
+        is_dm: bool,
+        users: &[OwnedUserId],
+        name: Option<String>,
+        avatar: Option<Vec<u8>>,
+    ) -> Result<api::client::room::create_room::v3::Response, CreateRoomError> {
+        let mut request = api::client::room::create_room::v3::Request::new();
+
+        let mut init_state_ev_vec: Vec<Raw<AnyInitialStateEvent>> = vec![];
+
+        if let Some(data) = avatar {
+            init_state_ev_vec.push(avatar_initial_state(client, &data).await?);
+
+            request.initial_state = &init_state_ev_vec;
+        }
+
+        request.name = name.as_deref();
+        request.is_direct = is_dm;
+
+        let visibility = Visibility::Private;
+        if is_dm {
+            request.invite = users;
+            request.visibility = visibility.clone();
+            request.preset = Some(RoomPreset::PrivateChat);
+        }
+
+        let response = client.create_room(request).await.map_err(|e| {
+            log::error!("{:?}", e);
+            CreateRoomError::RequestFailed
+        })?;
+
+        // The room is already created, a DM missing from m.direct still works
+        if is_dm {
+            if let Err(e) = mark_direct_room(client, &response.room_id, users).await {
+                log::error!("failed to mark {} as direct {e:?}", response.room_id);
+            }
+        }
+
+        Ok(response)
+    }
+
+    #[derive(Debug)]
+    pub enum DirectError {
+        InvalidRoomId,
+        InvalidUserId,
+        RoomNotFound,
+        MembersNotFound,
+        RequestFailed,
+    }
+
+    async fn direct_rooms(client: &Client) -> BTreeMap<OwnedUserId, Vec<OwnedRoomId>> {
+        client
+            .account()
+            .account_data::<DirectEventContent>()
+            .await
+            .ok()
+            .flatten()
+            .and_then(|raw| raw.deserialize().ok())
+            .map(|content| content.0)
+            .unwrap_or_default()
+    }
+
+    /// A joined DM with the user, as listed in the m.direct account data,
+    /// where the user is still joined or invited
+    pub async fn find_direct_room(
+        client: &Client,
+        user_id: &str,
+    ) -> Result<Option<Joined>, DirectError> {
+        let user_id: OwnedUserId =
+            UserId::parse(user_id.trim()).map_err(|_| DirectError::InvalidUserId)?;
+
+        let rooms = direct_rooms(client)
+            .await
+            .remove(&user_id)
+            .unwrap_or_default();
+
+        for room_id in rooms {
+            let Some(room) = client.get_joined_room(&room_id) else {
+                continue;
+            };
+
+            let Ok(members) = room.active_members().await else {
+                continue;
+            };
+
+            if members.iter().any(|member| member.user_id().eq(&user_id)) {
+                return Ok(Some(room));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn mark_direct_room(
+        client: &Client,
+        room_id: &RoomId,
+        users: &[OwnedUserId],
+    ) -> Result<(), DirectError> {
+        let mut direct = direct_rooms(client).await;
+
+        for user_id in users {
+            let rooms = direct.entry(user_id.clone()).or_default();
+
+            if !rooms.iter().any(|id| id.eq(room_id)) {
+                rooms.push(room_id.to_owned());
+            }
+        }
+
+        client
+            .account()
+            .set_account_data(DirectEventContent(direct))
+            .await
+            .map_err(|_| DirectError::RequestFailed)?;
+
+        Ok(())
+    }
+
+    /// Turns a joined room into a DM with its other members, or back into a
+    /// regular room removing it from every entry of m.direct
+    pub async fn set_room_direct(
+        client: &Client,
+        room_id: &str,
+        is_direct: bool,
+    ) -> Result<(), DirectError> {
+        let room_id = RoomId::parse(room_id).map_err(|_| DirectError::InvalidRoomId)?;
+        let room = client
+            .get_joined_room(&room_id)
+            .ok_or(DirectError::RoomNotFound)?;
+
+        if is_direct {
+            // Lazy loaded rooms may not have every member in the store yet,
+            // users who left are not part of the DM
+            let users = room
+                .active_members()
+                .await
+                .map_err(|_| DirectError::MembersNotFound)?
+                .into_iter()
+                .map(|member| member.user_id().to_owned())
+                .filter(|user_id| client.user_id().is_some_and(|me| !me.eq(user_id)))
+                .collect::<Vec<_>>();
+
+            if users.is_empty() {
+                return Err(DirectError::MembersNotFound);
+            }
+
+            return mark_direct_room(client, &room_id, &users).await;
+        }
+
+        let mut direct = direct_rooms(client).await;
+
+        direct
+            .values_mut()
+            .for_each(|rooms| rooms.retain(|id| !id.eq(&room_id)));
+        direct.retain(|_, rooms| !rooms.is_empty());
+
+        client
+            .account()
+            .set_account_data(DirectEventContent(direct))
+            .await
+            .map_err(|_| DirectError::RequestFailed)?;
+
+        Ok(())
+    }
+
+    async fn avatar_initial_state(
+        client: &Client,
+        data: &[u8],
+    ) -> Result<Raw<AnyInitialStateEvent>, CreateRoomError> {
+        let response = client
+            .media()
+            .upload(&mime::IMAGE_JPEG, data)
+            .await
+            .map_err(|_| CreateRoomError::InvalidMedia)?;
+
+        let mut avatar_content = RoomAvatarEventContent::new();
+        avatar_content.url = Some(response.content_uri);
+
+        let init_state_ev: InitialStateEvent<RoomAvatarEventContent> = InitialStateEvent {
+            content: avatar_content,
+            state_key: EmptyStateKey,
+        };
+
+        let raw_init_state_ev =
+            Raw::new(&init_state_ev).map_err(|_| CreateRoomError::InvalidInfo)?;
+
+        Ok(raw_init_state_ev.cast())
+    }
+
+    fn raw_json<T>(value: serde_json::Value) -> Result<Raw<T>, CreateRoomError> {
+        serde_json::value::to_raw_value(&value)
+            .map(Raw::from_json)
+            .map_err(|_| CreateRoomError::InvalidInfo)
+    }
+
+    // Servers to join a room through, its own server and the one of the user
+    fn via_servers(client: &Client, room_id: &str) -> Vec<String> {
+        let mut via = vec![];
+
+        if let Some((_, server)) = room_id.split_once(':') {
+            via.push(server.to_string());
+        }
+
+        if let Some(user_id) = client.user_id() {
+            let server = user_id.server_name().to_string();
+
+            if !via.contains(&server) {
+                via.push(server);
+            }
+        }
+
+        via
+    }
+
+    pub async fn create_space(
+        client: &Client,
+        name: &str,
+        topic: Option<&str>,
+        is_public: bool,
+        avatar: Option<Vec<u8>>,
+    ) -> Result<api::client::room::create_room::v3::Response, CreateRoomError> {
+        let mut request = api::client::room::create_room::v3::Request::new();
+
+        let mut initial_state: Vec<Raw<AnyInitialStateEvent>> = vec![];
+
+        if let Some(data) = avatar {
+            initial_state.push(avatar_initial_state(client, &data).await?);
+        }
+
+        request.initial_state = &initial_state;
+        request.name = Some(name);
+        request.topic = topic;
+        request.creation_content = Some(raw_json(serde_json::json!({ "type": "m.space" }))?);
+        // Only admins post in the space room itself, members talk in its rooms
+        request.power_level_content_override =
+            Some(raw_json(serde_json::json!({ "events_default": 100 }))?);
+
+        if is_public {
+            request.visibility = Visibility::Public;
+            request.preset = Some(RoomPreset::PublicChat);
+        } else {
+            request.visibility = Visibility::Private;
+            request.preset = Some(RoomPreset::PrivateChat);
+        }
+
+        client.create_room(request).await.map_err(|e| {
+            log::error!("{:?}", e);
+            CreateRoomError::RequestFailed
+        })
+    }
+
+    /// Creates a room linked to the space, joinable by any member of the space
+    pub async fn create_room_in_space(
+        client: &Client,
+        space_id: &str,
+        name: &str,
+        topic: Option<&str>,
+    ) -> Result<api::client::room::create_room::v3::Response, CreateRoomError> {
+        let mut request = api::client::room::create_room::v3::Request::new();
+
+        let initial_state: Vec<Raw<AnyInitialStateEvent>> = vec![
+            raw_json(serde_json::json!({
+                "type": "m.space.parent",
+                "state_key": space_id,
+                "content": { "via": via_servers(client, space_id), "canonical": true },
+            }))?,
+            raw_json(serde_json::json!({
+                "type": "m.room.join_rules",
+                "state_key": "",
+                "content": {
+                    "join_rule": "restricted",
+                    "allow": [{ "type": "m.room_membership", "room_id": space_id }],
+                },
+            }))?,
+        ];
+
+        // Restricted join rules need room version 8 or later
+        let room_version = RoomVersionId::V10;
+
+        request.initial_state = &initial_state;
+        request.name = Some(name);
+        request.topic = topic;
+        request.room_version = Some(&room_version);
+        request.visibility = Visibility::Private;
+        request.preset = Some(RoomPreset::PrivateChat);
+
+        let response = client.create_room(request).await.map_err(|e| {
+            log::error!("{:?}", e);
+            CreateRoomError::RequestFailed
+        })?;
+
+        add_space_child(client, space_id, response.room_id.as_str())
+            .await
+            .map_err(|_| CreateRoomError::RequestFailed)?;
+
+        Ok(response)
+    }
+
+    #[derive(Debug)]
+    pub enum SpaceError {
+        InvalidRoomId,
+        InvalidUserId,
+        SpaceNotFound,
+        RequestFailed,
+    }
+
+    fn joined_space(client: &Client, space_id: &str) -> Result<Joined, SpaceError> {
+        let room_id = RoomId::parse(space_id).map_err(|_| SpaceError::InvalidRoomId)?;
+
+        client
+            .get_joined_room(&room_id)
+            .ok_or(SpaceError::SpaceNotFound)
+    }
+
+    pub async fn add_space_child(
+        client: &Client,
+        space_id: &str,
+        child_id: &str,
+    ) -> Result<(), SpaceError> {
+        let space = joined_space(client, space_id)?;
+        let child_room_id = RoomId::parse(child_id).map_err(|_| SpaceError::InvalidRoomId)?;
+
+        space
+            .send_state_event_raw(
+                serde_json::json!({ "via": via_servers(client, child_id) }),
+                "m.space.child",
+                child_id,
+            )
+            .await
+            .map_err(|_| SpaceError::RequestFailed)?;
+
+        // The parent link needs enough power in the child room, the space works without it
+        if let Some(child) = client.get_joined_room(&child_room_id) {
+            if let Err(e) = child
+                .send_state_event_raw(
+                    serde_json::json!({ "via": via_servers(client, space_id) }),
+                    "m.space.parent",
+                    space_id,
+                )
+                .await
+            {
+                info!("failed to link {child_id} to its space {e:?}");
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn remove_space_child(
+        client: &Client,
+        space_id: &str,
+        child_id: &str,
+    ) -> Result<(), SpaceError> {
+        let space = joined_space(client, space_id)?;
+        let child_room_id = RoomId::parse(child_id).map_err(|_| SpaceError::InvalidRoomId)?;
+
+        // An empty content removes the link
+        space
+            .send_state_event_raw(serde_json::json!({}), "m.space.child", child_id)
+            .await
+            .map_err(|_| SpaceError::RequestFailed)?;
+
+        if let Some(child) = client.get_joined_room(&child_room_id) {
+            if let Err(e) = child
+                .send_state_event_raw(serde_json::json!({}), "m.space.parent", space_id)
+                .await
+            {
+                info!("failed to unlink {child_id} from its space {e:?}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Invites the user to the space and to every room of the space we are in
+    pub async fn invite_to_space(
+        client: &Client,
+        space_id: &str,
+        user_id: &str,
+    ) -> Result<(), SpaceError> {
+        let space = joined_space(client, space_id)?;
+        let user_id = UserId::parse(user_id).map_err(|_| SpaceError::InvalidUserId)?;
+
+        space
+            .invite_user_by_id(&user_id)
+            .await
+            .map_err(|_| SpaceError::RequestFailed)?;
+
+        let space_room = Room::Joined(space);
+
+        for child_id in linked_rooms(&space_room, StateEventType::SpaceChild).await {
+            let Some(child) = RoomId::parse(&child_id)
+                .ok()
+                .and_then(|id| client.get_joined_room(&id))
+            else {
+                continue;
+            };
+
+            // Restricted rooms are joinable from the space, an invite still lists them
+            if let Err(e) = child.invite_user_by_id(&user_id).await {
+                info!("failed to invite {user_id} to {child_id} {e:?}");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub enum ImageType {
+        URL(String),
+        Media(Vec<u8>),
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct FileContent {
+        pub size: Option<u64>,
+        pub body: String,
+        pub source: Option<ImageType>,
+        #[serde(default)]
+        pub info: Option<MediaInfo>,
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct MediaInfo {
+        pub width: u64,
+        pub height: u64,
+        pub blurhash: Option<String>,
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct AudioContent {
+        pub file: FileContent,
+        /// Milliseconds
+        pub duration: Option<u64>,
+        /// Amplitudes of a voice message, from 0 to 1024
+        pub waveform: Option<Vec<u16>>,
+        pub is_voice: bool,
+    }
+
+    fn audio_content(audio: &AudioMessageEventContent, source: Option<ImageType>) -> AudioContent {
+        let info = audio.info.as_ref();
+        let extensible = audio.audio.as_ref();
+
+        let size = info.and_then(|info| info.size).map(|size| u64::from(size));
+
+        let duration = info
+            .and_then(|info| info.duration)
+            .or_else(|| extensible.and_then(|audio| audio.duration))
+            .map(|duration| duration.as_millis() as u64);
+
+        let waveform = extensible
+            .and_then(|audio| audio.waveform.as_ref())
+            .map(|waveform| {
+                waveform
+                    .iter()
+                    .map(|amplitude| u16::try_from(u64::from(amplitude.get())).unwrap_or(u16::MAX))
+                    .collect()
+            });
+
+        AudioContent {
+            file: FileContent {
+                size,
+                body: audio.body.clone(),
+                source,
+                info: None,
+            },
+            duration,
+            waveform,
+            is_voice: audio.voice.is_some(),
+        }
+    }
+
+    fn media_info(
+        width: Option<UInt>,
+        height: Option<UInt>,
+        blurhash: Option<&String>,
+    ) -> Option<MediaInfo> {
+        match (width, height) {
+            (Some(width), Some(height)) => Some(MediaInfo {
+                width: u64::from(width),
+                height: u64::from(height),
+                blurhash: blurhash.cloned(),
+            }),
+            _ => None,
+        }
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub enum TimelineMessageType {
+        Image(FileContent),
+        Text(String),
+        Html(String),
+        File(FileContent),
+        Video(FileContent),
+        Audio(AudioContent),
+        Emote(FormattedContent),
+        Notice(FormattedContent),
+        Poll(PollContent),
+        Location(LocationContent),
+        Beacon(BeaconContent),
+        Sticker(FileContent),
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct FormattedContent {
+        pub body: String,
+        /// Html body, when the event carries one
+        pub formatted: Option<String>,
+    }
+
+    /// The html body is rendered as is, so it only keeps the tags and
+    /// attributes allowed by the spec
+    fn formatted_content(body: &str, formatted: Option<&FormattedBody>) -> FormattedContent {
+        FormattedContent {
+            body: body.to_string(),
+            formatted: formatted
+                .filter(|formatted| matches!(formatted.format, MessageFormat::Html))
+                .map(|formatted| {
+                    sanitize_html(
+                        &formatted.body,
+                        HtmlSanitizerMode::Compat,
+                        RemoveReplyFallback::No,
+                    )
+                }),
+        }
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct LocationContent {
+        pub latitude: f64,
+        pub longitude: f64,
+        /// Radius in meters, from the `u` parameter of the uri
+        pub accuracy: Option<f64>,
+        pub description: Option<String>,
+    }
+
+    impl LocationContent {
+        /// Reads `geo:` uris (RFC 5870), like `geo:51.5008,0.1247;u=35`
+        pub fn from_geo_uri(uri: &str, description: Option<String>) -> Option<Self> {
+            let uri = uri.trim().strip_prefix("geo:")?;
+            let mut parts = uri.split(';');
+
+            let mut coordinates = parts.next()?.split(',');
+            let latitude = coordinates.next()?.trim().parse::<f64>().ok()?;
+            let longitude = coordinates.next()?.trim().parse::<f64>().ok()?;
+
+            if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                return None;
+            }
+
+            let accuracy = parts
+                .filter_map(|param| param.split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("u"))
+                .and_then(|(_, value)| value.trim().parse::<f64>().ok())
+                .filter(|accuracy| *accuracy > 0.0);
+
+            Some(Self {
+                latitude,
+                longitude,
+                accuracy,
+                description: description.filter(|description| !description.trim().is_empty()),
+            })
+        }
+
+        pub fn openstreetmap_url(&self) -> String {
+            format!(
+                "https://www.openstreetmap.org/?mlat={lat}&mlon={lon}#map=16/{lat}/{lon}",
+                lat = self.latitude,
+                lon = self.longitude
+            )
+        }
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub enum EventOrigin {
+        OTHER,
+        ME,
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct TimelineMessage {
+        pub event_id: String,
+        pub sender: RoomMember,
+        pub body: TimelineMessageType,
+        pub origin: EventOrigin,
+        pub time: String,
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct TimelineMessageReply {
+        pub event: TimelineMessage,
+        pub reply: Option<TimelineMessage>,
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct TimelineMessageThread {
+        pub event_id: String,
+        pub thread: Vec<TimelineMessage>,
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct TimelineThread {
+        pub event_id: String,
+        pub thread: Vec<TimelineMessage>,
+        pub latest_event: String,
+        pub count: usize,
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub enum TimelineRelation {
+        None(TimelineMessage),
+        Reply(TimelineMessageReply),
+        CustomThread(TimelineThread),
+        Thread(TimelineMessageThread),
+    }
+
+    #[derive(PartialEq, Debug, Clone)]
+    pub enum TimelineError {
+        RoomNotFound,
+        InvalidLimit,
+        MessagesNotFound,
+    }
+
+    pub async fn timeline(
+        client: &Client,
+        room_id: &RoomId,
+        limit: u64,
+        from: Option<String>,
+        old_messages: Vec<TimelineRelation>,
+        session_data: UserSession,
+    ) -> Result<(Option<String>, Vec<TimelineRelation>), TimelineError> {
+        let mut messages: Vec<TimelineRelation> = old_messages;
+
+        let room = client
+            .get_room(&room_id)
+            .ok_or(TimelineError::RoomNotFound)?;
+
+        let filter = assign!(RoomEventFilter::default(), {
+            lazy_load_options: LazyLoadOptions::Enabled { include_redundant_members: false },
+        });
+        let options = assign!(MessagesOptions::backward(), {
+            limit: UInt::new(limit).ok_or(TimelineError::InvalidLimit)?,
+            filter,
+            from: from.as_deref()
+        });
+
+        let m = room
+            .messages(options)
+            .await
+            .map_err(|_| TimelineError::MessagesNotFound)?;
+
+        let t = TimelineSlice::new(
+            m.chunk.into_iter().map(SyncTimelineEvent::from).collect(),
+            m.start,
+            m.end.clone(),
+            false,
+            false,
+        );
+
+        let ignored = ignored_users(client).await;
+
+        // Polls and live locations need their relations, fetched all at once
+        // instead of one request after another
+        let room_ref = &room;
+        let related = join_all(
+            t.events
+                .iter()
+                .filter_map(|sync_timeline_event| {
+                    let raw = sync_timeline_event
+                        .event
+                        .deserialize_as::<serde_json::Value>()
+                        .ok()?;
+                    let raw_type = raw["type"].as_str()?;
+                    let sender = raw["sender"].as_str()?;
+
+                    if ignored.iter().any(|user| user.eq(sender)) {
+                        return None;
+                    }
+
+                    let is_poll = POLL_START_TYPES.contains(&raw_type);
+                    if !is_poll && !BEACON_INFO_TYPES.contains(&raw_type) {
+                        return None;
+                    }
+
+                    Some((raw["event_id"].as_str()?.to_string(), is_poll))
+                })
+                .map(|(event_id, is_poll)| async move {
+                    let relations = if is_poll {
+                        poll_relations(client, room_ref, &event_id).await
+                    } else {
+                        beacon_relations(client, room_ref, &event_id).await
+                    };
+
+                    (event_id, relations)
+                }),
+        )
+        .await
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+        for sync_timeline_event in t.events.iter() {
+            let is_ignored = sync_timeline_event
+                .event
+                .get_field::<String>("sender")
+                .ok()
+                .flatten()
+                .is_some_and(|sender| ignored.contains(&sender));
+
+            if is_ignored {
+                continue;
+            }
+
+            let raw_event = sync_timeline_event
+                .event
+                .deserialize_as::<serde_json::Value>()
+                .unwrap_or_default();
+            let raw_type = raw_event["type"].as_str().unwrap_or_default();
+            let raw_event_id = raw_event["event_id"].as_str().unwrap_or_default();
+
+            // Polls aren't room messages, their votes come along with them
+            if POLL_START_TYPES.contains(&raw_type) {
+                let relations = related.get(raw_event_id).cloned().unwrap_or_default();
+
+                if let Some(poll) =
+                    format_poll_start(&raw_event, &relations, &room, &session_data.user_id).await
+                {
+                    messages.push(TimelineRelation::None(poll));
+                    messages.rotate_right(1);
+                }
+
+                continue;
+            }
+
+            // Live locations are state events, placed at their latest position
+            if BEACON_INFO_TYPES.contains(&raw_type) {
+                let relations = related.get(raw_event_id).cloned().unwrap_or_default();
+
+                if let Some(beacon) =
+                    format_beacon_info(&raw_event, &relations, &room, &session_data.user_id).await
+                {
+                    messages.push(TimelineRelation::None(beacon));
+                    messages.rotate_right(1);
+                }
+
+                continue;
+            }
+
+            let deserialized = deserialize_any_timeline_event(
+                sync_timeline_event
+                    .event
+                    .deserialize()
+                    .expect("can't deserialize iter events: timeline"),
+                &room,
+                &session_data.user_id,
+                &client,
+            )
+            .await;
+
+            if let Some(timeline_relation) = deserialized {
+                match &timeline_relation {
+                    TimelineRelation::Thread(thread) => {
+                        // Position of an existing thread timeline
+
+                        let position = messages.iter().position(|m| {
+                            if let TimelineRelation::CustomThread(thread) = m {
+                                thread.event_id.eq(&thread.event_id)
+                            } else {
+                                false
+                            }
+                        });
+
+                        if let Some(p) = position {
+                            if let TimelineRelation::CustomThread(ref mut timeline_thread) =
+                                messages[p]
+                            {
+                                timeline_thread.thread.push(thread.thread[0].clone());
+                                timeline_thread.thread.rotate_right(1);
+                            };
+                        } else {
+                            let n = TimelineRelation::CustomThread(TimelineThread {
+                                event_id: thread.event_id.clone(),
+                                thread: thread.thread.clone(),
+                                latest_event: thread.thread[thread.thread.len() - 1]
+                                    .clone()
+                                    .event_id,
+                                count: thread.thread.len(),
+                            });
+
+                            messages.push(n);
+                            messages.rotate_right(1);
+                        }
+                    }
+                    TimelineRelation::None(message) => {
+                        // Position of a head thread timeline
+                        let position = messages.iter().position(|m| {
+                            let TimelineRelation::CustomThread(thread) = m else {
+                                return false;
+                            };
+
+                            thread.event_id.eq(&message.event_id)
+                        });
+
+                        let Some(p) = position else {
+                            messages.push(timeline_relation);
+                            messages.rotate_right(1);
+                            continue;
+                        };
+
+                        if let TimelineRelation::CustomThread(ref mut timeline_thread) = messages[p]
+                        {
+                            let formatted_thread = format_head_thread(
+                                sync_timeline_event
+                                    .event
+                                    .deserialize()
+                                    .expect("can't deserialize event custom thread: timeline"),
+                            );
+
+                            if let Some((_, latest_event)) = formatted_thread {
+                                timeline_thread.latest_event = latest_event;
+                            }
+                            timeline_thread.thread.push(message.clone());
+                            timeline_thread.thread.rotate_right(1);
+                        };
+                    }
+                    _ => {
+                        messages.push(timeline_relation);
+                        messages.rotate_right(1);
+                    }
+                }
+            }
+        }
+
+        Ok((m.end, messages))
+    }
+
+    #[derive(PartialEq, Debug, Clone)]
+    pub enum RoomMediaError {
+        InvalidRoomId,
+        RoomNotFound,
+        InvalidLimit,
+        RequestFailed,
+    }
+
+    /// Pages backwards through the images, videos, audio and files of a room.
+    /// The server only returns events with a url, encrypted rooms hide it so
+    /// every encrypted event is fetched and filtered once decrypted
+    pub async fn room_media(
+        client: &Client,
+        room_id: &str,
+        limit: u64,
+        from: Option<String>,
+    ) -> Result<(Option<String>, Vec<TimelineMessage>), RoomMediaError> {
+        let room_id = RoomId::parse(room_id).map_err(|_| RoomMediaError::InvalidRoomId)?;
+        let room = client
+            .get_room(&room_id)
+            .ok_or(RoomMediaError::RoomNotFound)?;
+
+        let logged_user_id = client
+            .user_id()
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+
+        let filter = if room.is_encrypted().await.unwrap_or(false) {
+            assign!(RoomEventFilter::default(), {
+                types: Some(vec![String::from("m.room.encrypted")]),
+            })
+        } else {
+            assign!(RoomEventFilter::default(), {
+                types: Some(vec![String::from("m.room.message")]),
+                url_filter: Some(UrlFilter::EventsWithUrl),
+            })
+        };
+
+        let options = assign!(MessagesOptions::backward(), {
+            limit: UInt::new(limit).ok_or(RoomMediaError::InvalidLimit)?,
+            filter,
+            from: from.as_deref()
+        });
+
+        let response = room
+            .messages(options)
+            .await
+            .map_err(|_| RoomMediaError::RequestFailed)?;
+
+        let mut media = vec![];
+
+        for event in response.chunk.iter() {
+            let Ok(AnyTimelineEvent::MessageLike(AnyMessageLikeEvent::RoomMessage(
+                MessageLikeEvent::Original(original),
+            ))) = event.event.deserialize()
+            else {
+                continue;
+            };
+
+            if !matches!(
+                original.content.msgtype,
+                MessageType::Image(_)
+                    | MessageType::Video(_)
+                    | MessageType::Audio(_)
+                    | MessageType::File(_)
+            ) {
+                continue;
+            }
+
+            let sender = original.sender.to_string();
+            let member = room_member(original.sender, &room)
+                .await
+                .unwrap_or_else(|_| RoomMember {
+                    id: sender.clone(),
+                    name: sender,
+                    avatar_uri: None,
+                });
+
+            if let Some(message) = format_original_any_room_message_event(
+                &original.content.msgtype,
+                original.event_id,
+                &member,
+                &logged_user_id,
+                original.origin_server_ts,
+                client,
+            )
+            .await
+            {
+                media.push(message);
+            }
+        }
+
+        Ok((response.end, media))
+    }
+
+    pub fn format_head_thread(ev: AnySyncTimelineEvent) -> Option<(usize, String)> {
+        let AnySyncTimelineEvent::MessageLike(AnySyncMessageLikeEvent::RoomMessage(
+            SyncMessageLikeEvent::Original(original),
+        )) = ev
+        else {
+            return None;
+        };
+
+        original
+            .unsigned
+            .relations
+            .map(|relations| {
+                relations.thread.map(|thread| {
+                    (
+                        2,
+                        thread
+                            .latest_event
+                            .deserialize()
+                            .expect("can't deserialize latest event: format_head_thread")
+                            .event_id()
+                            .to_string(),
+                    )
+                })
+            })
+            .flatten()
+    }
+
+    pub async fn deserialize_any_timeline_event(
+        event: AnySyncTimelineEvent,
+        room: &Room,
+        logged_user_id: &str,
+        client: &Client,
+    ) -> Option<TimelineRelation> {
+        log::info!("{:?}", event);
+        let original = match event {
+            AnySyncTimelineEvent::MessageLike(AnySyncMessageLikeEvent::RoomMessage(
+                SyncMessageLikeEvent::Original(original),
+            )) => original,
+            AnySyncTimelineEvent::MessageLike(AnySyncMessageLikeEvent::Sticker(
+                SyncMessageLikeEvent::Original(sticker),
+            )) => {
+                return format_sticker_event(&sticker, room, logged_user_id)
+                    .await
+                    .map(TimelineRelation::None);
+            }
+            _ => return None,
+        };
+
+        let message_type = &original.content.msgtype;
+        let event_id = original.event_id;
+
+        let Ok(member) = room_member(original.sender, &room).await else {
+            return None;
+        };
+
+        let relates = &original.content.relates_to;
+        let time = original.origin_server_ts;
+
+        let formatted_message = format_original_any_room_message_event(
+            &message_type,
+            event_id,
+            &member,
+            &logged_user_id,
+            time,
+            &client,
+        )
+        .await;
+
+        let mut message_result = None;
+
+        let Some(relation) = relates else {
+            if let Some(message) = formatted_message {
+                message_result = Some(TimelineRelation::None(message));
+            }
+
+            return message_result;
+        };
+
+        match &relation {
+            Relation::_Custom => {
+                if let Some(message) = formatted_message {
+                    message_result = Some(TimelineRelation::None(message));
+                }
+            }
+            _ => {
+                if let Some(message) = formatted_message {
+                    message_result = format_relation_from_event(
+                        &message_type,
+                        relates,
+                        &room,
+                        message,
+                        &member,
+                        &logged_user_id,
+                        time,
+                        &client,
+                    )
+                    .await;
+                }
+            }
+        }
+        message_result
+    }
+
+    pub async fn deserialize_timeline_event(
+        event: AnyTimelineEvent,
+        room: &Room,
+        logged_user_id: &str,
+        client: &Client,
+    ) -> Option<TimelineMessage> {
+        let original = match event {
+            AnyTimelineEvent::MessageLike(AnyMessageLikeEvent::RoomMessage(
+                MessageLikeEvent::Original(original),
+            )) => original,
+            AnyTimelineEvent::MessageLike(AnyMessageLikeEvent::Sticker(
+                MessageLikeEvent::Original(sticker),
+            )) => return format_sticker_event(&sticker.into(), room, logged_user_id).await,
+            _ => return None,
+        };
+
+        let Ok(member) = room_member(original.sender, &room).await else {
+            return None;
+        };
+
+        let message_type = &original.content.msgtype;
+        let event_id = original.event_id;
+        let time = original.origin_server_ts;
+
+        let message_result = format_original_any_room_message_event(
+            &message_type,
+            event_id,
+            &member,
+            &logged_user_id,
+            time,
+            &client,
+        )
+        .await;
+
+        message_result
+    }
+
+    pub async fn format_original_any_room_message_event(
+        n: &MessageType,
+        event: OwnedEventId,
+        member: &RoomMember,
+        logged_user_id: &str,
+        time: MilliSecondsSinceUnixEpoch,
+        client: &Client,
+    ) -> Option<TimelineMessage> {
+        let mut message_result = None;
+
+        let timestamp = {
+            let d = UNIX_EPOCH + Duration::from_millis(time.0.into());
+
+            let datetime = DateTime::<Local>::from(d);
+            datetime.format("%H:%M").to_string()
+        };
+
+        match &n {
+            MessageType::Image(message_event_content) => match &message_event_content.source {
+                MediaSource::Plain(mx_uri) => {
+                    let https_uri = mxc_to_download_uri(&mx_uri);
+
+                    let size = message_event_content
+                        .info
+                        .as_ref()
+                        .and_then(|file_info| {
+                            file_info
+                                .size
+                                .map(|size| size.to_string().parse::<u64>().ok())
+                        })
+                        .flatten();
+
+                    if let Some(uri) = https_uri {
+                        message_result = Some(TimelineMessage {
+                            event_id: event.to_string(),
+                            sender: member.clone(),
+                            body: TimelineMessageType::Image(FileContent {
+                                size,
+                                body: message_event_content.body.clone(),
+                                source: Some(ImageType::URL(uri)),
+                                info: message_event_content.info.as_ref().and_then(|info| {
+                                    media_info(info.width, info.height, info.blurhash.as_ref())
+                                }),
+                            }),
+                            origin: if member.id.eq(logged_user_id) {
+                                EventOrigin::ME
+                            } else {
+                                EventOrigin::OTHER
+                            },
+                            time: timestamp,
+                        });
+                    }
+                }
+                MediaSource::Encrypted(_) => {
+                    let media_content = client
+                        .media()
+                        .get_media_content(
+                            &MediaRequest {
+                                source: message_event_content.source.clone(),
+                                format: MediaFormat::Thumbnail(MediaThumbnailSize {
+                                    method: Method::Crop,
+                                    width: UInt::new(16).unwrap(),
+                                    height: UInt::new(16).unwrap(),
+                                }),
+                            },
+                            true,
+                        )
+                        .await;
+
+                    let size = message_event_content
+                        .info
+                        .as_ref()
+                        .and_then(|file_info| {
+                            file_info
+                                .size
+                                .map(|size| size.to_string().parse::<u64>().ok())
+                        })
+                        .flatten();
+
+                    if let Ok(content) = media_content {
+                        message_result = Some(TimelineMessage {
+                            event_id: event.to_string(),
+                            sender: member.clone(),
+                            body: TimelineMessageType::Image(FileContent {
+                                size,
+                                body: message_event_content.body.clone(),
+                                source: Some(ImageType::Media(content)),
+                                info: message_event_content.info.as_ref().and_then(|info| {
+                                    media_info(info.width, info.height, info.blurhash.as_ref())
+                                }),
+                            }),
+                            origin: if member.id.eq(logged_user_id) {
+                                EventOrigin::ME
+                            } else {
+                                EventOrigin::OTHER
+                            },
+                            time: timestamp,
+                        });
+                    }
+                }
+            },
+            MessageType::Text(content) => {
+                message_result = Some(TimelineMessage {
+                    event_id: event.to_string(),
+                    sender: member.clone(),
+                    body: TimelineMessageType::Text(content.body.clone()),
+                    origin: if member.id.eq(logged_user_id) {
+                        EventOrigin::ME
+                    } else {
+                        EventOrigin::OTHER
+                    },
+                    time: timestamp,
+                });
+
+                if let Some(formatted) = &content.formatted {
+                    match formatted.format {
+                        MessageFormat::Html => {
+                            if let Some(ref mut message) = message_result {
+                                message.body = TimelineMessageType::Html(formatted.body.clone());
+                            }
+                        }
+                        _ => {}
+                    }
+                };
+            }
+            MessageType::Emote(content) => {
+                message_result = Some(TimelineMessage {
+                    event_id: event.to_string(),
+                    sender: member.clone(),
+                    body: TimelineMessageType::Emote(formatted_content(
+                        &content.body,
+                        content.formatted.as_ref(),
+                    )),
+                    origin: if member.id.eq(logged_user_id) {
+                        EventOrigin::ME
+                    } else {
+                        EventOrigin::OTHER
+                    },
+                    time: timestamp,
+                });
+            }
+            MessageType::Notice(content) => {
+                message_result = Some(TimelineMessage {
+                    event_id: event.to_string(),
+                    sender: member.clone(),
+                    body: TimelineMessageType::Notice(formatted_content(
+                        &content.body,
+                        content.formatted.as_ref(),
+                    )),
+                    origin: if member.id.eq(logged_user_id) {
+                        EventOrigin::ME
+                    } else {
+                        EventOrigin::OTHER
+                    },
+                    time: timestamp,
+                });
+            }
+            MessageType::File(message) => match &message.source {
+                MediaSource::Plain(mx_uri) => {
+                    let uri = mxc_to_download_uri(&mx_uri);
+                    let source = uri.and_then(|uri| Some(ImageType::URL(uri)));
+
+                    let size = message
+                        .info
+                        .as_ref()
+                        .and_then(|file_info| {
+                            file_info
+                                .size
+                                .map(|size| size.to_string().parse::<u64>().ok())
+                        })
+                        .flatten();
+
+                    message_result = Some(TimelineMessage {
+                        event_id: event.to_string(),
+                        sender: member.clone(),
+                        body: TimelineMessageType::File(FileContent {
+                            size,
+                            body: message.body.clone(),
+                            source,
+                            info: None,
+                        }),
+                        origin: if member.id.eq(logged_user_id) {
+                            EventOrigin::ME
+                        } else {
+                            EventOrigin::OTHER
+                        },
+                        time: timestamp,
+                    });
+                }
+                MediaSource::Encrypted(file) => {
+                    let uri = mxc_to_download_uri(&file.url);
+                    let source = uri.and_then(|uri| Some(ImageType::URL(uri)));
+
+                    let size = message
+                        .info
+                        .as_ref()
+                        .and_then(|file_info| {
+                            file_info
+                                .size
+                                .map(|size| size.to_string().parse::<u64>().ok())
+                        })
+                        .flatten();
+
+                    message_result = Some(TimelineMessage {
+                        event_id: event.to_string(),
+                        sender: member.clone(),
+                        body: TimelineMessageType::File(FileContent {
+                            size,
+                            body: message.body.clone(),
+                            source,
+                            info: None,
+                        }),
+                        origin: if member.id.eq(logged_user_id) {
+                            EventOrigin::ME
+                        } else {
+                            EventOrigin::OTHER
+                        },
+                        time: timestamp,
+                    });
+                }
+            },
+            MessageType::Video(video) => match &video.source {
+                MediaSource::Plain(mx_uri) => {
+                    let uri = mxc_to_download_uri(&mx_uri);
+                    let source = uri.and_then(|uri| Some(ImageType::URL(uri)));
+
+                    let size = video
+                        .info
+                        .as_ref()
+                        .and_then(|file_info| {
+                            file_info
+                                .size
+                                .map(|size| size.to_string().parse::<u64>().ok())
+                        })
+                        .flatten();
+
+                    message_result = Some(TimelineMessage {
+                        event_id: event.to_string(),
+                        sender: member.clone(),
+                        body: TimelineMessageType::Video(FileContent {
+                            size,
+                            body: video.body.clone(),
+                            source,
+                            info: video.info.as_ref().and_then(|info| {
+                                media_info(info.width, info.height, info.blurhash.as_ref())
+                            }),
+                        }),
+                        origin: if member.id.eq(logged_user_id) {
+                            EventOrigin::ME
+                        } else {
+                            EventOrigin::OTHER
+                        },
+                        time: timestamp,
+                    });
+                }
+                MediaSource::Encrypted(_) => {
+                    let message_content = client
+                        .media()
+                        .get_media_content(
+                            &MediaRequest {
+                                source: video.source.clone(),
+                                format: MediaFormat::File,
+                            },
+                            true,
+                        )
+                        .await;
+
+                    let size = video
+                        .info
+                        .as_ref()
+                        .and_then(|file_info| {
+                            file_info
+                                .size
+                                .map(|size| size.to_string().parse::<u64>().ok())
+                        })
+                        .flatten();
+
+                    if let Ok(content) = message_content {
+                        message_result = Some(TimelineMessage {
+                            event_id: event.to_string(),
+                            sender: member.clone(),
+                            body: TimelineMessageType::Video(FileContent {
+                                size,
+                                body: video.body.clone(),
+                                source: Some(ImageType::Media(content)),
+                                info: video.info.as_ref().and_then(|info| {
+                                    media_info(info.width, info.height, info.blurhash.as_ref())
+                                }),
+                            }),
+                            origin: if member.id.eq(logged_user_id) {
+                                EventOrigin::ME
+                            } else {
+                                EventOrigin::OTHER
+                            },
+                            time: timestamp,
+                        });
+                    }
+                }
+            },
+            MessageType::Audio(audio) => match &audio.source {
+                MediaSource::Plain(mx_uri) => {
+                    let uri = mxc_to_download_uri(&mx_uri);
+                    let source = uri.and_then(|uri| Some(ImageType::URL(uri)));
+
+                    message_result = Some(TimelineMessage {
+                        event_id: event.to_string(),
+                        sender: member.clone(),
+                        body: TimelineMessageType::Audio(audio_content(audio, source)),
+                        origin: if member.id.eq(logged_user_id) {
+                            EventOrigin::ME
+                        } else {
+                            EventOrigin::OTHER
+                        },
+                        time: timestamp,
+                    });
+                }
+                MediaSource::Encrypted(_) => {
+                    let message_content = client
+                        .media()
+                        .get_media_content(
+                            &MediaRequest {
+                                source: audio.source.clone(),
+                                format: MediaFormat::File,
+                            },
+                            true,
+                        )
+                        .await;
+
+                    if let Ok(content) = message_content {
+                        message_result = Some(TimelineMessage {
+                            event_id: event.to_string(),
+                            sender: member.clone(),
+                            body: TimelineMessageType::Audio(audio_content(
+                                audio,
+                                Some(ImageType::Media(content)),
+                            )),
+                            origin: if member.id.eq(logged_user_id) {
+                                EventOrigin::ME
+                            } else {
+                                EventOrigin::OTHER
+                            },
+                            time: timestamp,
+                        });
+                    }
+                }
+            },
+            MessageType::Location(location) => {
+                let body = (!location.body.starts_with("geo:")).then(|| location.body.clone());
+
+                if let Some(content) = LocationContent::from_geo_uri(&location.geo_uri, body) {
+                    message_result = Some(TimelineMessage {
+                        event_id: event.to_string(),
+                        sender: member.clone(),
+                        body: TimelineMessageType::Location(content),
+                        origin: if member.id.eq(logged_user_id) {
+                            EventOrigin::ME
+                        } else {
+                            EventOrigin::OTHER
+                        },
+                        time: timestamp,
+                    });
+                }
+            }
+            _ => {
+                info!("unsuported message_type matrix");
+            }
+        }
+
+        return message_result;
+    }
+
+    /// Stickers are images sent on their own, outside of room messages
+    pub async fn format_sticker_event(
+        event: &OriginalSyncMessageLikeEvent<StickerEventContent>,
+        room: &Room,
+        logged_user_id: &str,
+    ) -> Option<TimelineMessage> {
+        let uri = mxc_to_download_uri(&event.content.url)?;
+        let member = room_member(event.sender.clone(), room)
+            .await
+            .unwrap_or_else(|_| RoomMember {
+                id: event.sender.to_string(),
+                name: event.sender.to_string(),
+                avatar_uri: None,
+            });
+        let info = &event.content.info;
+
+        let timestamp = {
+            let d = UNIX_EPOCH + Duration::from_millis(event.origin_server_ts.0.into());
+
+            let datetime = DateTime::<Local>::from(d);
+            datetime.format("%H:%M").to_string()
+        };
+
+        Some(TimelineMessage {
+            event_id: event.event_id.to_string(),
+            origin: if member.id.eq(logged_user_id) {
+                EventOrigin::ME
+            } else {
+                EventOrigin::OTHER
+            },
+            sender: member,
+            body: TimelineMessageType::Sticker(FileContent {
+                size: info.size.map(u64::from),
+                body: event.content.body.clone(),
+                source: Some(ImageType::URL(uri)),
+                info: media_info(info.width, info.height, info.blurhash.as_ref()),
+            }),
+            time: timestamp,
+        })
+    }
+
+    pub async fn format_relation_from_event(
+        n: &MessageType,
+        relates: &Option<Relation>,
+        room: &Room,
+        message_result: TimelineMessage,
+        member: &RoomMember,
+        logged_user_id: &str,
+        time: MilliSecondsSinceUnixEpoch,
+        client: &Client,
+    ) -> Option<TimelineRelation> {
+        let Some(r) = relates else {
+            return None;
+        };
+        match r {
+            Relation::Reply { in_reply_to } => {
+                let event = room.event(&in_reply_to.event_id).await.ok()?;
+                let timestamp = {
+                    let d = UNIX_EPOCH + Duration::from_millis(time.0.into());
+                    let datetime = DateTime::<Utc>::from(d);
+                    datetime.format("%H:%M").to_string()
+                };
+
+                let desc_event = event
+                    .event
+                    .deserialize()
+                    .expect("can't deserialize event: format_relation_from_event");
+
+                let reply =
+                    deserialize_timeline_event(desc_event, room, &logged_user_id, &client).await;
+
+                reply.map(|r| {
+                    let mut final_message = TimelineMessageReply {
+                        event: message_result,
+                        reply: Some(r.clone()),
+                    };
+
+                    match &r.body {
+                        TimelineMessageType::Image(_) => {
+                            if n.body().contains("sent an image.") {
+                                let to_remove = format!("> <{}> {}", r.sender.id, "sent an image.");
+
+                                let uncleared_content = n.body();
+                                let n = uncleared_content.replace(&to_remove, "").clone();
+
+                                let content_body = TimelineMessageType::Text(n);
+                                let event = event.event.deserialize().ok()?.event_id().to_string();
+                                final_message.event = TimelineMessage {
+                                    event_id: event,
+                                    sender: member.clone(),
+                                    body: content_body,
+                                    origin: if member.id.eq(logged_user_id) {
+                                        EventOrigin::ME
+                                    } else {
+                                        EventOrigin::OTHER
+                                    },
+                                    time: timestamp,
+                                };
+                            }
+                        }
+                        TimelineMessageType::Text(body) => {
+                            if body.starts_with(">") {
+                                let to_remove =
+                                    format!("> <{}> {}", r.clone().sender.id, body.trim());
+
+                                let uncleared_content = n.body();
+                                let n = uncleared_content.replace(&to_remove, "").clone();
+
+                                let content_body = TimelineMessageType::Text(n);
+                                let event = event.event.deserialize().ok()?.event_id().to_string();
+                                final_message.event = TimelineMessage {
+                                    event_id: event,
+                                    sender: member.clone(),
+                                    body: content_body,
+                                    origin: if member.id.eq(logged_user_id) {
+                                        EventOrigin::ME
+                                    } else {
+                                        EventOrigin::OTHER
+                                    },
+                                    time: timestamp,
+                                };
+                            } else {
+                                final_message.reply = Some(r);
+                            }
+                        }
+                        TimelineMessageType::Html(_) => {
+                            final_message.reply = Some(r);
+                        }
+                        TimelineMessageType::File(_) => {
+                            final_message.reply = Some(r);
+                        }
+                        TimelineMessageType::Video(_) => {
+                            final_message.reply = Some(r);
+                        }
+                        TimelineMessageType::Audio(_) => {
+                            final_message.reply = Some(r);
+                        }
+                        TimelineMessageType::Emote(_)
+                        | TimelineMessageType::Notice(_)
+                        | TimelineMessageType::Poll(_)
+                        | TimelineMessageType::Location(_)
+                        | TimelineMessageType::Beacon(_)
+                        | TimelineMessageType::Sticker(_) => {
+                            final_message.reply = Some(r);
+                        }
+                    }
+
+                    Some(TimelineRelation::Reply(final_message))
+                })?
+            }
+            Relation::Thread(in_reply_to) => {
+                let final_message = TimelineMessageThread {
+                    event_id: in_reply_to.event_id.to_string(),
+                    thread: vec![message_result.clone()],
+                };
+
+                Some(TimelineRelation::Thread(final_message))
+            }
+            _ => None,
+        }
+    }
+
+    pub const POLL_START_TYPES: [&str; 2] = ["m.poll.start", "org.matrix.msc3381.poll.start"];
+    pub const POLL_RESPONSE_TYPES: [&str; 2] =
+        ["m.poll.response", "org.matrix.msc3381.poll.response"];
+    pub const POLL_END_TYPES: [&str; 2] = ["m.poll.end", "org.matrix.msc3381.poll.end"];
+
+    const POLL_MAX_ANSWERS: usize = 20;
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub enum PollKind {
+        Disclosed,
+        /// Results are shown once the poll ends
+        Undisclosed,
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct PollAnswer {
+        pub id: String,
+        pub text: String,
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct PollVote {
+        pub answers: Vec<String>,
+        /// Milliseconds since the epoch of the response
+        pub time: u64,
+    }
+
+    /// Who may redact the events of others, as the power levels of the room
+    /// set it
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct RedactPower {
+        pub redact: i64,
+        pub users_default: i64,
+        pub users: HashMap<String, i64>,
+    }
+
+    impl Default for RedactPower {
+        // Defaults of the spec when the power levels leave them out
+        fn default() -> Self {
+            Self {
+                redact: 50,
+                users_default: 0,
+                users: HashMap::new(),
+            }
+        }
+    }
+
+    impl RedactPower {
+        pub fn from_power_levels(content: &serde_json::Value) -> Self {
+            let default = Self::default();
+
+            Self {
+                redact: content["redact"].as_i64().unwrap_or(default.redact),
+                users_default: content["users_default"]
+                    .as_i64()
+                    .unwrap_or(default.users_default),
+                users: content["users"]
+                    .as_object()
+                    .map(|users| {
+                        users
+                            .iter()
+                            .filter_map(|(user_id, level)| Some((user_id.clone(), level.as_i64()?)))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            }
+        }
+
+        pub fn allows(&self, user_id: &str) -> bool {
+            self.users
+                .get(user_id)
+                .copied()
+                .unwrap_or(self.users_default)
+                >= self.redact
+        }
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct PollContent {
+        pub question: String,
+        pub kind: PollKind,
+        pub max_selections: u64,
+        pub answers: Vec<PollAnswer>,
+        pub creator: String,
+        /// Latest vote of each user
+        pub votes: HashMap<String, PollVote>,
+        pub ended_at: Option<u64>,
+        /// The end was sent from here and `ended_at` is a guess until the
+        /// server event comes back
+        #[serde(default)]
+        pub end_is_local: bool,
+        /// Latest server timestamp of the poll and its relations, the local
+        /// clock may be off so actions sent from here are placed there
+        #[serde(default)]
+        pub latest_ts: u64,
+        /// Besides the creator, moderators can end the poll
+        #[serde(default)]
+        pub redact_power: RedactPower,
+    }
+
+    impl PollContent {
+        pub fn is_ended(&self) -> bool {
+            self.ended_at.is_some()
+        }
+
+        pub fn can_end(&self, user_id: &str) -> bool {
+            self.creator.eq(user_id) || self.redact_power.allows(user_id)
+        }
+
+        /// Keeps the latest response of each user sent before the end of the
+        /// poll. Unknown answers are dropped, leaving a spoiled vote
+        pub fn apply_response(&mut self, sender: &str, answers: &[String], time: u64) {
+            self.latest_ts = self.latest_ts.max(time);
+
+            if !self.end_is_local && self.ended_at.is_some_and(|end| time > end) {
+                return;
+            }
+
+            if self.votes.get(sender).is_some_and(|vote| vote.time > time) {
+                return;
+            }
+
+            let mut valid: Vec<String> = vec![];
+            for answer in answers {
+                if self.answers.iter().any(|a| a.id.eq(answer)) && !valid.contains(answer) {
+                    valid.push(answer.clone());
+                }
+            }
+            valid.truncate(self.max_selections as usize);
+
+            self.votes.insert(
+                sender.to_string(),
+                PollVote {
+                    answers: valid,
+                    time,
+                },
+            );
+        }
+
+        /// Only the creator or a moderator can end a poll, votes sent
+        /// afterwards don't count. The server timestamp replaces the one of
+        /// an end sent from here
+        pub fn apply_end(&mut self, sender: &str, time: u64) {
+            self.latest_ts = self.latest_ts.max(time);
+
+            if !self.can_end(sender) {
+                return;
+            }
+
+            if !self.end_is_local && self.ended_at.is_some_and(|end| end <= time) {
+                return;
+            }
+
+            self.ended_at = Some(time);
+            self.end_is_local = false;
+            self.votes.retain(|_, vote| vote.time <= time);
+        }
+
+        /// A vote sent from here replaces the previous one of the user until
+        /// the server event comes back with its actual timestamp
+        pub fn apply_local_response(&mut self, sender: &str, answers: &[String]) {
+            self.apply_response(sender, answers, self.latest_ts);
+        }
+
+        /// Closes the poll right after sending its end, votes are left as they
+        /// are until the server tells when it actually ended
+        pub fn apply_local_end(&mut self) {
+            if self.ended_at.is_none() {
+                self.ended_at = Some(self.latest_ts);
+                self.end_is_local = true;
+            }
+        }
+
+        /// Applies a response or end event related to this poll
+        pub fn apply_event(&mut self, event: &serde_json::Value) {
+            let event_type = event["type"].as_str().unwrap_or_default();
+            let sender = event["sender"].as_str().unwrap_or_default();
+            let time = event["origin_server_ts"].as_u64().unwrap_or_default();
+            let content = &event["content"];
+
+            if POLL_RESPONSE_TYPES.contains(&event_type) {
+                let selections = content["m.selections"]
+                    .as_array()
+                    .or_else(|| content["org.matrix.msc3381.poll.response"]["answers"].as_array())
+                    .map(|answers| {
+                        answers
+                            .iter()
+                            .filter_map(|answer| answer.as_str().map(String::from))
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+
+                self.apply_response(sender, &selections, time);
+            } else if POLL_END_TYPES.contains(&event_type) {
+                self.apply_end(sender, time);
+            }
+        }
+
+        /// Number of votes of each answer, in the order of the answers
+        pub fn tally(&self) -> Vec<usize> {
+            self.answers
+                .iter()
+                .map(|answer| {
+                    self.votes
+                        .values()
+                        .filter(|vote| vote.answers.contains(&answer.id))
+                        .count()
+                })
+                .collect()
+        }
+
+        pub fn selection_of(&self, user_id: &str) -> Vec<String> {
+            self.votes
+                .get(user_id)
+                .map(|vote| vote.answers.clone())
+                .unwrap_or_default()
+        }
+    }
+
+    /// Text of an extensible event block, stable polls use a list of
+    /// representations while the unstable ones a single string
+    fn poll_text(value: &serde_json::Value) -> Option<String> {
+        value["m.text"]
+            .as_array()
+            .and_then(|texts| {
+                texts.iter().find(|text| {
+                    text["mimetype"]
+                        .as_str()
+                        .map_or(true, |m| m.eq("text/plain"))
+                })
+            })
+            .and_then(|text| text["body"].as_str())
+            .or_else(|| value["org.matrix.msc1767.text"].as_str())
+            .or_else(|| value["body"].as_str())
+            .map(String::from)
+    }
+
+    fn parse_poll_start(content: &serde_json::Value, creator: &str) -> Option<PollContent> {
+        let poll = [
+            &content["m.poll"],
+            &content["org.matrix.msc3381.poll.start"],
+        ]
+        .into_iter()
+        .find(|poll| poll.is_object())?;
+
+        let kind = match poll["kind"].as_str() {
+            Some(
+                "m.undisclosed" | "m.poll.undisclosed" | "org.matrix.msc3381.poll.undisclosed",
+            ) => PollKind::Undisclosed,
+            _ => PollKind::Disclosed,
+        };
+
+        let answers = poll["answers"]
+            .as_array()?
+            .iter()
+            .filter_map(|answer| {
+                Some(PollAnswer {
+                    id: answer["m.id"]
+                        .as_str()
+                        .or_else(|| answer["id"].as_str())?
+                        .to_string(),
+                    text: poll_text(answer)?,
+                })
+            })
+            .take(POLL_MAX_ANSWERS)
+            .collect::<Vec<_>>();
+
+        if answers.is_empty() {
+            return None;
+        }
+
+        Some(PollContent {
+            question: poll_text(&poll["question"])?,
+            kind,
+            max_selections: poll["max_selections"].as_u64().unwrap_or(1).max(1),
+            answers,
+            creator: creator.to_string(),
+            votes: HashMap::new(),
+            ended_at: None,
+            end_is_local: false,
+            latest_ts: 0,
+            redact_power: RedactPower::default(),
+        })
+    }
+
+    /// Turns a poll start event into a timeline message, `relations` are the
+    /// responses and end events known so far
+    pub async fn format_poll_start(
+        event: &serde_json::Value,
+        relations: &[serde_json::Value],
+        room: &Room,
+        logged_user_id: &str,
+    ) -> Option<TimelineMessage> {
+        let event_type = event["type"].as_str()?;
+        if !POLL_START_TYPES.contains(&event_type) {
+            return None;
+        }
+
+        let sender = event["sender"].as_str()?;
+
+        let mut poll = parse_poll_start(&event["content"], sender)?;
+        poll.redact_power = room_redact_power(room).await;
+        poll.latest_ts = event["origin_server_ts"].as_u64().unwrap_or_default();
+
+        let mut relations = relations.iter().collect::<Vec<_>>();
+        relations.sort_by_key(|event| event["origin_server_ts"].as_u64().unwrap_or_default());
+        for relation in relations {
+            poll.apply_event(relation);
+        }
+
+        format_raw_event(event, TimelineMessageType::Poll(poll), room, logged_user_id).await
+    }
+
+    async fn room_redact_power(room: &Room) -> RedactPower {
+        room.get_state_event(StateEventType::RoomPowerLevels, "")
+            .await
+            .ok()
+            .flatten()
+            .and_then(|event| {
+                event
+                    .get_field::<serde_json::Value>("content")
+                    .ok()
+                    .flatten()
+            })
+            .map(|content| RedactPower::from_power_levels(&content))
+            .unwrap_or_default()
+    }
+
+    /// Timeline message of an event that ruma doesn't know about
+    async fn format_raw_event(
+        event: &serde_json::Value,
+        body: TimelineMessageType,
+        room: &Room,
+        logged_user_id: &str,
+    ) -> Option<TimelineMessage> {
+        let sender = event["sender"].as_str()?;
+        let event_id = event["event_id"].as_str()?;
+        let time = event["origin_server_ts"].as_u64().unwrap_or_default();
+
+        let member = match UserId::parse(sender) {
+            Ok(user_id) => room_member(user_id, room).await.ok(),
+            Err(_) => None,
+        }
+        .unwrap_or_else(|| RoomMember {
+            id: sender.to_string(),
+            name: sender.to_string(),
+            avatar_uri: None,
+        });
 
         let timestamp = {
-            let d = UNIX_EPOCH + Duration::from_millis(time.0.into());
+            let d = UNIX_EPOCH + Duration::from_millis(time);
 
             let datetime = DateTime::<Local>::from(d);
             datetime.format("%H:%M").to_string()
         };
 
-        match &n {
-            MessageType::Image(message_event_content) => match &message_event_content.source {
-                MediaSource::Plain(mx_uri) => {
-                    let https_uri = mxc_to_download_uri(&mx_uri);
+        Some(TimelineMessage {
+            event_id: event_id.to_string(),
+            origin: if sender.eq(logged_user_id) {
+                EventOrigin::ME
+            } else {
+                EventOrigin::OTHER
+            },
+            sender: member,
+            body,
+            time: timestamp,
+        })
+    }
 
-                    let size = message_event_content
-                        .info
-                        .as_ref()
-                        .and_then(|file_info| {
-                            file_info
-                                .size
-                                .map(|size| size.to_string().parse::<u64>().ok())
-                        })
-                        .flatten();
+    /// Responses and end events of a poll
+    pub async fn poll_relations(
+        client: &Client,
+        room: &Room,
+        event_id: &str,
+    ) -> Vec<serde_json::Value> {
+        // A few pages are enough for a quick vote
+        reference_relations(client, room, event_id, 5).await
+    }
 
-                    if let Some(uri) = https_uri {
-                        message_result = Some(TimelineMessage {
-                            event_id: event.to_string(),
-                            sender: member.clone(),
-                            body: TimelineMessageType::Image(FileContent {
-                                size,
-                                body: message_event_content.body.clone(),
-                                source: Some(ImageType::URL(uri)),
-                            }),
-                            origin: if member.id.eq(logged_user_id) {
-                                EventOrigin::ME
-                            } else {
-                                EventOrigin::OTHER
-                            },
-                            time: timestamp,
-                        });
-                    }
-                }
-                MediaSource::Encrypted(_) => {
-                    let media_content = client
-                        .media()
-                        .get_media_content(
-                            &MediaRequest {
-                                source: message_event_content.source.clone(),
-                                format: MediaFormat::Thumbnail(MediaThumbnailSize {
-                                    method: Method::Crop,
-                                    width: UInt::new(16).unwrap(),
-                                    height: UInt::new(16).unwrap(),
-                                }),
-                            },
-                            true,
-                        )
-                        .await;
+    /// Events referencing `event_id`, newest first. Relations are newer than
+    /// our ruma, so the endpoint is called by hand, decrypting what comes
+    /// encrypted
+    async fn reference_relations(
+        client: &Client,
+        room: &Room,
+        event_id: &str,
+        max_pages: usize,
+    ) -> Vec<serde_json::Value> {
+        let Some(session) = client.session() else {
+            return vec![];
+        };
 
-                    let size = message_event_content
-                        .info
-                        .as_ref()
-                        .and_then(|file_info| {
-                            file_info
-                                .size
-                                .map(|size| size.to_string().parse::<u64>().ok())
-                        })
-                        .flatten();
+        let Ok(mut request_url) = client.homeserver().await.join("_matrix/client/v1/") else {
+            return vec![];
+        };
 
-                    if let Ok(content) = media_content {
-                        message_result = Some(TimelineMessage {
-                            event_id: event.to_string(),
-                            sender: member.clone(),
-                            body: TimelineMessageType::Image(FileContent {
-                                size,
-                                body: message_event_content.body.clone(),
-                                source: Some(ImageType::Media(content)),
-                            }),
-                            origin: if member.id.eq(logged_user_id) {
-                                EventOrigin::ME
-                            } else {
-                                EventOrigin::OTHER
-                            },
-                            time: timestamp,
-                        });
+        let Ok(mut segments) = request_url.path_segments_mut() else {
+            return vec![];
+        };
+        segments.pop_if_empty().extend(&[
+            "rooms",
+            room.room_id().as_str(),
+            "relations",
+            event_id,
+            "m.reference",
+        ]);
+        drop(segments);
+
+        let mut relations = vec![];
+        let mut from: Option<String> = None;
+
+        for _ in 0..max_pages {
+            let mut page_url = request_url.clone();
+            page_url.query_pairs_mut().append_pair("limit", "100");
+            if let Some(from) = &from {
+                page_url.query_pairs_mut().append_pair("from", from);
+            }
+
+            let Ok(res) = reqwest::Client::new()
+                .get(page_url)
+                .header("Authorization", format!("Bearer {}", session.access_token))
+                .send()
+                .await
+            else {
+                break;
+            };
+
+            let Some(body) = res
+                .text()
+                .await
+                .ok()
+                .and_then(|body| serde_json::from_str::<serde_json::Value>(&body).ok())
+            else {
+                break;
+            };
+
+            for event in body["chunk"].as_array().cloned().unwrap_or_default() {
+                if event["type"].as_str() == Some("m.room.encrypted") {
+                    let decrypted = match serde_json::value::to_raw_value(&event) {
+                        Ok(raw) => room.decrypt_event(&Raw::from_json(raw)).await.ok(),
+                        Err(_) => None,
+                    };
+
+                    if let Some(decrypted) =
+                        decrypted.and_then(|e| e.event.deserialize_as::<serde_json::Value>().ok())
+                    {
+                        relations.push(decrypted);
                     }
+                } else {
+                    relations.push(event);
                 }
-            },
-            MessageType::Text(content) => {
-                message_result = Some(TimelineMessage {
-                    event_id: event.to_string(),
-                    sender: member.clone(),
-                    body: TimelineMessageType::Text(content.body.clone()),
-                    origin: if member.id.eq(logged_user_id) {
-                        EventOrigin::ME
-                    } else {
-                        EventOrigin::OTHER
-                    },
-                    time: timestamp,
+            }
+
+            from = body["next_batch"].as_str().map(String::from);
+            if from.is_none() {
+                break;
+            }
+        }
+
+        relations
+    }
+
+    #[derive(PartialEq, Debug, Clone)]
+    pub enum PollError {
+        InvalidRoomId,
+        RoomNotFound,
+        RequestFailed,
+    }
+
+    fn poll_room(client: &Client, room_id: &str) -> Result<Joined, PollError> {
+        let room_id = RoomId::parse(room_id).map_err(|_| PollError::InvalidRoomId)?;
+
+        client
+            .get_joined_room(&room_id)
+            .ok_or(PollError::RoomNotFound)
+    }
+
+    /// Polls are sent in their unstable form, the one most clients read
+    pub async fn send_poll(
+        client: &Client,
+        room_id: &str,
+        question: &str,
+        answers: &[String],
+        kind: PollKind,
+    ) -> Result<(), PollError> {
+        let room = poll_room(client, room_id)?;
+
+        let answers = answers
+            .iter()
+            .map(|answer| {
+                serde_json::json!({
+                    "id": Uuid::new_v4().to_string(),
+                    "org.matrix.msc1767.text": answer,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let fallback =
+            answers
+                .iter()
+                .enumerate()
+                .fold(String::from(question), |text, (i, answer)| {
+                    format!(
+                        "{text}\n{}. {}",
+                        i + 1,
+                        answer["org.matrix.msc1767.text"]
+                            .as_str()
+                            .unwrap_or_default()
+                    )
                 });
 
-                if let Some(formatted) = &content.formatted {
-                    match formatted.format {
-                        MessageFormat::Html => {
-                            if let Some(ref mut message) = message_result {
-                                message.body = TimelineMessageType::Html(formatted.body.clone());
-                            }
-                        }
-                        _ => {}
-                    }
-                };
-            }
-            MessageType::File(message) => match &message.source {
-                MediaSource::Plain(mx_uri) => {
-                    let uri = mxc_to_download_uri(&mx_uri);
-                    let source = uri.and_then(|uri| Some(ImageType::URL(uri)));
+        let kind = match kind {
+            PollKind::Disclosed => "org.matrix.msc3381.poll.disclosed",
+            PollKind::Undisclosed => "org.matrix.msc3381.poll.undisclosed",
+        };
 
-                    let size = message
-                        .info
-                        .as_ref()
-                        .and_then(|file_info| {
-                            file_info
-                                .size
-                                .map(|size| size.to_string().parse::<u64>().ok())
-                        })
-                        .flatten();
+        let content = serde_json::json!({
+            "org.matrix.msc3381.poll.start": {
+                "question": {
+                    "org.matrix.msc1767.text": question,
+                    "body": question,
+                },
+                "kind": kind,
+                "max_selections": 1,
+                "answers": answers,
+            },
+            "org.matrix.msc1767.text": fallback,
+        });
 
-                    message_result = Some(TimelineMessage {
-                        event_id: event.to_string(),
-                        sender: member.clone(),
-                        body: TimelineMessageType::File(FileContent {
-                            size,
-                            body: message.body.clone(),
-                            source,
-                        }),
-                        origin: if member.id.eq(logged_user_id) {
-                            EventOrigin::ME
-                        } else {
-                            EventOrigin::OTHER
-                        },
-                        time: timestamp,
-                    });
-                }
-                MediaSource::Encrypted(file) => {
-                    let uri = mxc_to_download_uri(&file.url);
-                    let source = uri.and_then(|uri| Some(ImageType::URL(uri)));
+        room.send_raw(content, POLL_START_TYPES[1], None)
+            .await
+            .map_err(|_| PollError::RequestFailed)?;
 
-                    let size = message
-                        .info
-                        .as_ref()
-                        .and_then(|file_info| {
-                            file_info
-                                .size
-                                .map(|size| size.to_string().parse::<u64>().ok())
-                        })
-                        .flatten();
+        Ok(())
+    }
 
-                    message_result = Some(TimelineMessage {
-                        event_id: event.to_string(),
-                        sender: member.clone(),
-                        body: TimelineMessageType::File(FileContent {
-                            size,
-                            body: message.body.clone(),
-                            source,
-                        }),
-                        origin: if member.id.eq(logged_user_id) {
-                            EventOrigin::ME
-                        } else {
-                            EventOrigin::OTHER
-                        },
-                        time: timestamp,
-                    });
-                }
+    /// Votes, or changes the vote, for the given answers
+    pub async fn send_poll_response(
+        client: &Client,
+        room_id: &str,
+        poll_id: &str,
+        answers: &[String],
+    ) -> Result<(), PollError> {
+        let room = poll_room(client, room_id)?;
+
+        let content = serde_json::json!({
+            "m.relates_to": {
+                "rel_type": "m.reference",
+                "event_id": poll_id,
             },
-            MessageType::Video(video) => match &video.source {
-                MediaSource::Plain(mx_uri) => {
-                    let uri = mxc_to_download_uri(&mx_uri);
-                    let source = uri.and_then(|uri| Some(ImageType::URL(uri)));
-
-                    let size = video
-                        .info
-                        .as_ref()
-                        .and_then(|file_info| {
-                            file_info
-                                .size
-                                .map(|size| size.to_string().parse::<u64>().ok())
-                        })
-                        .flatten();
+            "org.matrix.msc3381.poll.response": {
+                "answers": answers,
+            },
+        });
 
-                    message_result = Some(TimelineMessage {
-                        event_id: event.to_string(),
-                        sender: member.clone(),
-                        body: TimelineMessageType::Video(FileContent {
-                            size,
-                            body: video.body.clone(),
-                            source,
-                        }),
-                        origin: if member.id.eq(logged_user_id) {
-                            EventOrigin::ME
-                        } else {
-                            EventOrigin::OTHER
-                        },
-                        time: timestamp,
-                    });
-                }
-                MediaSource::Encrypted(_) => {
-                    let message_content = client
-                        .media()
-                        .get_media_content(
-                            &MediaRequest {
-                                source: video.source.clone(),
-                                format: MediaFormat::File,
-                            },
-                            true,
-                        )
-                        .await;
+        room.send_raw(content, POLL_RESPONSE_TYPES[1], None)
+            .await
+            .map_err(|_| PollError::RequestFailed)?;
 
-                    let size = video
-                        .info
-                        .as_ref()
-                        .and_then(|file_info| {
-                            file_info
-                                .size
-                                .map(|size| size.to_string().parse::<u64>().ok())
-                        })
-                        .flatten();
+        Ok(())
+    }
 
-                    if let Ok(content) = message_content {
-                        message_result = Some(TimelineMessage {
-                            event_id: event.to_string(),
-                            sender: member.clone(),
-                            body: TimelineMessageType::Video(FileContent {
-                                size,
-                                body: video.body.clone(),
-                                source: Some(ImageType::Media(content)),
-                            }),
-                            origin: if member.id.eq(logged_user_id) {
-                                EventOrigin::ME
-                            } else {
-                                EventOrigin::OTHER
-                            },
-                            time: timestamp,
-                        });
-                    }
-                }
+    pub async fn end_poll(
+        client: &Client,
+        room_id: &str,
+        poll_id: &str,
+        fallback: &str,
+    ) -> Result<(), PollError> {
+        let room = poll_room(client, room_id)?;
+
+        let content = serde_json::json!({
+            "m.relates_to": {
+                "rel_type": "m.reference",
+                "event_id": poll_id,
             },
-            _ => {
-                info!("unsuported message_type matrix");
+            "org.matrix.msc3381.poll.end": {},
+            "org.matrix.msc1767.text": fallback,
+        });
+
+        room.send_raw(content, POLL_END_TYPES[1], None)
+            .await
+            .map_err(|_| PollError::RequestFailed)?;
+
+        Ok(())
+    }
+
+    pub const BEACON_INFO_TYPES: [&str; 2] = ["m.beacon_info", "org.matrix.msc3672.beacon_info"];
+    pub const BEACON_TYPES: [&str; 2] = ["m.beacon", "org.matrix.msc3672.beacon"];
+
+    /// Live location shared by a user (MSC3489), its position changes with
+    /// every beacon event referencing it
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct BeaconContent {
+        pub description: Option<String>,
+        /// Milliseconds since the epoch, by the server clock
+        pub expires_at: u64,
+        pub stopped: bool,
+        pub location: Option<LocationContent>,
+        pub updated_at: Option<u64>,
+        /// Latest server timestamp of the share and its beacons
+        #[serde(default)]
+        pub latest_ts: u64,
+    }
+
+    impl BeaconContent {
+        /// Decided on server timestamps, the local clock may be off
+        pub fn is_live(&self) -> bool {
+            !self.stopped && self.latest_ts < self.expires_at
+        }
+
+        pub fn stop(&mut self) {
+            self.stopped = true;
+        }
+
+        /// Moves to the position of a beacon event, older positions are ignored
+        pub fn apply_location(&mut self, event: &serde_json::Value) {
+            let content = &event["content"];
+            let location = stable_or_unstable(content, "m.location", "org.matrix.msc3488.location");
+            if let Some(origin_server_ts) = event["origin_server_ts"].as_u64() {
+                self.latest_ts = self.latest_ts.max(origin_server_ts);
             }
+
+            let Some(uri) = location["uri"].as_str() else {
+                return;
+            };
+
+            let ts = stable_or_unstable(content, "m.ts", "org.matrix.msc3488.ts")
+                .as_u64()
+                .or_else(|| event["origin_server_ts"].as_u64())
+                .unwrap_or_default();
+
+            if self.updated_at.is_some_and(|updated_at| updated_at > ts) {
+                return;
+            }
+
+            let description = location["description"].as_str().map(String::from);
+            if let Some(location) = LocationContent::from_geo_uri(uri, description) {
+                self.location = Some(location);
+                self.updated_at = Some(ts);
+            }
+        }
+    }
+
+    fn stable_or_unstable<'a>(
+        content: &'a serde_json::Value,
+        stable: &str,
+        unstable: &str,
+    ) -> &'a serde_json::Value {
+        match &content[stable] {
+            serde_json::Value::Null => &content[unstable],
+            value => value,
         }
+    }
 
-        return message_result;
+    /// Only beacon infos starting a share are shown, the ones stopping it
+    /// end the previous share of their sender. The timeout runs from the
+    /// server timestamp, `m.ts` is the clock of the sender
+    fn parse_beacon_info(event: &serde_json::Value) -> Option<BeaconContent> {
+        let content = &event["content"];
+        if !content["live"].as_bool().unwrap_or(false) {
+            return None;
+        }
+
+        let started_at = event["origin_server_ts"]
+            .as_u64()
+            .or_else(|| stable_or_unstable(content, "m.ts", "org.matrix.msc3488.ts").as_u64())?;
+        let timeout = content["timeout"].as_u64()?;
+
+        Some(BeaconContent {
+            description: content["description"].as_str().map(String::from),
+            expires_at: started_at.saturating_add(timeout),
+            stopped: false,
+            location: None,
+            updated_at: None,
+            latest_ts: started_at,
+        })
     }
 
-    pub async fn format_relation_from_event(
-        n: &MessageType,
-        relates: &Option<Relation>,
+    /// Turns a beacon info state event into a timeline message placed at the
+    /// last known position, `relations` are the beacon events referencing it
+    pub async fn format_beacon_info(
+        event: &serde_json::Value,
+        relations: &[serde_json::Value],
         room: &Room,
-        message_result: TimelineMessage,
-        member: &RoomMember,
         logged_user_id: &str,
-        time: MilliSecondsSinceUnixEpoch,
-        client: &Client,
-    ) -> Option<TimelineRelation> {
-        let Some(r) = relates else {
+    ) -> Option<TimelineMessage> {
+        let event_type = event["type"].as_str()?;
+        if !BEACON_INFO_TYPES.contains(&event_type) {
             return None;
+        }
+
+        let mut beacon = parse_beacon_info(event)?;
+
+        for relation in relations {
+            beacon.apply_location(relation);
+        }
+
+        // A newer beacon info of the same user replaced this share
+        let state_key = event["state_key"].as_str().unwrap_or_default();
+        let current = room
+            .get_state_event(StateEventType::from(event_type), state_key)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|current| current.get_field::<String>("event_id").ok().flatten());
+
+        let event_id = event["event_id"].as_str().unwrap_or_default();
+        if current.is_some_and(|current| current.ne(event_id)) {
+            beacon.stop();
+        }
+
+        format_raw_event(
+            event,
+            TimelineMessageType::Beacon(beacon),
+            room,
+            logged_user_id,
+        )
+        .await
+    }
+
+    /// Positions shared by a beacon, newest first
+    pub async fn beacon_relations(
+        client: &Client,
+        room: &Room,
+        event_id: &str,
+    ) -> Vec<serde_json::Value> {
+        // The latest position is all that's shown
+        reference_relations(client, room, event_id, 1).await
+    }
+
+    const USER_EMOTES_TYPE: &str = "im.ponies.user_emotes";
+    pub const ROOM_EMOTES_TYPE: &str = "im.ponies.room_emotes";
+    const EMOTE_ROOMS_TYPE: &str = "im.ponies.emote_rooms";
+
+    #[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+    pub struct PackImageInfo {
+        pub width: Option<u64>,
+        pub height: Option<u64>,
+        pub mimetype: Option<String>,
+        pub size: Option<u64>,
+    }
+
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct PackImage {
+        pub shortcode: String,
+        pub body: String,
+        /// Mxc uri of the image
+        pub url: String,
+        pub thumbnail: Option<String>,
+        pub info: PackImageInfo,
+    }
+
+    /// Image pack of MSC2545, only the images usable as stickers are kept
+    #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+    pub struct ImagePack {
+        pub id: String,
+        /// Missing for the personal pack without a display name
+        pub name: Option<String>,
+        pub images: Vec<PackImage>,
+    }
+
+    #[derive(PartialEq, Debug, Clone)]
+    pub enum StickerError {
+        InvalidRoomId,
+        RoomNotFound,
+        RequestFailed,
+    }
+
+    fn parse_image_pack(
+        id: String,
+        content: &serde_json::Value,
+        name: Option<String>,
+    ) -> Option<ImagePack> {
+        let usage_of = |value: &serde_json::Value| -> Option<Vec<String>> {
+            value.as_array().map(|usage| {
+                usage
+                    .iter()
+                    .filter_map(|usage| usage.as_str().map(String::from))
+                    .collect()
+            })
         };
-        match r {
-            Relation::Reply { in_reply_to } => {
-                let event = room.event(&in_reply_to.event_id).await.ok()?;
-                let timestamp = {
-                    let d = UNIX_EPOCH + Duration::from_millis(time.0.into());
-                    let datetime = DateTime::<Utc>::from(d);
-                    datetime.format("%H:%M").to_string()
-                };
 
-                let desc_event = event
-                    .event
-                    .deserialize()
-                    .expect("can't deserialize event: format_relation_from_event");
+        // Without usage the images are emoticons and stickers alike
+        let pack_usage = usage_of(&content["pack"]["usage"]).unwrap_or_default();
+
+        let images = content["images"]
+            .as_object()?
+            .iter()
+            .filter_map(|(shortcode, image)| {
+                let usage = usage_of(&image["usage"]).unwrap_or_else(|| pack_usage.clone());
+                if !usage.is_empty() && !usage.iter().any(|usage| usage.eq("sticker")) {
+                    return None;
+                }
+
+                let url = image["url"].as_str()?;
+                let mxc = <&MxcUri>::from(url);
+                if !mxc.is_valid() {
+                    return None;
+                }
+
+                let info = &image["info"];
+
+                Some(PackImage {
+                    shortcode: shortcode.clone(),
+                    body: image["body"].as_str().unwrap_or(shortcode).to_string(),
+                    url: url.to_string(),
+                    thumbnail: mxc_to_thumbnail_uri(
+                        mxc,
+                        ImageSize::new(96, 96),
+                        ImageMethod::SCALE,
+                    ),
+                    info: PackImageInfo {
+                        width: info["w"].as_u64(),
+                        height: info["h"].as_u64(),
+                        mimetype: info["mimetype"].as_str().map(String::from),
+                        size: info["size"].as_u64(),
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if images.is_empty() {
+            return None;
+        }
+
+        Some(ImagePack {
+            id,
+            name: content["pack"]["display_name"]
+                .as_str()
+                .map(String::from)
+                .or(name),
+            images,
+        })
+    }
+
+    /// Account data the sdk has no type for, as the last sync left it
+    async fn global_account_data(client: &Client, event_type: &str) -> Option<serde_json::Value> {
+        client
+            .account()
+            .account_data_raw(GlobalAccountDataEventType::from(event_type))
+            .await
+            .ok()
+            .flatten()
+            .and_then(|raw| raw.deserialize_as::<serde_json::Value>().ok())
+    }
+
+    async fn room_image_packs(room: &Room, state_keys: Option<Vec<String>>) -> Vec<ImagePack> {
+        let events = room
+            .get_state_events(StateEventType::from(ROOM_EMOTES_TYPE))
+            .await
+            .unwrap_or_default();
+
+        events
+            .into_iter()
+            .filter_map(|event| {
+                let state_key = event.get_field::<String>("state_key").ok().flatten()?;
+                if state_keys
+                    .as_ref()
+                    .is_some_and(|state_keys| !state_keys.contains(&state_key))
+                {
+                    return None;
+                }
 
-                let reply =
-                    deserialize_timeline_event(desc_event, room, &logged_user_id, &client).await;
+                let content = event
+                    .get_field::<serde_json::Value>("content")
+                    .ok()
+                    .flatten()?;
 
-                reply.map(|r| {
-                    let mut final_message = TimelineMessageReply {
-                        event: message_result,
-                        reply: Some(r.clone()),
-                    };
+                parse_image_pack(
+                    format!("{}/{}", room.room_id(), state_key),
+                    &content,
+                    room.name(),
+                )
+            })
+            .collect()
+    }
 
-                    match &r.body {
-                        TimelineMessageType::Image(_) => {
-                            if n.body().contains("sent an image.") {
-                                let to_remove = format!("> <{}> {}", r.sender.id, "sent an image.");
+    /// Sticker packs usable in `room_id`: the personal one, the ones of the
+    /// room and the ones of the rooms enabled everywhere
+    pub async fn image_packs(
+        client: &Client,
+        room_id: &str,
+    ) -> Result<Vec<ImagePack>, StickerError> {
+        let room_id = RoomId::parse(room_id).map_err(|_| StickerError::InvalidRoomId)?;
+        let room = client
+            .get_room(&room_id)
+            .ok_or(StickerError::RoomNotFound)?;
 
-                                let uncleared_content = n.body();
-                                let n = uncleared_content.replace(&to_remove, "").clone();
+        let mut packs = vec![];
 
-                                let content_body = TimelineMessageType::Text(n);
-                                let event = event.event.deserialize().ok()?.event_id().to_string();
-                                final_message.event = TimelineMessage {
-                                    event_id: event,
-                                    sender: member.clone(),
-                                    body: content_body,
-                                    origin: if member.id.eq(logged_user_id) {
-                                        EventOrigin::ME
-                                    } else {
-                                        EventOrigin::OTHER
-                                    },
-                                    time: timestamp,
-                                };
-                            }
-                        }
-                        TimelineMessageType::Text(body) => {
-                            if body.starts_with(">") {
-                                let to_remove =
-                                    format!("> <{}> {}", r.clone().sender.id, body.trim());
+        if let Some(content) = global_account_data(client, USER_EMOTES_TYPE).await {
+            packs.extend(parse_image_pack(
+                USER_EMOTES_TYPE.to_string(),
+                &content,
+                None,
+            ));
+        }
 
-                                let uncleared_content = n.body();
-                                let n = uncleared_content.replace(&to_remove, "").clone();
+        packs.extend(room_image_packs(&room, None).await);
 
-                                let content_body = TimelineMessageType::Text(n);
-                                let event = event.event.deserialize().ok()?.event_id().to_string();
-                                final_message.event = TimelineMessage {
-                                    event_id: event,
-                                    sender: member.clone(),
-                                    body: content_body,
-                                    origin: if member.id.eq(logged_user_id) {
-                                        EventOrigin::ME
-                                    } else {
-                                        EventOrigin::OTHER
-                                    },
-                                    time: timestamp,
-                                };
-                            } else {
-                                final_message.reply = Some(r);
-                            }
-                        }
-                        TimelineMessageType::Html(_) => {
-                            final_message.reply = Some(r);
-                        }
-                        TimelineMessageType::File(_) => {
-                            final_message.reply = Some(r);
-                        }
-                        TimelineMessageType::Video(_) => {
-                            final_message.reply = Some(r);
-                        }
-                    }
+        let emote_rooms = global_account_data(client, EMOTE_ROOMS_TYPE)
+            .await
+            .and_then(|content| content["rooms"].as_object().cloned())
+            .unwrap_or_default();
 
-                    Some(TimelineRelation::Reply(final_message))
-                })?
+        for (emote_room_id, state_keys) in emote_rooms {
+            if emote_room_id.eq(room_id.as_str()) {
+                continue;
             }
-            Relation::Thread(in_reply_to) => {
-                let final_message = TimelineMessageThread {
-                    event_id: in_reply_to.event_id.to_string(),
-                    thread: vec![message_result.clone()],
-                };
 
-                Some(TimelineRelation::Thread(final_message))
-            }
-            _ => None,
+            let Some(emote_room) = RoomId::parse(&emote_room_id)
+                .ok()
+                .and_then(|emote_room_id| client.get_room(&emote_room_id))
+            else {
+                continue;
+            };
+
+            let state_keys = state_keys
+                .as_object()
+                .map(|state_keys| state_keys.keys().cloned().collect::<Vec<_>>())
+                .unwrap_or_default();
+
+            packs.extend(room_image_packs(&emote_room, Some(state_keys)).await);
+        }
+
+        Ok(packs)
+    }
+
+    pub async fn send_sticker(
+        client: &Client,
+        room_id: &str,
+        image: &PackImage,
+    ) -> Result<(), StickerError> {
+        let room_id = RoomId::parse(room_id).map_err(|_| StickerError::InvalidRoomId)?;
+        let room = client
+            .get_joined_room(&room_id)
+            .ok_or(StickerError::RoomNotFound)?;
+
+        let mut info = serde_json::Map::new();
+        if let Some(width) = image.info.width {
+            info.insert("w".into(), width.into());
+        }
+        if let Some(height) = image.info.height {
+            info.insert("h".into(), height.into());
+        }
+        if let Some(mimetype) = &image.info.mimetype {
+            info.insert("mimetype".into(), mimetype.clone().into());
+        }
+        if let Some(size) = image.info.size {
+            info.insert("size".into(), size.into());
         }
+
+        let content = serde_json::json!({
+            "body": image.body,
+            "url": image.url,
+            "info": info,
+        });
+
+        room.send_raw(content, "m.sticker", None)
+            .await
+            .map_err(|_| StickerError::RequestFailed)?;
+
+        Ok(())
     }
 
     #[derive(Debug, Serialize, Deserialize)]
@@ -1311,13 +4188,19 @@ pub mod matrix {
 
         #[serde(skip_serializing_if = "Option::is_none")]
         pub sync_token: Option<String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub sync_filter: Option<SyncFilter>,
     }
 
     pub struct LoginResult {
         // client
     }
 
-    use matrix_sdk::ruma::api::client::account::register::v3::Request as RegistrationRequest;
+    use matrix_sdk::ruma::api::client::account::register::v3::{
+        Request as RegistrationRequest, Response as RegistrationResponse,
+    };
+    use ruma::api::IncomingResponse;
 
     pub async fn prepare_register(
         homeserver: &str,
@@ -1417,6 +4300,7 @@ pub mod matrix {
             client_session,
             user_session: user_session.clone(),
             sync_token: None,
+            sync_filter: None,
         })
         .map_err(|_| String::from("Serialization failed"))?;
 
@@ -1428,6 +4312,163 @@ pub mod matrix {
         Ok((client, serialized_session))
     }
 
+    #[derive(Debug)]
+    pub enum UpgradeGuestError {
+        SessionNotFound,
+        RequestFailed,
+        Rejected,
+        InvalidResponse,
+        /// The server wants a stage we can't complete, it's done through its
+        /// web fallback and the upgrade is retried with the same session
+        AuthRequired(UiaStage),
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct UiaStage {
+        pub session: String,
+        pub stage: String,
+        pub fallback_url: String,
+    }
+
+    const UIA_DUMMY: &str = "m.login.dummy";
+
+    /// Next stage to complete, preferring the flows we can finish ourselves.
+    /// A flow is usable while the completed stages are a prefix of it
+    pub fn next_uia_stage(flows: &serde_json::Value, completed: &[String]) -> Option<String> {
+        let flows = flows
+            .as_array()?
+            .iter()
+            .filter_map(|flow| {
+                flow["stages"].as_array().map(|stages| {
+                    stages
+                        .iter()
+                        .filter_map(|stage| stage.as_str().map(String::from))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .filter(|stages| stages.starts_with(completed) && stages.len() > completed.len())
+            .collect::<Vec<_>>();
+
+        let remaining = |stages: &Vec<String>| stages[completed.len()..].to_vec();
+
+        flows
+            .iter()
+            .find(|stages| remaining(stages).iter().all(|stage| stage.eq(UIA_DUMMY)))
+            .or_else(|| flows.first())
+            .map(|stages| stages[completed.len()].clone())
+    }
+
+    // A guest is upgraded by registering again with its own access token,
+    // the server keeps the same user id so joined rooms and history are preserved
+    pub async fn upgrade_guest(
+        client: &Client,
+        password: &str,
+        uia_session: Option<String>,
+    ) -> Result<Session, UpgradeGuestError> {
+        let guest_session = client.session().ok_or(UpgradeGuestError::SessionNotFound)?;
+        let homeserver = client.homeserver().await;
+
+        let request_url = homeserver
+            .join("_matrix/client/v3/register?kind=user")
+            .map_err(|_| UpgradeGuestError::RequestFailed)?;
+
+        // The first request has no auth, the server answers with its flows
+        let mut auth = uia_session.map(|session| serde_json::json!({ "session": session }));
+
+        // Every round completes one stage, a few are enough for any flow
+        for _ in 0..5 {
+            // ruma's RegistrationRequest doesn't expose `guest_access_token`
+            let mut body = serde_json::json!({
+                "username": guest_session.user_id.localpart(),
+                "password": password,
+                "device_id": guest_session.device_id,
+                "guest_access_token": guest_session.access_token,
+                "initial_device_display_name": "Fido",
+            });
+            if let Some(auth) = &auth {
+                body["auth"] = auth.clone();
+            }
+
+            let res = reqwest::Client::new()
+                .post(request_url.clone())
+                .header("Content-Type", "application/json")
+                .body(body.to_string())
+                .send()
+                .await
+                .map_err(|_| UpgradeGuestError::RequestFailed)?;
+
+            let status = res.status();
+            let body = res
+                .text()
+                .await
+                .map_err(|_| UpgradeGuestError::InvalidResponse)?;
+
+            if status.is_success() {
+                let info = RegistrationResponse::try_from_http_response(http::Response::new(body))
+                    .map_err(|_| UpgradeGuestError::InvalidResponse)?;
+
+                let Some(access_token) = info.access_token else {
+                    return Err(UpgradeGuestError::InvalidResponse);
+                };
+
+                return Ok(Session {
+                    access_token,
+                    refresh_token: info.refresh_token,
+                    user_id: info.user_id,
+                    device_id: info.device_id.unwrap_or(guest_session.device_id),
+                });
+            }
+
+            if status.as_u16() != 401 {
+                log::info!("upgrade guest rejected {:?}", status);
+                return Err(UpgradeGuestError::Rejected);
+            }
+
+            let uia = serde_json::from_str::<serde_json::Value>(&body)
+                .map_err(|_| UpgradeGuestError::InvalidResponse)?;
+            let session = uia["session"]
+                .as_str()
+                .ok_or(UpgradeGuestError::InvalidResponse)?
+                .to_string();
+            let completed = uia["completed"]
+                .as_array()
+                .map(|completed| {
+                    completed
+                        .iter()
+                        .filter_map(|stage| stage.as_str().map(String::from))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            let stage =
+                next_uia_stage(&uia["flows"], &completed).ok_or(UpgradeGuestError::Rejected)?;
+
+            if !stage.eq(UIA_DUMMY) {
+                let mut fallback_url = homeserver
+                    .join("_matrix/client/v3/auth/")
+                    .map_err(|_| UpgradeGuestError::RequestFailed)?;
+                fallback_url
+                    .path_segments_mut()
+                    .map_err(|_| UpgradeGuestError::RequestFailed)?
+                    .pop_if_empty()
+                    .extend(&[stage.as_str(), "fallback", "web"]);
+                fallback_url
+                    .query_pairs_mut()
+                    .append_pair("session", &session);
+
+                return Err(UpgradeGuestError::AuthRequired(UiaStage {
+                    session,
+                    stage,
+                    fallback_url: fallback_url.to_string(),
+                }));
+            }
+
+            auth = Some(serde_json::json!({ "type": UIA_DUMMY, "session": session }));
+        }
+
+        Err(UpgradeGuestError::Rejected)
+    }
+
     pub async fn login(
         homeserver: &str,
         username: &str,
@@ -1464,6 +4505,7 @@ pub mod matrix {
             client_session,
             user_session,
             sync_token: None,
+            sync_filter: None,
         })?;
 
         info!("Syncing");
@@ -1481,6 +4523,7 @@ pub mod matrix {
             client_session,
             user_session,
             sync_token,
+            ..
         } = serde_json::from_str(&serialized_session)?;
 
         let client = Client::builder()
@@ -1534,4 +4577,396 @@ pub mod matrix {
             }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn formatted_content_strips_unsafe_html() {
+            let formatted = FormattedBody::html(
+                "<b>waves</b><script>alert(1)</script><img src=\"x\" onerror=\"alert(2)\">",
+            );
+
+            let content = formatted_content("waves", Some(&formatted));
+
+            let html = content.formatted.unwrap();
+            assert!(html.contains("<b>waves</b>"));
+            assert!(!html.contains("script"));
+            assert!(!html.contains("onerror"));
+        }
+
+        #[test]
+        fn formatted_content_ignores_other_formats() {
+            let formatted = FormattedBody {
+                format: MessageFormat::from("org.example.markdown"),
+                body: String::from("**waves**"),
+            };
+
+            let content = formatted_content("waves", Some(&formatted));
+
+            assert_eq!(content.body, "waves");
+            assert_eq!(content.formatted, None);
+        }
+
+        #[test]
+        fn parse_image_pack_keeps_sticker_images() {
+            let content = serde_json::json!({
+                "pack": { "display_name": "Cats", "usage": ["emoticon"] },
+                "images": {
+                    "smile": { "url": "mxc://example.org/smile" },
+                    "wave": {
+                        "url": "mxc://example.org/wave",
+                        "body": "A cat waving",
+                        "usage": ["sticker"],
+                        "info": { "w": 256, "h": 128, "mimetype": "image/png", "size": 2048 }
+                    }
+                }
+            });
+
+            let pack = parse_image_pack(String::from("pack"), &content, Some(String::from("Room")))
+                .unwrap();
+
+            assert_eq!(pack.id, "pack");
+            assert_eq!(pack.name, Some(String::from("Cats")));
+            assert_eq!(pack.images.len(), 1);
+
+            let image = &pack.images[0];
+            assert_eq!(image.shortcode, "wave");
+            assert_eq!(image.body, "A cat waving");
+            assert_eq!(image.url, "mxc://example.org/wave");
+            assert!(image.thumbnail.is_some());
+            assert_eq!(
+                image.info,
+                PackImageInfo {
+                    width: Some(256),
+                    height: Some(128),
+                    mimetype: Some(String::from("image/png")),
+                    size: Some(2048),
+                }
+            );
+        }
+
+        #[test]
+        fn parse_image_pack_without_usage_keeps_every_image() {
+            let content = serde_json::json!({
+                "images": {
+                    "smile": { "url": "mxc://example.org/smile" },
+                    "wave": { "url": "mxc://example.org/wave" }
+                }
+            });
+
+            let pack = parse_image_pack(String::from("pack"), &content, Some(String::from("Room")))
+                .unwrap();
+
+            assert_eq!(pack.name, Some(String::from("Room")));
+            assert_eq!(pack.images.len(), 2);
+            assert!(pack
+                .images
+                .iter()
+                .all(|image| image.body.eq(&image.shortcode)));
+        }
+
+        #[test]
+        fn parse_image_pack_drops_invalid_urls() {
+            let content = serde_json::json!({
+                "images": {
+                    "broken": { "url": "https://example.org/broken.png" },
+                    "missing": {}
+                }
+            });
+
+            assert!(parse_image_pack(String::from("pack"), &content, None).is_none());
+        }
+
+        #[test]
+        fn parse_image_pack_without_stickers_is_none() {
+            let content = serde_json::json!({
+                "pack": { "usage": ["emoticon"] },
+                "images": { "smile": { "url": "mxc://example.org/smile" } }
+            });
+
+            assert!(parse_image_pack(String::from("pack"), &content, None).is_none());
+            assert!(parse_image_pack(String::from("pack"), &serde_json::json!({}), None).is_none());
+        }
+
+        #[test]
+        fn from_geo_uri_reads_coordinates_and_accuracy() {
+            let location = LocationContent::from_geo_uri(
+                "geo:51.5008,0.1247;u=35",
+                Some(String::from("Big Ben")),
+            )
+            .unwrap();
+
+            assert_eq!(location.latitude, 51.5008);
+            assert_eq!(location.longitude, 0.1247);
+            assert_eq!(location.accuracy, Some(35.0));
+            assert_eq!(location.description, Some(String::from("Big Ben")));
+        }
+
+        #[test]
+        fn from_geo_uri_ignores_altitude_and_other_params() {
+            let location =
+                LocationContent::from_geo_uri(" geo:-33.86,151.2,42;crs=wgs84;U=10 ", None)
+                    .unwrap();
+
+            assert_eq!(location.latitude, -33.86);
+            assert_eq!(location.longitude, 151.2);
+            assert_eq!(location.accuracy, Some(10.0));
+        }
+
+        #[test]
+        fn from_geo_uri_drops_invalid_accuracy_and_blank_description() {
+            let location =
+                LocationContent::from_geo_uri("geo:10,20;u=0", Some(String::from("  "))).unwrap();
+
+            assert_eq!(location.accuracy, None);
+            assert_eq!(location.description, None);
+        }
+
+        #[test]
+        fn from_geo_uri_rejects_invalid_uris() {
+            assert!(LocationContent::from_geo_uri("51.5,0.12", None).is_none());
+            assert!(LocationContent::from_geo_uri("geo:51.5", None).is_none());
+            assert!(LocationContent::from_geo_uri("geo:north,0.12", None).is_none());
+            assert!(LocationContent::from_geo_uri("geo:91,0", None).is_none());
+            assert!(LocationContent::from_geo_uri("geo:0,-181", None).is_none());
+        }
+
+        fn poll(creator: &str) -> PollContent {
+            PollContent {
+                question: String::from("Lunch?"),
+                kind: PollKind::Disclosed,
+                max_selections: 1,
+                answers: ["pizza", "sushi"]
+                    .iter()
+                    .map(|id| PollAnswer {
+                        id: id.to_string(),
+                        text: id.to_string(),
+                    })
+                    .collect(),
+                creator: creator.to_string(),
+                votes: HashMap::new(),
+                ended_at: None,
+                end_is_local: false,
+                latest_ts: 0,
+                redact_power: RedactPower::default(),
+            }
+        }
+
+        fn answers(ids: &[&str]) -> Vec<String> {
+            ids.iter().map(|id| id.to_string()).collect()
+        }
+
+        #[test]
+        fn poll_keeps_the_latest_response_of_each_user() {
+            let mut p = poll("@alice:example.org");
+
+            p.apply_response("@bob:example.org", &answers(&["pizza"]), 20);
+            p.apply_response("@bob:example.org", &answers(&["sushi"]), 10);
+            p.apply_response("@carol:example.org", &answers(&["sushi"]), 15);
+            p.apply_response("@carol:example.org", &answers(&["pizza"]), 30);
+
+            assert_eq!(p.selection_of("@bob:example.org"), answers(&["pizza"]));
+            assert_eq!(p.tally(), vec![2, 0]);
+        }
+
+        #[test]
+        fn poll_spoils_unknown_answers_and_caps_selections() {
+            let mut p = poll("@alice:example.org");
+
+            p.apply_response("@bob:example.org", &answers(&["pasta"]), 10);
+            p.apply_response("@carol:example.org", &answers(&["sushi", "pizza"]), 10);
+
+            assert!(p.selection_of("@bob:example.org").is_empty());
+            assert_eq!(p.selection_of("@carol:example.org"), answers(&["sushi"]));
+            assert_eq!(p.tally(), vec![0, 1]);
+        }
+
+        #[test]
+        fn poll_end_drops_later_votes() {
+            let mut p = poll("@alice:example.org");
+
+            p.apply_response("@bob:example.org", &answers(&["pizza"]), 10);
+            p.apply_response("@carol:example.org", &answers(&["sushi"]), 30);
+            p.apply_end("@alice:example.org", 20);
+            p.apply_response("@dave:example.org", &answers(&["sushi"]), 40);
+
+            assert_eq!(p.ended_at, Some(20));
+            assert_eq!(p.tally(), vec![1, 0]);
+        }
+
+        #[test]
+        fn poll_end_needs_the_creator_or_a_moderator() {
+            let mut p = poll("@alice:example.org");
+
+            p.apply_end("@bob:example.org", 20);
+            assert!(!p.is_ended());
+
+            p.redact_power = RedactPower::from_power_levels(&serde_json::json!({
+                "users": { "@mod:example.org": 50 }
+            }));
+            p.apply_end("@mod:example.org", 20);
+            assert_eq!(p.ended_at, Some(20));
+        }
+
+        #[test]
+        fn poll_local_end_gives_way_to_the_server() {
+            let mut p = poll("@alice:example.org");
+
+            p.apply_response("@bob:example.org", &answers(&["pizza"]), 25);
+            p.apply_local_end();
+            assert_eq!(p.ended_at, Some(25));
+
+            // Votes still on their way count until the server tells the end
+            p.apply_response("@carol:example.org", &answers(&["sushi"]), 28);
+            p.apply_end("@alice:example.org", 30);
+
+            assert_eq!(p.ended_at, Some(30));
+            assert!(!p.end_is_local);
+            assert_eq!(p.tally(), vec![1, 1]);
+        }
+
+        #[test]
+        fn poll_local_response_follows_the_server_clock() {
+            let mut p = poll("@alice:example.org");
+
+            p.apply_response("@bob:example.org", &answers(&["pizza"]), 1_000);
+            p.apply_response("@carol:example.org", &answers(&["pizza"]), 2_000);
+
+            // Our clock could be far behind the server, the vote still replaces
+            p.apply_local_response("@bob:example.org", &answers(&["sushi"]));
+            assert_eq!(p.selection_of("@bob:example.org"), answers(&["sushi"]));
+
+            p.apply_response("@bob:example.org", &answers(&["sushi"]), 2_500);
+            assert_eq!(p.tally(), vec![1, 1]);
+        }
+
+        fn beacon_info(origin_server_ts: u64, sender_ts: u64) -> serde_json::Value {
+            serde_json::json!({
+                "type": "m.beacon_info",
+                "origin_server_ts": origin_server_ts,
+                "content": {
+                    "live": true,
+                    "timeout": 1_000,
+                    "m.ts": sender_ts,
+                }
+            })
+        }
+
+        fn beacon(origin_server_ts: u64) -> serde_json::Value {
+            serde_json::json!({
+                "type": "m.beacon",
+                "origin_server_ts": origin_server_ts,
+                "content": {
+                    "m.location": { "uri": "geo:51.5,0.12" },
+                    "m.ts": origin_server_ts,
+                }
+            })
+        }
+
+        #[test]
+        fn beacon_expires_by_server_timestamps() {
+            // The clock of the sender is an hour ahead
+            let mut b = parse_beacon_info(&beacon_info(10_000, 3_610_000)).unwrap();
+            assert_eq!(b.expires_at, 11_000);
+            assert!(b.is_live());
+
+            b.apply_location(&beacon(10_500));
+            assert!(b.is_live());
+            assert!(b.location.is_some());
+
+            b.apply_location(&beacon(11_200));
+            assert!(!b.is_live());
+        }
+
+        #[test]
+        fn beacon_info_needs_to_be_live() {
+            let mut event = beacon_info(10_000, 10_000);
+            event["content"]["live"] = serde_json::json!(false);
+
+            assert!(parse_beacon_info(&event).is_none());
+        }
+
+        #[test]
+        fn knock_requests_skip_other_memberships() {
+            let events = serde_json::json!([
+                {
+                    "type": "m.room.member",
+                    "state_key": "@alice:example.org",
+                    "content": { "membership": "knock", "displayname": "Alice", "reason": "Hi" }
+                },
+                {
+                    "type": "m.room.member",
+                    "state_key": "@bob:example.org",
+                    "content": { "membership": "join", "displayname": "Bob" }
+                },
+                {
+                    "type": "m.room.member",
+                    "state_key": "@carol:example.org",
+                    "content": { "membership": "knock" }
+                }
+            ]);
+
+            let knocks = knock_requests(events.as_array().unwrap());
+
+            assert_eq!(knocks.len(), 2);
+            assert_eq!(knocks[0].member.name, "Alice");
+            assert_eq!(knocks[0].reason.as_deref(), Some("Hi"));
+            // Without a display name the user id stands in
+            assert_eq!(knocks[1].member.name, "@carol:example.org");
+            assert_eq!(knocks[1].reason, None);
+        }
+
+        fn stages(stages: &[&str]) -> Vec<String> {
+            stages.iter().map(|stage| stage.to_string()).collect()
+        }
+
+        #[test]
+        fn uia_prefers_a_flow_we_can_finish() {
+            let flows = serde_json::json!([
+                { "stages": ["m.login.email.identity"] },
+                { "stages": ["m.login.dummy"] },
+            ]);
+
+            assert_eq!(
+                next_uia_stage(&flows, &[]),
+                Some(String::from("m.login.dummy"))
+            );
+        }
+
+        #[test]
+        fn uia_asks_for_the_first_stage_without_dummy_flows() {
+            let flows = serde_json::json!([
+                { "stages": ["m.login.recaptcha", "m.login.terms"] },
+                { "stages": ["m.login.email.identity"] },
+            ]);
+
+            assert_eq!(
+                next_uia_stage(&flows, &[]),
+                Some(String::from("m.login.recaptcha"))
+            );
+        }
+
+        #[test]
+        fn uia_continues_the_flow_of_the_completed_stages() {
+            let flows = serde_json::json!([
+                { "stages": ["m.login.email.identity"] },
+                { "stages": ["m.login.recaptcha", "m.login.terms"] },
+            ]);
+
+            assert_eq!(
+                next_uia_stage(&flows, &stages(&["m.login.recaptcha"])),
+                Some(String::from("m.login.terms"))
+            );
+        }
+
+        #[test]
+        fn uia_without_flows_has_no_stage() {
+            assert_eq!(next_uia_stage(&serde_json::Value::Null, &[]), None);
+
+            let flows = serde_json::json!([{ "stages": ["m.login.terms"] }]);
+            assert_eq!(next_uia_stage(&flows, &stages(&["m.login.terms"])), None);
+        }
+    }
 }
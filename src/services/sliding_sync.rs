@@ -0,0 +1,611 @@
+use std::collections::HashMap;
+
+use log::info;
+use matrix_sdk::{
+    config::SyncSettings,
+    ruma::{api::client::sync::sync_events, uint, OwnedMxcUri, RoomId},
+    Client,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    components::atoms::room::RoomItem,
+    services::{
+        filter::{limited_sync_filter_definition, timeline_event_types},
+        matrix::matrix::Conversations,
+    },
+    utils::matrix::{mxc_to_thumbnail_uri, ImageMethod, ImageSize},
+};
+
+const SLIDING_SYNC_PATH: &str = "_matrix/client/unstable/org.matrix.simplified_msc3575/sync";
+// Long-poll timeout in milliseconds, the server answers earlier when something changes
+const SLIDING_SYNC_TIMEOUT: u32 = 30_000;
+/// Rooms added to the list window each time it grows
+pub const ROOMS_WINDOW: u32 = 20;
+// Spaces and invitations are few, their windows grow to fit them all
+const MIN_LIST_RANGE_END: u32 = 19;
+
+const ROOMS_LIST: &str = "rooms";
+const SPACES_LIST: &str = "spaces";
+const INVITES_LIST: &str = "invites";
+
+#[derive(Debug, PartialEq)]
+pub enum SlidingSyncError {
+    SessionNotFound,
+    InvalidRoomId,
+    Unsupported,
+    /// The server dropped the connection, it has to start over without `pos`
+    UnknownPos,
+    RequestFailed,
+    InvalidResponse,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SlidingSyncResponse {
+    pos: String,
+    #[serde(default)]
+    lists: HashMap<String, ListResponse>,
+    #[serde(default)]
+    rooms: HashMap<String, RoomResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListResponse {
+    #[serde(default)]
+    count: u64,
+}
+
+/// Changes of a room since the last response, missing fields are unchanged
+#[derive(Debug, Default, Deserialize)]
+struct RoomResponse {
+    name: Option<String>,
+    avatar: Option<String>,
+    is_dm: Option<bool>,
+    heroes: Option<Vec<Hero>>,
+    /// The room is sent in full, forgetting what was known of it
+    #[serde(default)]
+    initial: bool,
+    #[serde(default)]
+    required_state: Vec<serde_json::Value>,
+    #[serde(default)]
+    timeline: Vec<serde_json::Value>,
+    invite_state: Option<Vec<serde_json::Value>>,
+    bump_stamp: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+struct Hero {
+    displayname: Option<String>,
+    avatar_url: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct SlidingRoom {
+    name: Option<String>,
+    avatar: Option<String>,
+    is_dm: bool,
+    heroes: Vec<Hero>,
+    is_space: bool,
+    is_public: bool,
+    is_invite: bool,
+    /// Rooms in the `m.space.child` state of a space
+    children: Vec<String>,
+    preview: Option<String>,
+    bump_stamp: u64,
+}
+
+impl SlidingRoom {
+    fn update(&mut self, update: RoomResponse) {
+        if update.initial {
+            *self = SlidingRoom::default();
+        }
+
+        if update.name.is_some() {
+            self.name = update.name;
+        }
+
+        if update.avatar.is_some() {
+            self.avatar = update.avatar;
+        }
+
+        if let Some(is_dm) = update.is_dm {
+            self.is_dm = is_dm;
+        }
+
+        if let Some(heroes) = update.heroes {
+            self.heroes = heroes;
+        }
+
+        if let Some(bump_stamp) = update.bump_stamp {
+            self.bump_stamp = bump_stamp;
+        }
+
+        if update.invite_state.is_some() {
+            self.is_invite = true;
+        }
+
+        // State changes also arrive in the timeline, after the required state
+        update
+            .required_state
+            .iter()
+            .chain(update.invite_state.iter().flatten())
+            .chain(
+                update
+                    .timeline
+                    .iter()
+                    .filter(|event| event["state_key"].is_string()),
+            )
+            .for_each(|event| self.apply_state(event));
+
+        if let Some(body) = update
+            .timeline
+            .iter()
+            .rev()
+            .find(|event| event["type"].as_str().eq(&Some("m.room.message")))
+            .and_then(|event| event["content"]["body"].as_str())
+        {
+            self.preview = Some(body.to_string());
+        }
+    }
+
+    fn apply_state(&mut self, event: &serde_json::Value) {
+        match event["type"].as_str() {
+            Some("m.room.create") => {
+                self.is_space = event["content"]["type"].as_str().eq(&Some("m.space"));
+            }
+            Some("m.room.join_rules") => {
+                self.is_public = event["content"]["join_rule"].as_str().eq(&Some("public"));
+            }
+            Some("m.space.child") => {
+                let Some(child) = event["state_key"].as_str() else {
+                    return;
+                };
+
+                self.children.retain(|id| !id.eq(child));
+
+                // Children are removed by emptying their `via`
+                if event["content"]["via"]
+                    .as_array()
+                    .is_some_and(|via| !via.is_empty())
+                {
+                    self.children.push(child.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn to_item(&self, room_id: &str) -> RoomItem {
+        let name = self.name.clone().unwrap_or_else(|| {
+            let heroes = self
+                .heroes
+                .iter()
+                .filter_map(|hero| hero.displayname.clone())
+                .collect::<Vec<_>>();
+
+            if heroes.is_empty() {
+                room_id.to_string()
+            } else {
+                heroes.join(", ")
+            }
+        });
+
+        let avatar = if self.is_dm {
+            self.avatar
+                .clone()
+                .or_else(|| self.heroes.first().and_then(|hero| hero.avatar_url.clone()))
+        } else {
+            self.avatar.clone()
+        };
+
+        RoomItem {
+            avatar_uri: avatar.and_then(|uri| {
+                mxc_to_thumbnail_uri(
+                    &OwnedMxcUri::from(uri.as_str()),
+                    ImageSize::default(),
+                    ImageMethod::CROP,
+                )
+            }),
+            id: room_id.to_string(),
+            name,
+            is_public: self.is_public,
+            is_direct: self.is_dm,
+        }
+    }
+}
+
+/// A sliding sync connection. Each response only carries what changed since
+/// `pos`, merged here into the rooms known so far.
+#[derive(Clone, Debug, Default)]
+pub struct SlidingSync {
+    pos: Option<String>,
+    counts: HashMap<String, u64>,
+    rooms: HashMap<String, SlidingRoom>,
+}
+
+impl SlidingSync {
+    pub fn pos(&self) -> Option<String> {
+        self.pos.clone()
+    }
+
+    /// Starts a new connection, the next response sends every room again
+    pub fn reset(&mut self) {
+        *self = SlidingSync::default();
+    }
+
+    /// Joined rooms on the server, the window may hold fewer
+    pub fn count(&self) -> u64 {
+        self.counts.get(ROOMS_LIST).copied().unwrap_or(0)
+    }
+
+    fn list_range_end(&self, list: &str) -> u64 {
+        self.counts
+            .get(list)
+            .map(|count| count.saturating_sub(1))
+            .unwrap_or(0)
+            .max(u64::from(MIN_LIST_RANGE_END))
+    }
+
+    /// Rooms sorted by activity up to `range_end`, spaces and invitations,
+    /// with `subscriptions` followed even when out of the window
+    pub fn request_body(&self, range_end: u32, subscriptions: &[String]) -> serde_json::Value {
+        let required_state = json!([["m.room.create", ""], ["m.room.join_rules", ""]]);
+
+        let room_subscriptions = subscriptions
+            .iter()
+            .map(|room_id| {
+                (
+                    room_id.clone(),
+                    json!({ "timeline_limit": 1, "required_state": required_state }),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>();
+
+        json!({
+            "lists": {
+                "rooms": {
+                    "ranges": [[0, range_end]],
+                    "timeline_limit": 1,
+                    "required_state": required_state,
+                    "filters": { "is_invite": false, "not_room_types": ["m.space"] },
+                },
+                "spaces": {
+                    "ranges": [[0, self.list_range_end(SPACES_LIST)]],
+                    "timeline_limit": 0,
+                    "required_state": [
+                        ["m.room.create", ""],
+                        ["m.room.join_rules", ""],
+                        ["m.space.child", "*"],
+                    ],
+                    "filters": { "is_invite": false, "room_types": ["m.space"] },
+                },
+                "invites": {
+                    "ranges": [[0, self.list_range_end(INVITES_LIST)]],
+                    "timeline_limit": 0,
+                    "required_state": [],
+                    "filters": { "is_invite": true },
+                },
+            },
+            "room_subscriptions": room_subscriptions,
+        })
+    }
+
+    pub fn apply(&mut self, response: SlidingSyncResponse) {
+        self.pos = Some(response.pos);
+
+        for (name, list) in response.lists.into_iter() {
+            self.counts.insert(name, list.count);
+        }
+
+        for (room_id, update) in response.rooms.into_iter() {
+            self.rooms.entry(room_id).or_default().update(update);
+        }
+    }
+
+    /// Rooms of the window sorted by recent activity like the server does,
+    /// and the spaces with the known rooms among their children
+    pub fn conversations(&self, range_end: u32) -> Conversations {
+        let mut joined = self
+            .rooms
+            .iter()
+            .filter(|(_, room)| !room.is_space && !room.is_invite)
+            .collect::<Vec<_>>();
+
+        joined.sort_by(|(a_id, a), (b_id, b)| b.bump_stamp.cmp(&a.bump_stamp).then(a_id.cmp(b_id)));
+
+        let spaces = self
+            .rooms
+            .iter()
+            .filter(|(_, room)| room.is_space && !room.is_invite)
+            .map(|(space_id, space)| {
+                let children = joined
+                    .iter()
+                    .filter(|(room_id, _)| space.children.contains(*room_id))
+                    .map(|(room_id, room)| room.to_item(room_id))
+                    .collect::<Vec<_>>();
+
+                (space.to_item(space_id), children)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let rooms = joined
+            .into_iter()
+            .take(range_end as usize + 1)
+            .map(|(room_id, room)| room.to_item(room_id))
+            .collect::<Vec<_>>();
+
+        Conversations { rooms, spaces }
+    }
+
+    /// Body of the latest message of each room
+    pub fn previews(&self) -> HashMap<String, String> {
+        self.rooms
+            .iter()
+            .filter_map(|(room_id, room)| Some((room_id.clone(), room.preview.clone()?)))
+            .collect()
+    }
+
+    pub fn invites(&self) -> Vec<String> {
+        self.rooms
+            .iter()
+            .filter(|(_, room)| room.is_invite)
+            .map(|(room_id, _)| room_id.clone())
+            .collect()
+    }
+}
+
+/// Sends the next request of the connection, long-polling once it has a `pos`
+pub async fn sliding_sync_once(
+    client: &Client,
+    pos: Option<String>,
+    body: serde_json::Value,
+) -> Result<SlidingSyncResponse, SlidingSyncError> {
+    let session = client.session().ok_or(SlidingSyncError::SessionNotFound)?;
+
+    let mut request_url = client
+        .homeserver()
+        .await
+        .join(SLIDING_SYNC_PATH)
+        .map_err(|_| SlidingSyncError::RequestFailed)?;
+
+    if let Some(pos) = pos {
+        request_url
+            .query_pairs_mut()
+            .append_pair("pos", &pos)
+            .append_pair("timeout", &SLIDING_SYNC_TIMEOUT.to_string());
+    }
+
+    let res = reqwest::Client::new()
+        .post(request_url)
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {}", session.access_token))
+        .body(body.to_string())
+        .send()
+        .await
+        .map_err(|_| SlidingSyncError::RequestFailed)?;
+
+    let status = res.status();
+    let body = res
+        .text()
+        .await
+        .map_err(|_| SlidingSyncError::InvalidResponse)?;
+
+    if !status.is_success() {
+        let errcode = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v["errcode"].as_str().map(String::from));
+
+        info!("sliding sync rejected {:?} {:?}", status, errcode);
+
+        return match (status.as_u16(), errcode.as_deref()) {
+            (_, Some("M_UNKNOWN_POS")) => Err(SlidingSyncError::UnknownPos),
+            (404, _) | (_, Some("M_UNRECOGNIZED")) => Err(SlidingSyncError::Unsupported),
+            _ => Err(SlidingSyncError::RequestFailed),
+        };
+    }
+
+    serde_json::from_str(&body).map_err(|_| SlidingSyncError::InvalidResponse)
+}
+
+/// Brings a room listed by sliding sync into the store, with a classic sync
+/// limited to it. The light classic sync only follows rooms already there.
+pub async fn sync_room_state(client: &Client, room_id: &str) -> Result<(), SlidingSyncError> {
+    let room_id = RoomId::parse(room_id).map_err(|_| SlidingSyncError::InvalidRoomId)?;
+    let rooms = [room_id];
+    let types = timeline_event_types();
+
+    let mut filter = limited_sync_filter_definition(&types, &rooms);
+    filter.room.timeline.limit = Some(uint!(1));
+
+    let sync_settings = SyncSettings::default()
+        .filter(sync_events::v3::Filter::FilterDefinition(filter))
+        .full_state(true);
+
+    client
+        .sync_once(sync_settings)
+        .await
+        .map_err(|_| SlidingSyncError::RequestFailed)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(value: serde_json::Value) -> SlidingSyncResponse {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn connection() -> SlidingSync {
+        let mut sliding = SlidingSync::default();
+
+        sliding.apply(response(json!({
+            "pos": "1",
+            "lists": { "rooms": { "count": 3 }, "spaces": { "count": 1 } },
+            "rooms": {
+                "!old:server": {
+                    "initial": true,
+                    "name": "Old",
+                    "bump_stamp": 1,
+                    "required_state": [
+                        { "type": "m.room.join_rules", "state_key": "", "content": { "join_rule": "public" } }
+                    ]
+                },
+                "!new:server": {
+                    "initial": true,
+                    "name": "New",
+                    "bump_stamp": 5,
+                    "timeline": [
+                        { "type": "m.room.message", "content": { "msgtype": "m.text", "body": "hello" } }
+                    ]
+                },
+                "!dm:server": {
+                    "initial": true,
+                    "is_dm": true,
+                    "heroes": [{ "displayname": "Alice", "avatar_url": "mxc://server/alice" }],
+                    "bump_stamp": 3
+                },
+                "!space:server": {
+                    "initial": true,
+                    "name": "Space",
+                    "required_state": [
+                        { "type": "m.room.create", "state_key": "", "content": { "type": "m.space" } },
+                        { "type": "m.space.child", "state_key": "!old:server", "content": { "via": ["server"] } }
+                    ]
+                }
+            }
+        })));
+
+        sliding
+    }
+
+    #[test]
+    fn conversations_are_sorted_by_activity_within_the_window() {
+        let sliding = connection();
+
+        let Conversations { rooms, spaces } = sliding.conversations(1);
+        let ids = rooms
+            .iter()
+            .map(|room| room.id.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, vec!["!new:server", "!dm:server"]);
+        assert_eq!(sliding.count(), 3);
+
+        let (space, children) = spaces.into_iter().next().unwrap();
+        assert_eq!(space.name, "Space");
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].id, "!old:server");
+        assert!(children[0].is_public);
+    }
+
+    #[test]
+    fn rooms_without_name_use_their_heroes() {
+        let Conversations { rooms, .. } = connection().conversations(10);
+        let dm = rooms.iter().find(|room| room.id.eq("!dm:server")).unwrap();
+
+        assert_eq!(dm.name, "Alice");
+        assert!(dm.is_direct);
+        assert!(dm.avatar_uri.is_some());
+    }
+
+    #[test]
+    fn updates_merge_into_the_known_rooms() {
+        let mut sliding = connection();
+
+        sliding.apply(response(json!({
+            "pos": "2",
+            "rooms": {
+                "!old:server": {
+                    "bump_stamp": 9,
+                    "timeline": [
+                        { "type": "m.room.message", "content": { "msgtype": "m.text", "body": "back" } }
+                    ]
+                },
+                "!space:server": {
+                    "timeline": [
+                        { "type": "m.space.child", "state_key": "!old:server", "content": {} }
+                    ]
+                }
+            }
+        })));
+
+        let Conversations { rooms, spaces } = sliding.conversations(10);
+
+        assert_eq!(sliding.pos(), Some(String::from("2")));
+        assert_eq!(rooms[0].id, "!old:server");
+        assert_eq!(rooms[0].name, "Old");
+        assert!(rooms[0].is_public);
+        assert_eq!(
+            sliding.previews().get("!old:server"),
+            Some(&String::from("back"))
+        );
+        assert_eq!(
+            sliding.previews().get("!new:server"),
+            Some(&String::from("hello"))
+        );
+        assert!(spaces.values().all(|children| children.is_empty()));
+    }
+
+    #[test]
+    fn initial_rooms_forget_previous_state() {
+        let mut sliding = connection();
+
+        sliding.apply(response(json!({
+            "pos": "2",
+            "rooms": { "!old:server": { "initial": true, "name": "Renamed", "bump_stamp": 1 } }
+        })));
+
+        let Conversations { rooms, .. } = sliding.conversations(10);
+        let old = rooms.iter().find(|room| room.id.eq("!old:server")).unwrap();
+
+        assert_eq!(old.name, "Renamed");
+        assert!(!old.is_public);
+    }
+
+    #[test]
+    fn invitations_stay_out_of_the_room_list() {
+        let mut sliding = connection();
+
+        sliding.apply(response(json!({
+            "pos": "2",
+            "lists": { "invites": { "count": 1 } },
+            "rooms": {
+                "!invite:server": {
+                    "initial": true,
+                    "bump_stamp": 20,
+                    "invite_state": [
+                        { "type": "m.room.name", "state_key": "", "content": { "name": "Party" } }
+                    ]
+                }
+            }
+        })));
+
+        let Conversations { rooms, .. } = sliding.conversations(10);
+
+        assert_eq!(sliding.invites(), vec![String::from("!invite:server")]);
+        assert!(!rooms.iter().any(|room| room.id.eq("!invite:server")));
+    }
+
+    #[test]
+    fn request_body_windows_the_lists() {
+        let mut sliding = connection();
+
+        sliding.apply(response(json!({
+            "pos": "2",
+            "lists": { "spaces": { "count": 45 } }
+        })));
+
+        let body = sliding.request_body(39, &[String::from("!active:server")]);
+
+        assert_eq!(body["lists"]["rooms"]["ranges"], json!([[0, 39]]));
+        assert_eq!(body["lists"]["spaces"]["ranges"], json!([[0, 44]]));
+        assert_eq!(body["lists"]["invites"]["ranges"], json!([[0, 19]]));
+        assert_eq!(
+            body["room_subscriptions"]["!active:server"]["timeline_limit"],
+            json!(1)
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use log::info;
+use matrix_sdk::{
+    ruma::{api::client::context::get_context, uint, EventId, RoomId},
+    Client,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::services::matrix::matrix::{
+    AudioContent, FileContent, ImageType, TimelineMessage, TimelineMessageType, TimelineRelation,
+};
+
+const CACHE_INDEX_KEY: &[u8] = b"fido.timeline_cache.index";
+const CACHE_ROOM_PREFIX: &str = "fido.timeline_cache.room.";
+const MAX_CACHED_ROOMS: usize = 30;
+const MAX_CACHED_MESSAGES: usize = 100;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CachedTimeline {
+    /// Pagination token to continue loading older messages
+    pub from: Option<String>,
+    pub messages: Vec<TimelineRelation>,
+}
+
+pub enum TimelineCacheError {
+    StoreFailed,
+    SerializationFailed,
+}
+
+fn room_key(room_id: &str) -> Vec<u8> {
+    format!("{CACHE_ROOM_PREFIX}{room_id}").into_bytes()
+}
+
+async fn get_index(client: &Client) -> Vec<String> {
+    client
+        .store()
+        .get_custom_value(CACHE_INDEX_KEY)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_slice(&value).ok())
+        .unwrap_or_default()
+}
+
+async fn set_index(client: &Client, index: &[String]) -> Result<(), TimelineCacheError> {
+    let value = serde_json::to_vec(index).map_err(|_| TimelineCacheError::SerializationFailed)?;
+
+    client
+        .store()
+        .set_custom_value(CACHE_INDEX_KEY, value)
+        .await
+        .map_err(|_| TimelineCacheError::StoreFailed)?;
+
+    Ok(())
+}
+
+pub async fn get_cached_timeline(client: &Client, room_id: &str) -> Option<CachedTimeline> {
+    let value = client
+        .store()
+        .get_custom_value(&room_key(room_id))
+        .await
+        .ok()
+        .flatten()?;
+
+    // Evicted rooms are overwritten with an empty value
+    if value.is_empty() {
+        return None;
+    }
+
+    serde_json::from_slice(&value).ok()
+}
+
+pub async fn cache_timeline(
+    client: &Client,
+    room_id: &str,
+    timeline: CachedTimeline,
+) -> Result<(), TimelineCacheError> {
+    let CachedTimeline { from, messages } = if timeline.messages.len() > MAX_CACHED_MESSAGES {
+        let mut trimmed = trim_timeline(timeline);
+
+        if let Some(oldest) = trimmed.messages.first() {
+            trimmed.from = token_before(client, room_id, relation_event_id(oldest)).await;
+        }
+
+        trimmed
+    } else {
+        timeline
+    };
+
+    let messages = messages.into_iter().map(strip_media).collect();
+
+    let value = serde_json::to_vec(&CachedTimeline { from, messages })
+        .map_err(|_| TimelineCacheError::SerializationFailed)?;
+
+    client
+        .store()
+        .set_custom_value(&room_key(room_id), value)
+        .await
+        .map_err(|_| TimelineCacheError::StoreFailed)?;
+
+    let mut index = get_index(client).await;
+    index.retain(|id| !id.eq(room_id));
+    index.insert(0, room_id.to_string());
+
+    if index.len() > MAX_CACHED_ROOMS {
+        for evicted in index.split_off(MAX_CACHED_ROOMS) {
+            if let Err(_) = client
+                .store()
+                .set_custom_value(&room_key(&evicted), vec![])
+                .await
+            {
+                info!("Failed to evict cached timeline {evicted}");
+            }
+        }
+    }
+
+    set_index(client, &index).await
+}
+
+/// Keeps the newest messages, the pagination token no longer matches the
+/// oldest cached message once some of them are dropped.
+fn trim_timeline(timeline: CachedTimeline) -> CachedTimeline {
+    let CachedTimeline { messages, .. } = timeline;
+
+    CachedTimeline {
+        from: None,
+        messages: messages[messages.len().saturating_sub(MAX_CACHED_MESSAGES)..].to_vec(),
+    }
+}
+
+// The event context starts right before the event, so paginating from it
+// continues with the messages that were trimmed out of the cache
+async fn token_before(client: &Client, room_id: &str, event_id: &str) -> Option<String> {
+    let room_id = RoomId::parse(room_id).ok()?;
+    let event_id = EventId::parse(event_id).ok()?;
+
+    let mut request = get_context::v3::Request::new(&room_id, &event_id);
+    request.limit = uint!(0);
+
+    let response = client.send(request, None).await.ok()?;
+
+    response.start
+}
+
+pub async fn evict_timeline_cache(client: &Client) -> Result<(), TimelineCacheError> {
+    for room_id in get_index(client).await {
+        client
+            .store()
+            .set_custom_value(&room_key(&room_id), vec![])
+            .await
+            .map_err(|_| TimelineCacheError::StoreFailed)?;
+    }
+
+    set_index(client, &[]).await
+}
+
+/// Joins the latest page fetched from the server with the cached history,
+/// the server copy wins for every event both of them contain.
+pub fn reconcile(cached: CachedTimeline, fetched: CachedTimeline) -> CachedTimeline {
+    // Without a token older messages could never be loaded past the cache,
+    // paginate from the fetched page instead
+    if cached.from.is_none() && fetched.from.is_some() {
+        return fetched;
+    }
+
+    let overlap = fetched.messages.first().and_then(|first| {
+        cached
+            .messages
+            .iter()
+            .position(|m| relation_event_id(m).eq(relation_event_id(first)))
+    });
+
+    match overlap {
+        Some(position) => {
+            let mut messages = cached.messages[..position].to_vec();
+            messages.extend(fetched.messages);
+
+            CachedTimeline {
+                from: cached.from,
+                messages,
+            }
+        }
+        // There is a gap between the cache and the latest page, drop the cache
+        None => fetched,
+    }
+}
+
+pub(crate) fn relation_event_id(relation: &TimelineRelation) -> &str {
+    match relation {
+        TimelineRelation::None(message) => &message.event_id,
+        TimelineRelation::Reply(reply) => &reply.event.event_id,
+        TimelineRelation::CustomThread(thread) => &thread.event_id,
+        TimelineRelation::Thread(thread) => &thread.event_id,
+    }
+}
+
+// Decrypted media is kept in memory only, the cache holds the text around it
+pub(crate) fn strip_media(relation: TimelineRelation) -> TimelineRelation {
+    match relation {
+        TimelineRelation::None(message) => TimelineRelation::None(strip_message_media(message)),
+        TimelineRelation::Reply(mut reply) => {
+            reply.event = strip_message_media(reply.event);
+            reply.reply = reply.reply.map(strip_message_media);
+
+            TimelineRelation::Reply(reply)
+        }
+        TimelineRelation::CustomThread(mut thread) => {
+            thread.thread = thread.thread.into_iter().map(strip_message_media).collect();
+
+            TimelineRelation::CustomThread(thread)
+        }
+        TimelineRelation::Thread(mut thread) => {
+            thread.thread = thread.thread.into_iter().map(strip_message_media).collect();
+
+            TimelineRelation::Thread(thread)
+        }
+    }
+}
+
+fn strip_message_media(mut message: TimelineMessage) -> TimelineMessage {
+    let strip = |content: FileContent| FileContent {
+        source: match content.source {
+            Some(ImageType::Media(_)) => None,
+            source => source,
+        },
+        ..content
+    };
+
+    message.body = match message.body {
+        TimelineMessageType::Image(content) => TimelineMessageType::Image(strip(content)),
+        TimelineMessageType::File(content) => TimelineMessageType::File(strip(content)),
+        TimelineMessageType::Video(content) => TimelineMessageType::Video(strip(content)),
+        TimelineMessageType::Audio(audio) => TimelineMessageType::Audio(AudioContent {
+            file: strip(audio.file),
+            ..audio
+        }),
+        body => body,
+    };
+
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::matrix::matrix::{EventOrigin, RoomMember};
+
+    fn message(event_id: &str) -> TimelineRelation {
+        TimelineRelation::None(TimelineMessage {
+            event_id: event_id.to_string(),
+            sender: RoomMember {
+                id: String::from("@alice:example.org"),
+                name: String::from("Alice"),
+                avatar_uri: None,
+            },
+            body: TimelineMessageType::Text(event_id.to_string()),
+            origin: EventOrigin::OTHER,
+            time: String::from("10:00"),
+        })
+    }
+
+    fn timeline(from: Option<&str>, ids: std::ops::Range<usize>) -> CachedTimeline {
+        CachedTimeline {
+            from: from.map(String::from),
+            messages: ids.map(|i| message(&format!("$event{i}"))).collect(),
+        }
+    }
+
+    fn event_ids(timeline: &CachedTimeline) -> Vec<&str> {
+        timeline.messages.iter().map(relation_event_id).collect()
+    }
+
+    #[test]
+    fn reconcile_appends_the_fetched_page() {
+        let result = reconcile(
+            timeline(Some("t_cache"), 0..10),
+            timeline(Some("t_page"), 8..12),
+        );
+
+        assert_eq!(result.from.as_deref(), Some("t_cache"));
+        assert_eq!(event_ids(&result), event_ids(&timeline(None, 0..12)));
+    }
+
+    #[test]
+    fn reconcile_drops_the_cache_on_a_gap() {
+        let result = reconcile(
+            timeline(Some("t_cache"), 0..10),
+            timeline(Some("t_page"), 20..25),
+        );
+
+        assert_eq!(result.from.as_deref(), Some("t_page"));
+        assert_eq!(event_ids(&result), event_ids(&timeline(None, 20..25)));
+    }
+
+    #[test]
+    fn trim_keeps_the_newest_messages() {
+        let trimmed = trim_timeline(timeline(Some("t_cache"), 0..150));
+
+        assert_eq!(trimmed.messages.len(), MAX_CACHED_MESSAGES);
+        assert_eq!(relation_event_id(&trimmed.messages[0]), "$event50");
+        assert!(trimmed.from.is_none());
+    }
+
+    #[test]
+    fn trimmed_cache_without_token_paginates_from_the_server() {
+        let trimmed = trim_timeline(timeline(Some("t_cache"), 0..150));
+        let result = reconcile(trimmed, timeline(Some("t_page"), 140..160));
+
+        // Loading more continues right before the fetched page
+        assert_eq!(result.from.as_deref(), Some("t_page"));
+        assert_eq!(event_ids(&result), event_ids(&timeline(None, 140..160)));
+    }
+}
@@ -0,0 +1,405 @@
+use std::{cell::RefCell, future::Future, rc::Rc};
+
+use futures::channel::oneshot;
+use gloo::events::EventListener;
+use mime::Mime;
+use wasm_bindgen::{prelude::Closure, Clamped, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    CanvasRenderingContext2d, EventTarget, HtmlAudioElement, HtmlCanvasElement, HtmlImageElement,
+    HtmlVideoElement, ImageData, Url,
+};
+
+use super::vec_to_url::vec_to_url;
+
+const THUMBNAIL_MAX_WIDTH: u32 = 800;
+const THUMBNAIL_MAX_HEIGHT: u32 = 600;
+const THUMBNAIL_TYPE: &str = "image/jpeg";
+
+const COMPRESSED_MAX_SIZE: u32 = 2048;
+const COMPRESSED_QUALITY: f64 = 0.82;
+
+const BLURHASH_SIZE: u32 = 32;
+const BLURHASH_COMPONENTS_X: u32 = 4;
+const BLURHASH_COMPONENTS_Y: u32 = 3;
+
+pub struct MediaThumbnail {
+    pub data: Vec<u8>,
+    pub content_type: Mime,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub struct CompressedImage {
+    pub data: Vec<u8>,
+    pub content_type: Mime,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub struct MediaMetadata {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Milliseconds, only known for videos and audio
+    pub duration: Option<u64>,
+    pub blurhash: Option<String>,
+    pub thumbnail: Option<MediaThumbnail>,
+}
+
+/// Reads dimensions from an image or video and renders a thumbnail and
+/// blurhash of it through a canvas. Audio only gets its duration
+pub async fn read_media_metadata(
+    data: &[u8],
+    content_type: &Mime,
+) -> Result<MediaMetadata, JsValue> {
+    let url = vec_to_url(data.to_vec())?;
+
+    let metadata = match content_type.type_() {
+        mime::IMAGE => read_image(&url).await,
+        mime::VIDEO => read_video(&url).await,
+        mime::AUDIO => read_audio(&url).await,
+        _ => Err(JsValue::from_str("Unsupported media type")),
+    };
+
+    let _ = Url::revoke_object_url(&url);
+
+    metadata
+}
+
+/// Animated and vector images lose what makes them special once drawn into
+/// a canvas, everything else can be re-encoded
+pub fn is_compressible(content_type: &Mime) -> bool {
+    content_type.type_() == mime::IMAGE
+        && content_type.subtype() != mime::GIF
+        && content_type.subtype() != mime::SVG
+}
+
+/// Downscales an image to `COMPRESSED_MAX_SIZE` and re-encodes it as WebP,
+/// or JPEG where the browser can't encode WebP. Drawing into a canvas keeps
+/// only the pixels, so EXIF data such as the location is dropped
+pub async fn compress_image(data: &[u8]) -> Result<CompressedImage, JsValue> {
+    let url = vec_to_url(data.to_vec())?;
+    let image = HtmlImageElement::new()?;
+    let loaded = wait_for(&image, "load");
+
+    image.set_src(&url);
+    let loaded = loaded.await;
+    let _ = Url::revoke_object_url(&url);
+    loaded?;
+
+    let (width, height) = fit_within(
+        image.natural_width(),
+        image.natural_height(),
+        COMPRESSED_MAX_SIZE,
+        COMPRESSED_MAX_SIZE,
+    );
+
+    let (canvas, context) = create_canvas(width, height)?;
+    context.draw_image_with_html_image_element_and_dw_and_dh(
+        &image,
+        0.0,
+        0.0,
+        width as f64,
+        height as f64,
+    )?;
+
+    let (data, content_type) =
+        match canvas_to_bytes(&canvas, "image/webp", COMPRESSED_QUALITY).await? {
+            (data, content_type) if content_type == "image/webp" => (data, content_type),
+            // Browsers fall back to PNG for types they can't encode
+            _ => canvas_to_bytes(&canvas, THUMBNAIL_TYPE, COMPRESSED_QUALITY).await?,
+        };
+
+    Ok(CompressedImage {
+        data,
+        content_type: content_type.parse().unwrap_or(mime::IMAGE_JPEG),
+        width,
+        height,
+    })
+}
+
+/// Scales dimensions down, keeping the aspect ratio, until they fit in the
+/// given box. Smaller dimensions are kept as they are
+pub fn fit_within(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    let scale = f64::min(
+        1.0,
+        f64::min(
+            max_width as f64 / width.max(1) as f64,
+            max_height as f64 / height.max(1) as f64,
+        ),
+    );
+
+    (
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    )
+}
+
+/// Renders a blurhash into a data url, usable as a placeholder background
+pub fn blurhash_to_url(blurhash: &str, width: u64, height: u64) -> Option<String> {
+    let (width, height) = placeholder_size(width, height);
+    let pixels = blurhash::decode(blurhash, width, height, 1.0).ok()?;
+
+    let image =
+        ImageData::new_with_u8_clamped_array_and_sh(Clamped(&pixels), width, height).ok()?;
+    let (canvas, context) = create_canvas(width, height).ok()?;
+
+    context.put_image_data(&image, 0.0, 0.0).ok()?;
+    canvas.to_data_url().ok()
+}
+
+async fn read_image(url: &str) -> Result<MediaMetadata, JsValue> {
+    let image = HtmlImageElement::new()?;
+    let loaded = wait_for(&image, "load");
+
+    image.set_src(url);
+    loaded.await?;
+
+    let (width, height) = (image.natural_width(), image.natural_height());
+
+    let blurhash = render_blurhash(|context, w, h| {
+        context.draw_image_with_html_image_element_and_dw_and_dh(&image, 0.0, 0.0, w, h)
+    })
+    .ok();
+
+    // Small images are their own thumbnail
+    let thumbnail = if width > THUMBNAIL_MAX_WIDTH || height > THUMBNAIL_MAX_HEIGHT {
+        render_thumbnail(width, height, |context, w, h| {
+            context.draw_image_with_html_image_element_and_dw_and_dh(&image, 0.0, 0.0, w, h)
+        })
+        .await
+        .ok()
+    } else {
+        None
+    };
+
+    Ok(MediaMetadata {
+        width: Some(width),
+        height: Some(height),
+        duration: None,
+        blurhash,
+        thumbnail,
+    })
+}
+
+async fn read_video(url: &str) -> Result<MediaMetadata, JsValue> {
+    let video = gloo::utils::document()
+        .create_element("video")?
+        .dyn_into::<HtmlVideoElement>()?;
+    let loaded = wait_for(&video, "loadeddata");
+
+    video.set_muted(true);
+    video.set_preload("auto");
+    video.set_src(url);
+    loaded.await?;
+
+    let (width, height) = (video.video_width(), video.video_height());
+    let duration = video.duration();
+
+    let blurhash = render_blurhash(|context, w, h| {
+        context.draw_image_with_html_video_element_and_dw_and_dh(&video, 0.0, 0.0, w, h)
+    })
+    .ok();
+
+    let thumbnail = render_thumbnail(width, height, |context, w, h| {
+        context.draw_image_with_html_video_element_and_dw_and_dh(&video, 0.0, 0.0, w, h)
+    })
+    .await
+    .ok();
+
+    Ok(MediaMetadata {
+        width: Some(width),
+        height: Some(height),
+        duration: duration_to_millis(duration),
+        blurhash,
+        thumbnail,
+    })
+}
+
+async fn read_audio(url: &str) -> Result<MediaMetadata, JsValue> {
+    let audio = HtmlAudioElement::new()?;
+    let loaded = wait_for(&audio, "loadedmetadata");
+
+    audio.set_preload("metadata");
+    audio.set_src(url);
+    loaded.await?;
+
+    Ok(MediaMetadata {
+        width: None,
+        height: None,
+        duration: duration_to_millis(audio.duration()),
+        blurhash: None,
+        thumbnail: None,
+    })
+}
+
+/// Media elements report NaN or infinity while the duration is unknown
+fn duration_to_millis(seconds: f64) -> Option<u64> {
+    seconds.is_finite().then(|| (seconds * 1000.0) as u64)
+}
+
+fn render_blurhash(
+    draw: impl Fn(&CanvasRenderingContext2d, f64, f64) -> Result<(), JsValue>,
+) -> Result<String, JsValue> {
+    let (_, context) = create_canvas(BLURHASH_SIZE, BLURHASH_SIZE)?;
+    draw(&context, BLURHASH_SIZE as f64, BLURHASH_SIZE as f64)?;
+
+    let pixels = context
+        .get_image_data(0.0, 0.0, BLURHASH_SIZE as f64, BLURHASH_SIZE as f64)?
+        .data();
+
+    blurhash::encode(
+        BLURHASH_COMPONENTS_X,
+        BLURHASH_COMPONENTS_Y,
+        BLURHASH_SIZE,
+        BLURHASH_SIZE,
+        &pixels.0,
+    )
+    .map_err(|_| JsValue::from_str("Unable to encode blurhash"))
+}
+
+async fn render_thumbnail(
+    width: u32,
+    height: u32,
+    draw: impl Fn(&CanvasRenderingContext2d, f64, f64) -> Result<(), JsValue>,
+) -> Result<MediaThumbnail, JsValue> {
+    let (width, height) = fit_within(width, height, THUMBNAIL_MAX_WIDTH, THUMBNAIL_MAX_HEIGHT);
+
+    let (canvas, context) = create_canvas(width, height)?;
+    draw(&context, width as f64, height as f64)?;
+
+    let (data, _) = canvas_to_bytes(&canvas, THUMBNAIL_TYPE, 0.92).await?;
+
+    Ok(MediaThumbnail {
+        data,
+        content_type: mime::IMAGE_JPEG,
+        width,
+        height,
+    })
+}
+
+fn create_canvas(
+    width: u32,
+    height: u32,
+) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), JsValue> {
+    let canvas = gloo::utils::document()
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+
+    canvas.set_width(width);
+    canvas.set_height(height);
+
+    let context = canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("Canvas context not available"))?
+        .dyn_into::<CanvasRenderingContext2d>()?;
+
+    Ok((canvas, context))
+}
+
+/// Encodes the canvas, returning the bytes along with the type the browser
+/// actually used
+async fn canvas_to_bytes(
+    canvas: &HtmlCanvasElement,
+    content_type: &str,
+    quality: f64,
+) -> Result<(Vec<u8>, String), JsValue> {
+    let (tx, rx) = oneshot::channel::<Option<web_sys::Blob>>();
+    let callback = Closure::once(move |blob: JsValue| {
+        let _ = tx.send(blob.dyn_into::<web_sys::Blob>().ok());
+    });
+
+    canvas.to_blob_with_type_and_encoder_options(
+        callback.as_ref().unchecked_ref(),
+        content_type,
+        &JsValue::from_f64(quality),
+    )?;
+
+    let blob = rx
+        .await
+        .ok()
+        .flatten()
+        .ok_or_else(|| JsValue::from_str("Unable to encode canvas"))?;
+    let buffer = JsFuture::from(blob.array_buffer()).await?;
+
+    Ok((js_sys::Uint8Array::new(&buffer).to_vec(), blob.type_()))
+}
+
+/// Resolves once `event` fires on the target, or fails on its `error` event.
+/// Listeners are attached right away so the event can't be missed
+fn wait_for(
+    target: &EventTarget,
+    event: &'static str,
+) -> impl Future<Output = Result<(), JsValue>> {
+    let (tx, rx) = oneshot::channel::<bool>();
+    let tx = Rc::new(RefCell::new(Some(tx)));
+
+    let loaded = EventListener::once(target, event, {
+        let tx = tx.clone();
+        move |_| {
+            if let Some(tx) = tx.borrow_mut().take() {
+                let _ = tx.send(true);
+            }
+        }
+    });
+    let failed = EventListener::once(target, "error", move |_| {
+        if let Some(tx) = tx.borrow_mut().take() {
+            let _ = tx.send(false);
+        }
+    });
+
+    async move {
+        let result = rx.await;
+        drop((loaded, failed));
+
+        match result {
+            Ok(true) => Ok(()),
+            _ => Err(JsValue::from_str("Unable to load media")),
+        }
+    }
+}
+
+/// Blurhash decoding is expensive, a few pixels stretched over the media
+/// look the same
+fn placeholder_size(width: u64, height: u64) -> (u32, u32) {
+    if width >= height {
+        let height = (BLURHASH_SIZE as u64 * height / width.max(1)).max(1);
+        (BLURHASH_SIZE, height as u32)
+    } else {
+        let width = (BLURHASH_SIZE as u64 * width / height.max(1)).max(1);
+        (width as u32, BLURHASH_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_within_scales_landscape_by_width() {
+        assert_eq!(fit_within(1600, 900, 800, 600), (800, 450));
+    }
+
+    #[test]
+    fn fit_within_scales_portrait_by_height() {
+        assert_eq!(fit_within(900, 1600, 800, 600), (338, 600));
+    }
+
+    #[test]
+    fn fit_within_keeps_small_images() {
+        assert_eq!(fit_within(320, 240, 800, 600), (320, 240));
+    }
+
+    #[test]
+    fn fit_within_keeps_images_at_the_boundary() {
+        assert_eq!(fit_within(800, 600, 800, 600), (800, 600));
+        assert_eq!(fit_within(1600, 1200, 800, 600), (800, 600));
+    }
+
+    #[test]
+    fn fit_within_never_returns_zero() {
+        assert_eq!(fit_within(0, 0, 800, 600), (1, 1));
+        assert_eq!(fit_within(0, 1200, 800, 600), (1, 600));
+        assert_eq!(fit_within(10000, 1, 800, 600), (800, 1));
+    }
+}
@@ -0,0 +1,18 @@
+use gloo::storage::{errors::StorageError, LocalStorage};
+
+/// Link previews in encrypted rooms, fetching them tells the homeserver
+/// which links were shared
+pub const ENCRYPTED_URL_PREVIEWS: &str = "url_previews_encrypted";
+/// Map tiles loaded without a click, OpenStreetMap learns the shared locations
+pub const MAP_TILES_AUTOLOAD: &str = "map_tiles_autoload";
+/// Room list from sliding sync, read once when the app loads
+pub const SLIDING_SYNC: &str = "sliding_sync";
+
+/// User preferences kept in the browser, off until turned on
+pub fn get_bool(key: &str) -> bool {
+    <LocalStorage as gloo::storage::Storage>::get(key).unwrap_or(false)
+}
+
+pub fn set_bool(key: &str, value: bool) -> Result<(), StorageError> {
+    <LocalStorage as gloo::storage::Storage>::set(key, value)
+}
@@ -0,0 +1,101 @@
+use std::{cell::RefCell, future::Future, rc::Rc};
+
+use futures::channel::oneshot;
+use gloo::events::EventListener;
+use wasm_bindgen::JsCast;
+use web_sys::{ProgressEvent, XmlHttpRequest};
+
+#[derive(Clone, Debug)]
+pub enum UploadError {
+    Cancelled,
+    RequestFailed,
+}
+
+/// Handle of an upload in flight, cancelling it aborts the request
+#[derive(Clone, Debug)]
+pub struct Upload {
+    request: XmlHttpRequest,
+}
+
+impl Upload {
+    pub fn cancel(&self) {
+        let _ = self.request.abort();
+    }
+}
+
+/// Posts `data` through an XMLHttpRequest, the only browser API reporting
+/// upload progress. Progress goes from 0 to 1, the returned future resolves
+/// to the response body
+pub fn post_with_progress(
+    url: &str,
+    headers: &[(&str, &str)],
+    data: &[u8],
+    on_progress: impl Fn(f64) + 'static,
+) -> Result<(Upload, impl Future<Output = Result<String, UploadError>>), UploadError> {
+    let request = XmlHttpRequest::new().map_err(|_| UploadError::RequestFailed)?;
+
+    request
+        .open("POST", url)
+        .map_err(|_| UploadError::RequestFailed)?;
+
+    for (name, value) in headers {
+        request
+            .set_request_header(name, value)
+            .map_err(|_| UploadError::RequestFailed)?;
+    }
+
+    let upload = request.upload().map_err(|_| UploadError::RequestFailed)?;
+    let progress = EventListener::new(&upload, "progress", move |event| {
+        if let Some(event) = event.dyn_ref::<ProgressEvent>() {
+            if event.length_computable() && event.total() > 0.0 {
+                on_progress(event.loaded() / event.total());
+            }
+        }
+    });
+
+    let (tx, rx) = oneshot::channel::<Result<(), UploadError>>();
+    let tx = Rc::new(RefCell::new(Some(tx)));
+
+    let settle = move |result: Result<(), UploadError>| {
+        let tx = tx.clone();
+        move |_: &web_sys::Event| {
+            if let Some(tx) = tx.borrow_mut().take() {
+                let _ = tx.send(result.clone());
+            }
+        }
+    };
+
+    let listeners = [
+        EventListener::once(&request, "load", settle(Ok(()))),
+        EventListener::once(&request, "error", settle(Err(UploadError::RequestFailed))),
+        EventListener::once(&request, "abort", settle(Err(UploadError::Cancelled))),
+    ];
+
+    let body = js_sys::Uint8Array::from(data);
+    request
+        .send_with_opt_buffer_source(Some(&body))
+        .map_err(|_| UploadError::RequestFailed)?;
+
+    let handle = Upload {
+        request: request.clone(),
+    };
+
+    let response = async move {
+        let result = rx.await.unwrap_or(Err(UploadError::RequestFailed));
+        drop((progress, listeners));
+        result?;
+
+        let status = request.status().unwrap_or_default();
+        if !(200..300).contains(&status) {
+            return Err(UploadError::RequestFailed);
+        }
+
+        request
+            .response_text()
+            .ok()
+            .flatten()
+            .ok_or(UploadError::RequestFailed)
+    };
+
+    Ok((handle, response))
+}
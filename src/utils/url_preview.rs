@@ -0,0 +1,64 @@
+/// Finds the http(s) links of a plain or html body, in order and without
+/// repeating them
+pub fn find_urls(body: &str) -> Vec<String> {
+    let mut urls: Vec<String> = vec![];
+    let mut rest = body;
+
+    while let Some(start) = ["http://", "https://"]
+        .iter()
+        .filter_map(|scheme| rest.find(scheme))
+        .min()
+    {
+        let candidate = &rest[start..];
+        let end = candidate
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>'))
+            .unwrap_or(candidate.len());
+
+        // Punctuation closing a sentence isn't part of the link
+        let url = candidate[..end]
+            .trim_end_matches(|c| matches!(c, '.' | ',' | ';' | ':' | '!' | '?' | ')'));
+
+        if url.len() > "https://".len() && !urls.iter().any(|u| u == url) {
+            urls.push(url.to_string());
+        }
+
+        rest = &candidate[end..];
+    }
+
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_links_in_plain_text_in_order() {
+        assert_eq!(
+            find_urls("see https://example.org and http://matrix.org/docs"),
+            vec!["https://example.org", "http://matrix.org/docs"]
+        );
+    }
+
+    #[test]
+    fn finds_links_in_html_once() {
+        assert_eq!(
+            find_urls(r#"<a href="https://example.org/a">https://example.org/a</a>"#),
+            vec!["https://example.org/a"]
+        );
+    }
+
+    #[test]
+    fn drops_closing_punctuation() {
+        assert_eq!(
+            find_urls("(read https://example.org/page?q=1). Or https://matrix.org!"),
+            vec!["https://example.org/page?q=1", "https://matrix.org"]
+        );
+    }
+
+    #[test]
+    fn ignores_text_without_links() {
+        assert!(find_urls("no links here, just https:// and http").is_empty());
+        assert!(find_urls("").is_empty());
+    }
+}